@@ -0,0 +1,21 @@
+from babelone_cli._babelone_core import (
+    BuildSystem,
+    Entrypoints,
+    PackageLayout,
+    Project,
+    PyProject,
+    Requirements,
+    Setup,
+    translate_str,
+)
+
+__all__ = [
+    "BuildSystem",
+    "Entrypoints",
+    "PackageLayout",
+    "Project",
+    "PyProject",
+    "Requirements",
+    "Setup",
+    "translate_str",
+]
@@ -0,0 +1,148 @@
+"""Type stubs of the Rust core, built with PyO3.
+
+Nested objects, e.g., `PyProject.project`, are returned as copies; assign
+them back for any changes to take effect.
+"""
+
+from typing import Any, Dict, List, Optional, Tuple, Union
+
+Readme = Union[str, Dict[str, str]]
+# {"attr": ..., "path": ...}, {"file": ...} or {"path": ..., "pattern": ...}
+DynamicVersion = Dict[str, str]
+
+def translate(
+    source: str,
+    destination: str,
+    environment: Optional[str] = None,
+    import_depth: Optional[int] = None,
+    force: bool = False,
+    backup: bool = False,
+) -> None: ...
+def translate_str(source_text: str, source_format: str, target_format: str) -> str: ...
+def translate_file(
+    source: str,
+    target_format: str,
+    source_format: Optional[str] = None,
+    environment: Optional[str] = None,
+    import_depth: Optional[int] = None,
+) -> str: ...
+def check(
+    source: str,
+    destination: str,
+    environment: Optional[str] = None,
+    import_depth: Optional[int] = None,
+) -> str: ...
+def sync_file(
+    source: str,
+    destination: str,
+    environment: Optional[str] = None,
+    import_depth: Optional[int] = None,
+) -> str: ...
+def diff_specs(
+    old: str, new: str, json: bool = False, import_depth: Optional[int] = None
+) -> Tuple[str, bool]: ...
+def create_str(target_format: str, root: str) -> str: ...
+def create(destination: str, force: bool = False, backup: bool = False) -> None: ...
+def write_file(
+    path: str, contents: str, force: bool = False, backup: bool = False
+) -> None: ...
+def unified_diff(
+    original: str, modified: str, original_name: str, modified_name: str
+) -> str: ...
+def tree(environment: str) -> str: ...
+def check_imports(
+    source: str,
+    package: Optional[str] = None,
+    mappings: List[Tuple[str, str]] = ...,
+) -> str: ...
+
+class Requirements:
+    requires: List[str]
+    hashes: Dict[str, List[str]]
+    via: Dict[str, List[str]]
+    def __init__(self) -> None: ...
+    @staticmethod
+    def from_file(path: str) -> "Requirements": ...
+    @staticmethod
+    def from_str(contents: str) -> "Requirements": ...
+    def to_setup(self) -> "Setup": ...
+    def to_pyproject(self) -> "PyProject": ...
+    def to_str(self) -> str: ...
+    def to_file(self, path: str, force: bool = False, backup: bool = False) -> None: ...
+    def to_dict(self) -> dict: ...
+
+class Entrypoints:
+    console_scripts: Optional[List[str]]
+    gui_scripts: Optional[List[str]]
+    def __init__(self) -> None: ...
+    def to_dict(self) -> dict: ...
+
+class PackageLayout:
+    package_dir: Optional[str]
+    packages: List[str]
+    namespaces: bool
+    py_modules: List[str]
+    package_data: Dict[str, List[str]]
+    def __init__(self) -> None: ...
+    def to_dict(self) -> dict: ...
+
+class Setup:
+    package_name: Optional[str]
+    version: Optional[str]
+    description: Optional[str]
+    python_requires: Optional[str]
+    project_urls: Optional[Dict[str, str]]
+    extra_requires: Optional[Dict[str, List[str]]]
+    install_requires: Optional[List[str]]
+    setup_requires: Optional[List[str]]
+    entry_points: Optional[Entrypoints]
+    layout: Optional[PackageLayout]
+    dynamic_version: Optional[DynamicVersion]
+    readme: Optional[Readme]
+    requirements_file: Optional[str]
+    def __init__(self) -> None: ...
+    @staticmethod
+    def from_file(path: str, import_depth: int = 3) -> "Setup": ...
+    @staticmethod
+    def from_str(contents: str) -> "Setup": ...
+    def to_requirements(self) -> Requirements: ...
+    def to_pyproject(self) -> "PyProject": ...
+    def to_str(self) -> str: ...
+    def to_file(self, path: str, force: bool = False, backup: bool = False) -> None: ...
+    def to_dict(self) -> dict: ...
+
+class BuildSystem:
+    build_backend: Optional[str]
+    requires: Optional[List[str]]
+    def __init__(self) -> None: ...
+    def to_dict(self) -> dict: ...
+
+class Project:
+    name: Optional[str]
+    version: Optional[str]
+    dynamic: Optional[List[str]]
+    description: Optional[str]
+    readme: Optional[Readme]
+    requires_python: Optional[str]
+    dependencies: Optional[List[str]]
+    optional_dependencies: Optional[Dict[str, List[str]]]
+    project_scripts: Optional[Dict[str, str]]
+    project_gui_scripts: Optional[Dict[str, str]]
+    urls: Optional[Dict[str, str]]
+    def __init__(self) -> None: ...
+    def to_dict(self) -> dict: ...
+
+class PyProject:
+    build_system: Optional[BuildSystem]
+    project: Optional[Project]
+    tool: Optional[Dict[str, Any]]
+    def __init__(self) -> None: ...
+    @staticmethod
+    def from_file(path: str) -> "PyProject": ...
+    @staticmethod
+    def from_str(contents: str) -> "PyProject": ...
+    def to_requirements(self) -> Requirements: ...
+    def to_setup(self) -> Setup: ...
+    def to_str(self) -> str: ...
+    def to_file(self, path: str, force: bool = False, backup: bool = False) -> None: ...
+    def to_dict(self) -> dict: ...
@@ -1,3 +1,8 @@
+import os
+import sys
+import warnings
+from typing import Optional, Tuple
+
 from babelone_cli import _babelone_core
 import rich_click as click
 
@@ -13,27 +18,323 @@ def cli():
     """babelone /ˌbæbɪˈloʊn/
 
     Scaffold or translate between Python package build specification files,
-    including requirements.txt, setup.py, and pyproject.toml.
+    including requirements.txt, setup.py, pyproject.toml, and environment.yml.
+
+    """
+    warnings.showwarning = _show_warning
+
+
+def _show_warning(message, category, filename, lineno, file=None, line=None):
+    """Print warnings of the core, e.g., about untranslatable entries, as the
+    standalone CLI does.
+
+    """
+    click.echo(f"warning: {message}", err=True)
+
+
+def _format_of(argument: str, path: str, flag: str, format: Optional[str]) -> str:
+    """Return the format of the ARGUMENT at PATH, as given by FLAG, else its
+    file name. There is no file name to go by for stdin and stdout.
 
     """
+    if format:
+        return format
+    if path == "-":
+        raise click.UsageError(f"{flag} must be given when {argument} is -.")
+    return os.path.basename(path)
+
+
+def _write_options(command):
+    """Add the options controlling how OUTPUT is written to COMMAND."""
+    command = click.option(
+        "--diff",
+        is_flag=True,
+        help="Show a unified diff of OUTPUT against what would be written to it, "
+        "instead of writing it. Exits with 1 if OUTPUT would change.",
+    )(command)
+    command = click.option(
+        "--backup",
+        is_flag=True,
+        help="Keep a copy of OUTPUT as OUTPUT.bak when overwriting it with --force.",
+    )(command)
+    command = click.option(
+        "--force", is_flag=True, help="Overwrite OUTPUT if it already exists."
+    )(command)
+    return click.option(
+        "--dry-run",
+        is_flag=True,
+        help="Print what would be written to OUTPUT to stdout instead.",
+    )(command)
+
+
+def _write_output(
+    output: str,
+    contents: str,
+    dry_run: bool,
+    force: bool,
+    backup: bool,
+    diff: bool,
+):
+    if diff:
+        if output == "-":
+            raise click.UsageError("--diff cannot be given when OUTPUT is -.")
+        if dry_run or force or backup:
+            raise click.UsageError(
+                "--diff cannot be given with --dry-run, --force or --backup."
+            )
+        original, original_name = "", "/dev/null"
+        if os.path.exists(output):
+            with open(output, encoding="utf-8") as f:
+                original, original_name = f.read(), f"a/{output}"
+        changes = _babelone_core.unified_diff(
+            original, contents, original_name, f"b/{output}"
+        )
+        click.echo(changes, nl=False)
+        if changes:
+            sys.exit(1)
+    elif output == "-" or dry_run:
+        click.echo(contents, nl=False)
+    else:
+        _babelone_core.write_file(output, contents, force, backup)
 
 
 @cli.command()
-@click.argument("output", nargs=1, type=click.Path(), required=True)
-def create(output: str):
-    """Scaffold a build spec file and save at the OUTPUT path."""
-    _babelone_core.create(output)
+@click.argument("output", nargs=1, type=click.Path(allow_dash=True), required=True)
+@click.option(
+    "--to",
+    metavar="FORMAT",
+    help="Format of OUTPUT, given as a file name, e.g., pyproject.toml. "
+    "Required when OUTPUT is -.",
+)
+@_write_options
+def create(
+    output: str,
+    to: Optional[str],
+    dry_run: bool,
+    force: bool,
+    backup: bool,
+    diff: bool,
+):
+    """Scaffold a build spec file and save at the OUTPUT path. The
+    packages, modules and data files found in its directory are declared
+    in a scaffolded setup.py or pyproject.toml. OUTPUT may be - to write
+    to stdout, declaring those of the current directory.
+
+    """
+    root = os.path.dirname(output) or "."
+    contents = _babelone_core.create_str(_format_of("OUTPUT", output, "--to", to), root)
+    _write_output(output, contents, dry_run, force, backup, diff)
 
 
 @cli.command()
-@click.argument("input", nargs=1, type=click.Path(exists=True), required=True)
-@click.argument("output", nargs=1, type=click.Path(), required=True)
-def translate(input: str, output: str):
+@click.argument(
+    "input", nargs=1, type=click.Path(exists=True, allow_dash=True), required=True
+)
+@click.argument("output", nargs=1, type=click.Path(allow_dash=True), required=True)
+@click.option(
+    "--from",
+    "from_",
+    metavar="FORMAT",
+    help="Format of INPUT, given as a file name, e.g., setup.py. "
+    "Required when INPUT is -.",
+)
+@click.option(
+    "--to",
+    metavar="FORMAT",
+    help="Format of OUTPUT, given as a file name, e.g., pyproject.toml. "
+    "Required when OUTPUT is -.",
+)
+@click.option(
+    "--env",
+    type=click.Path(exists=True, file_okay=False),
+    help="Virtual environment in which a frozen requirements.txt INPUT is installed, "
+    "used to keep only its top-level requirements.",
+)
+@click.option(
+    "--import-depth",
+    type=click.IntRange(min=0),
+    help="How many modules deep imports of local modules in a setup.py INPUT "
+    "are followed. Defaults to 3.",
+)
+@_write_options
+def translate(
+    input: str,
+    output: str,
+    from_: Optional[str],
+    to: Optional[str],
+    env: Optional[str],
+    import_depth: Optional[int],
+    dry_run: bool,
+    force: bool,
+    backup: bool,
+    diff: bool,
+):
     """Translate the file at the INPUT path to another format saved at
-    the OUTPUT path.
+    the OUTPUT path. INPUT may also be a wheel (.whl) or sdist (.tar.gz),
+    whose metadata is read without unpacking the archive, or a virtual
+    environment directory, whose installed packages are frozen. Either
+    path may be - to read from stdin or write to stdout.
+
+    """
+    target_format = _format_of("OUTPUT", output, "--to", to)
+    if input == "-":
+        if env:
+            raise click.UsageError("--env cannot be given when INPUT is -.")
+        source_format = _format_of("INPUT", input, "--from", from_)
+        contents = _babelone_core.translate_str(
+            sys.stdin.read(), source_format, target_format
+        )
+    else:
+        contents = _babelone_core.translate_file(
+            input, target_format, from_, env, import_depth
+        )
+    _write_output(output, contents, dry_run, force, backup, diff)
+
+
+@cli.command()
+@click.argument("source", nargs=1, type=click.Path(exists=True), required=True)
+@click.argument("dest", nargs=1, type=click.Path(exists=True), required=True)
+@click.option(
+    "--env",
+    type=click.Path(exists=True, file_okay=False),
+    help="Virtual environment in which a frozen requirements.txt SOURCE is "
+    "installed, used to keep only its top-level requirements.",
+)
+@click.option(
+    "--import-depth",
+    type=click.IntRange(min=0),
+    help="How many modules deep imports of local modules in a setup.py are "
+    "followed. Defaults to 3.",
+)
+def check(source: str, dest: str, env: Optional[str], import_depth: Optional[int]):
+    """Check that the requirements declared at the DEST path agree with
+    those translated from the SOURCE path. Requirements are normalized
+    rather than compared as written, and any which differ are reported.
+
+    """
+    report = _babelone_core.check(source, dest, env, import_depth)
+    if report:
+        click.echo(
+            f"{dest} is out of sync with {source} "
+            "(+ missing, - unexpected, ~ different):"
+        )
+        click.echo(report, nl=False)
+        sys.exit(1)
+
+
+@cli.command()
+@click.argument("source", nargs=1, type=click.Path(exists=True), required=True)
+@click.argument("dest", nargs=1, type=click.Path(exists=True), required=True)
+@click.option(
+    "--env",
+    type=click.Path(exists=True, file_okay=False),
+    help="Virtual environment in which a frozen requirements.txt SOURCE is "
+    "installed, used to keep only its top-level requirements.",
+)
+@click.option(
+    "--import-depth",
+    type=click.IntRange(min=0),
+    help="How many modules deep imports of local modules in a setup.py SOURCE "
+    "are followed. Defaults to 3.",
+)
+@_write_options
+def sync(
+    source: str,
+    dest: str,
+    env: Optional[str],
+    import_depth: Optional[int],
+    dry_run: bool,
+    force: bool,
+    backup: bool,
+    diff: bool,
+):
+    """Update the requirements of the existing file at the DEST path to
+    those translated from the SOURCE path. Only project.dependencies and
+    project.optional-dependencies of a pyproject.toml, install_requires
+    and extras_require of a setup.py, or the requirements of a
+    requirements.txt are replaced, and everything else is left as it is.
+
+    """
+    contents = _babelone_core.sync_file(source, dest, env, import_depth)
+    # DEST is updated in place, so it is always overwritten.
+    _write_output(dest, contents, dry_run, not diff, backup, diff)
+
+
+@cli.command("diff")
+@click.argument("old", nargs=1, type=click.Path(exists=True), required=True)
+@click.argument("new", nargs=1, type=click.Path(exists=True), required=True)
+@click.option("--json", "json_", is_flag=True, help="Print the differences as JSON.")
+@click.option(
+    "--import-depth",
+    type=click.IntRange(min=0),
+    help="How many modules deep imports of local modules in a setup.py are "
+    "followed. Defaults to 3.",
+)
+def diff_specs(old: str, new: str, json_: bool, import_depth: Optional[int]):
+    """Show how the build specification at the NEW path differs from the
+    one at the OLD path, which may be of different formats. Added, removed
+    and changed requirements, extras, scripts and metadata fields are
+    reported, and the exit code is 1 if there are any.
+
+    """
+    report, same = _babelone_core.diff_specs(old, new, json_, import_depth)
+    click.echo(report, nl=False)
+    if not same:
+        sys.exit(1)
+
+
+@cli.command()
+@click.argument(
+    "environment",
+    nargs=1,
+    type=click.Path(exists=True, file_okay=False),
+    default=".venv",
+)
+def tree(environment: str):
+    """Show the dependency tree of the virtual environment at the
+    ENVIRONMENT path, warning of missing or conflicting requirements.
+
+    """
+    click.echo(_babelone_core.tree(environment), nl=False)
+
+
+@cli.command()
+@click.argument("input", nargs=1, type=click.Path(exists=True), required=True)
+@click.option(
+    "--package",
+    type=click.Path(exists=True, file_okay=False),
+    help="Directory holding the package's sources. Defaults to that of INPUT.",
+)
+@click.option(
+    "--map",
+    "mappings",
+    multiple=True,
+    metavar="IMPORT=DISTRIBUTION",
+    help="Distribution providing an import name, e.g., yaml=PyYAML.",
+)
+def imports(input: str, package: Optional[str], mappings: Tuple[str, ...]):
+    """Compare the dependencies declared by the requirements.txt,
+    setup.py or pyproject.toml at the INPUT path against those imported
+    by the package's .py files, reporting any that are undeclared or unused.
 
     """
-    _babelone_core.translate(input, output)
+    pairs = []
+    for mapping in mappings:
+        module, sep, distribution = mapping.partition("=")
+        if not sep or not module or not distribution:
+            raise click.BadParameter(
+                f"expected IMPORT=DISTRIBUTION, got {mapping!r}", param_hint="--map"
+            )
+        pairs.append((module.strip(), distribution.strip()))
+    report = _babelone_core.check_imports(input, package, pairs)
+    click.echo(report, nl=False)
+    if report:
+        sys.exit(1)
 
 
-cli()
+try:
+    cli()
+except (OSError, ValueError, NotImplementedError) as error:
+    # 1 is left to mean that files differ, as it does for diff(1).
+    click.echo(f"error: {error}", err=True)
+    sys.exit(2)
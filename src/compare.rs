@@ -0,0 +1,675 @@
+//! Semantic comparison of build specifications, whose requirements are
+//! normalized rather than compared as written, e.g., `Flask >= 3.0` is the
+//! same requirement as `flask>=3.0`.
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use crate::pep508::{self, ParsedRequirement, Version};
+use crate::specs::{Project, PyProject};
+
+/// The requirements of a build spec, keyed by the extra requiring them (`None`
+/// for those always required).
+pub type DependencyGroups = BTreeMap<Option<String>, DependencyGroup>;
+
+/// The requirements always required, or required by an extra.
+#[derive(Debug, Default, PartialEq)]
+pub struct DependencyGroup {
+    /// Requirements keyed by their canonical name and marker, as a distribution
+    /// may be required differently on each platform. Each requirement of a
+    /// name and marker given more than once is kept, in order.
+    pub requirements: BTreeMap<String, Vec<ParsedRequirement>>,
+    /// Entries which are not dependency specifiers, e.g., `-e .` or
+    /// `--index-url <url>`, with their whitespace collapsed.
+    pub entries: Vec<String>,
+}
+
+/// A value which differs between two build specs.
+#[derive(Debug, PartialEq)]
+pub enum Change<T> {
+    Added(T),
+    Removed(T),
+    Changed { from: T, to: T },
+}
+
+/// A requirement which differs between two build specs.
+pub type DependencyChange = Change<ParsedRequirement>;
+
+/// How the requirements of a build spec differ from those of another.
+#[derive(Debug, Default, PartialEq)]
+pub struct DependencyReport {
+    pub changes: BTreeMap<Option<String>, Vec<DependencyChange>>,
+    /// Entries which are not dependency specifiers, e.g., `-e .`, found in
+    /// one spec only.
+    pub entries: BTreeMap<Option<String>, Vec<Change<String>>>,
+}
+
+/// Normalizes a requirement for comparison: its name and extras are canonicalized,
+/// its version specifiers sorted, and its marker spaced and quoted consistently.
+pub fn normalize_requirement(requirement: &ParsedRequirement) -> ParsedRequirement {
+    let mut extras = requirement
+        .extras
+        .iter()
+        .map(|extra| pep508::canonicalize_name(extra))
+        .collect::<Vec<String>>();
+    extras.sort();
+    extras.dedup();
+    let mut specifiers = requirement
+        .specifier
+        .split(',')
+        .map(|specifier| specifier.trim())
+        .filter(|specifier| !specifier.is_empty())
+        .collect::<Vec<&str>>();
+    specifiers.sort();
+    ParsedRequirement {
+        name: pep508::canonicalize_name(&requirement.name),
+        extras,
+        specifier: specifiers.join(","),
+        url: requirement.url.as_ref().map(|url| url.trim().to_string()),
+        marker: requirement.marker.as_deref().map(normalize_marker),
+    }
+}
+
+/// Separates the tokens of a marker by single spaces and quotes its strings
+/// with `"`, e.g., `python_version<'3.8'` becomes `python_version < "3.8"`.
+fn normalize_marker(marker: &str) -> String {
+    let mut tokens = Vec::<String>::new();
+    let mut chars = marker.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            '\'' | '"' => {
+                let value = chars
+                    .by_ref()
+                    .take_while(|&next| next != c)
+                    .collect::<String>();
+                tokens.push(format!("\"{}\"", value));
+            }
+            '(' | ')' => tokens.push(c.to_string()),
+            c if "<>=!~".contains(c) => {
+                let mut operator = c.to_string();
+                while let Some(next) = chars.next_if(|next| "<>=!~".contains(*next)) {
+                    operator.push(next);
+                }
+                tokens.push(operator);
+            }
+            c => {
+                let mut word = c.to_string();
+                while let Some(next) =
+                    chars.next_if(|next| !next.is_whitespace() && !"'\"()<>=!~".contains(*next))
+                {
+                    word.push(next);
+                }
+                tokens.push(word);
+            }
+        }
+    }
+    tokens.join(" ").replace("( ", "(").replace(" )", ")")
+}
+
+/// Collects the normalized requirements of a pyproject.toml, the model any
+/// other build spec is compared through.
+pub fn dependency_groups(pyproject: &PyProject) -> DependencyGroups {
+    let mut groups = DependencyGroups::new();
+    let Some(project) = &pyproject.project else {
+        return groups;
+    };
+    let mut add = |extra: Option<String>, requirements: &[String]| {
+        let group = groups.entry(extra).or_default();
+        for requirement in requirements {
+            let Some(requirement) = pep508::parse_requirement(requirement) else {
+                let entry = requirement.split_whitespace().collect::<Vec<&str>>();
+                group.entries.push(entry.join(" "));
+                continue;
+            };
+            let requirement = normalize_requirement(&requirement);
+            let key = match &requirement.marker {
+                Some(marker) => format!("{}; {}", requirement.name, marker),
+                None => requirement.name.clone(),
+            };
+            group.requirements.entry(key).or_default().push(requirement);
+        }
+    };
+    add(None, project.dependencies.as_deref().unwrap_or_default());
+    for (extra, requirements) in project.optional_dependencies.iter().flatten() {
+        add(Some(pep508::canonicalize_name(extra)), requirements);
+    }
+    groups.retain(|_, group| !group.requirements.is_empty() || !group.entries.is_empty());
+    groups
+}
+
+/// Compares the requirements of `old` against those of `new`. A requirement
+/// of a name and marker given more times in one than in the other, i.e., a
+/// duplicate, is reported as added or removed.
+pub fn compare_dependencies(old: &DependencyGroups, new: &DependencyGroups) -> DependencyReport {
+    let empty = DependencyGroup::default();
+    let mut report = DependencyReport::default();
+    let extras = old.keys().chain(new.keys()).collect::<BTreeSet<_>>();
+    for extra in extras {
+        let old_group = old.get(extra).unwrap_or(&empty);
+        let new_group = new.get(extra).unwrap_or(&empty);
+        let keys = old_group
+            .requirements
+            .keys()
+            .chain(new_group.requirements.keys())
+            .collect::<BTreeSet<_>>();
+        let mut changes = Vec::new();
+        for key in keys {
+            let from = old_group
+                .requirements
+                .get(key)
+                .map_or(&[][..], Vec::as_slice);
+            let to = new_group
+                .requirements
+                .get(key)
+                .map_or(&[][..], Vec::as_slice);
+            match (from, to) {
+                ([from], [to]) if from != to => changes.push(DependencyChange::Changed {
+                    from: from.clone(),
+                    to: to.clone(),
+                }),
+                _ => changes.extend(compare_entries(from, to)),
+            }
+        }
+        if !changes.is_empty() {
+            report.changes.insert(extra.clone(), changes);
+        }
+        let entries = compare_entries(&old_group.entries, &new_group.entries);
+        if !entries.is_empty() {
+            report.entries.insert(extra.clone(), entries);
+        }
+    }
+    report
+}
+
+/// Reports the values of `old` which `new` lacks as removed and the others
+/// of `new` as added, counting each value as often as it is given.
+fn compare_entries<T: Clone + PartialEq>(old: &[T], new: &[T]) -> Vec<Change<T>> {
+    let mut added = new.iter().collect::<Vec<&T>>();
+    let mut changes = Vec::new();
+    for value in old {
+        match added.iter().position(|other| *other == value) {
+            Some(index) => {
+                added.remove(index);
+            }
+            None => changes.push(Change::Removed(value.clone())),
+        }
+    }
+    changes.extend(added.into_iter().map(|value| Change::Added(value.clone())));
+    changes
+}
+
+impl DependencyReport {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty() && self.entries.is_empty()
+    }
+}
+
+impl fmt::Display for DependencyReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let extras = self
+            .changes
+            .keys()
+            .chain(self.entries.keys())
+            .collect::<BTreeSet<_>>();
+        for extra in extras {
+            if let Some(extra) = extra {
+                writeln!(f, "[{}]", extra)?;
+            }
+            for change in self.changes.get(extra).into_iter().flatten() {
+                writeln!(f, "{}", change)?;
+            }
+            for change in self.entries.get(extra).into_iter().flatten() {
+                writeln!(f, "{}", change)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Change<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Change::Added(value) => write!(f, "+ {}", value),
+            Change::Removed(value) => write!(f, "- {}", value),
+            Change::Changed { from, to } => write!(f, "~ {} -> {}", from, to),
+        }
+    }
+}
+
+impl<T: fmt::Display> Change<T> {
+    fn to_json(&self) -> Value {
+        match self {
+            Change::Added(value) => json!({"added": value.to_string()}),
+            Change::Removed(value) => json!({"removed": value.to_string()}),
+            Change::Changed { from, to } => {
+                json!({"from": from.to_string(), "to": to.to_string()})
+            }
+        }
+    }
+}
+
+/// How a build spec differs from another of any format, compared through
+/// the pyproject.toml each translates to.
+#[derive(Debug, Default, PartialEq)]
+pub struct SpecReport {
+    /// Fields such as `version` or `requires-python`, and `urls.<label>`.
+    pub metadata: BTreeMap<String, Change<String>>,
+    /// Requirements added or removed, or changed other than in their version
+    /// specifiers, e.g., in their extras.
+    pub dependencies: DependencyReport,
+    /// Requirements whose version specifiers alone differ, e.g., a bump from
+    /// `numpy>=1.26` to `numpy>=2.0`, keyed by extra like `dependencies`.
+    pub specifiers: BTreeMap<Option<String>, Vec<DependencyChange>>,
+    /// Extras which are declared by one spec only.
+    pub extras: Vec<Change<String>>,
+    /// The `module:function` targets of console scripts, and of GUI scripts
+    /// keyed as `gui:<name>`.
+    pub scripts: BTreeMap<String, Change<String>>,
+}
+
+/// Compares the metadata, requirements, extras and scripts of `old` against
+/// those of `new`.
+pub fn compare_specs(old: &PyProject, new: &PyProject) -> SpecReport {
+    let default = Default::default();
+    let old_project = old.project.as_ref().unwrap_or(&default);
+    let new_project = new.project.as_ref().unwrap_or(&default);
+    let mut report = SpecReport {
+        dependencies: compare_dependencies(&dependency_groups(old), &dependency_groups(new)),
+        ..Default::default()
+    };
+    for (extra, changes) in report.dependencies.changes.iter_mut() {
+        let (specifiers, others) = std::mem::take(changes)
+            .into_iter()
+            .partition::<Vec<_>, _>(is_specifier_change);
+        *changes = others;
+        if !specifiers.is_empty() {
+            report.specifiers.insert(extra.clone(), specifiers);
+        }
+    }
+    report
+        .dependencies
+        .changes
+        .retain(|_, changes| !changes.is_empty());
+
+    let fields = |project: &Project| {
+        let mut fields = BTreeMap::new();
+        let values = [
+            ("name", &project.name),
+            ("version", &project.version),
+            ("description", &project.description),
+            ("requires-python", &project.requires_python),
+        ];
+        for (field, value) in values {
+            if let Some(value) = value.as_ref().filter(|value| !value.is_empty()) {
+                fields.insert(field.to_string(), value.clone());
+            }
+        }
+        for (label, url) in project.urls.iter().flatten() {
+            fields.insert(format!("urls.{}", label), url.clone());
+        }
+        fields
+    };
+    report.metadata = compare_values(
+        &fields(old_project),
+        &fields(new_project),
+        |field, from, to| match field {
+            "name" => pep508::canonicalize_name(from) == pep508::canonicalize_name(to),
+            "version" => match (from.parse::<Version>(), to.parse::<Version>()) {
+                (Ok(from), Ok(to)) => from == to,
+                _ => from == to,
+            },
+            "requires-python" => normalize_specifier(from) == normalize_specifier(to),
+            _ => from == to,
+        },
+    );
+
+    let extras = |project: &Project| {
+        project
+            .optional_dependencies
+            .iter()
+            .flatten()
+            .map(|(extra, _)| pep508::canonicalize_name(extra))
+            .collect::<BTreeSet<String>>()
+    };
+    let (old_extras, new_extras) = (extras(old_project), extras(new_project));
+    report.extras = old_extras
+        .symmetric_difference(&new_extras)
+        .map(|extra| match new_extras.contains(extra) {
+            true => Change::Added(extra.clone()),
+            false => Change::Removed(extra.clone()),
+        })
+        .collect();
+
+    let scripts = |project: &Project| {
+        let mut scripts = BTreeMap::new();
+        for (name, target) in project.project_scripts.iter().flatten() {
+            scripts.insert(name.clone(), target.clone());
+        }
+        for (name, target) in project.project_gui_scripts.iter().flatten() {
+            scripts.insert(format!("gui:{}", name), target.clone());
+        }
+        scripts
+    };
+    report.scripts = compare_values(
+        &scripts(old_project),
+        &scripts(new_project),
+        |_, from, to| from.replace(' ', "") == to.replace(' ', ""),
+    );
+    report
+}
+
+/// Whether a requirement changed in its version specifiers alone.
+fn is_specifier_change(change: &DependencyChange) -> bool {
+    match change {
+        Change::Changed { from, to } => {
+            from.extras == to.extras && from.url == to.url && from.specifier != to.specifier
+        }
+        _ => false,
+    }
+}
+
+/// Compares the values of `old` against those of `new` by key, where `same`
+/// tells whether two values of a key are equivalent.
+fn compare_values(
+    old: &BTreeMap<String, String>,
+    new: &BTreeMap<String, String>,
+    same: impl Fn(&str, &str, &str) -> bool,
+) -> BTreeMap<String, Change<String>> {
+    let mut changes = BTreeMap::new();
+    for (key, from) in old {
+        match new.get(key) {
+            Some(to) if !same(key, from, to) => {
+                let change = Change::Changed {
+                    from: from.clone(),
+                    to: to.clone(),
+                };
+                changes.insert(key.clone(), change);
+            }
+            Some(_) => {}
+            None => {
+                changes.insert(key.clone(), Change::Removed(from.clone()));
+            }
+        }
+    }
+    for (key, to) in new {
+        if !old.contains_key(key) {
+            changes.insert(key.clone(), Change::Added(to.clone()));
+        }
+    }
+    changes
+}
+
+/// Sorts the comma-separated version specifiers of, e.g., `requires-python`.
+fn normalize_specifier(specifier: &str) -> String {
+    let mut specifiers = specifier
+        .split(',')
+        .map(|specifier| specifier.chars().filter(|c| !c.is_whitespace()).collect())
+        .filter(|specifier: &String| !specifier.is_empty())
+        .collect::<Vec<String>>();
+    specifiers.sort();
+    specifiers.join(",")
+}
+
+impl SpecReport {
+    pub fn is_empty(&self) -> bool {
+        self.metadata.is_empty()
+            && self.dependencies.is_empty()
+            && self.specifiers.is_empty()
+            && self.extras.is_empty()
+            && self.scripts.is_empty()
+    }
+
+    /// The report as JSON, e.g., for a bot commenting on a pull request.
+    /// Requirements are listed with the extra requiring them, which is `null`
+    /// for those always required.
+    pub fn to_json(&self) -> Value {
+        let metadata = self
+            .metadata
+            .iter()
+            .map(|(field, change)| (field.clone(), change.to_json()))
+            .collect::<serde_json::Map<String, Value>>();
+        let dependencies = self
+            .dependencies
+            .changes
+            .iter()
+            .flat_map(|(extra, changes)| changes.iter().map(move |change| (extra, change)))
+            .map(|(extra, change)| {
+                let name = match change {
+                    Change::Added(requirement) | Change::Removed(requirement) => &requirement.name,
+                    Change::Changed { to, .. } => &to.name,
+                };
+                let mut change = change.to_json();
+                change["extra"] = json!(extra);
+                change["name"] = json!(name);
+                change
+            })
+            .collect::<Vec<Value>>();
+        let specifiers = self
+            .specifiers
+            .iter()
+            .flat_map(|(extra, changes)| changes.iter().map(move |change| (extra, change)))
+            .filter_map(|(extra, change)| match change {
+                Change::Changed { from, to } => Some(json!({
+                    "extra": extra,
+                    "name": to.name,
+                    "marker": to.marker,
+                    "from": from.specifier,
+                    "to": to.specifier,
+                })),
+                _ => None,
+            })
+            .collect::<Vec<Value>>();
+        let entries = self
+            .dependencies
+            .entries
+            .iter()
+            .flat_map(|(extra, changes)| changes.iter().map(move |change| (extra, change)))
+            .map(|(extra, change)| {
+                let mut change = change.to_json();
+                change["extra"] = json!(extra);
+                change
+            })
+            .collect::<Vec<Value>>();
+        let scripts = self
+            .scripts
+            .iter()
+            .map(|(name, change)| (name.clone(), change.to_json()))
+            .collect::<serde_json::Map<String, Value>>();
+        json!({
+            "metadata": metadata,
+            "dependencies": dependencies,
+            "specifiers": specifiers,
+            "entries": entries,
+            "extras": self.extras.iter().map(Change::to_json).collect::<Vec<Value>>(),
+            "scripts": scripts,
+        })
+    }
+}
+
+impl fmt::Display for SpecReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if !self.metadata.is_empty() {
+            writeln!(f, "Metadata:")?;
+            for (field, change) in &self.metadata {
+                let change = match change {
+                    Change::Added(value) => format!("+ {}: {}", field, value),
+                    Change::Removed(value) => format!("- {}: {}", field, value),
+                    Change::Changed { from, to } => format!("~ {}: {} -> {}", field, from, to),
+                };
+                writeln!(f, "{}", change)?;
+            }
+        }
+        if !self.dependencies.is_empty() {
+            write!(f, "Dependencies:\n{}", self.dependencies)?;
+        }
+        if !self.specifiers.is_empty() {
+            writeln!(f, "Specifiers:")?;
+            for (extra, changes) in &self.specifiers {
+                if let Some(extra) = extra {
+                    writeln!(f, "[{}]", extra)?;
+                }
+                for change in changes {
+                    if let Change::Changed { from, to } = change {
+                        let specifier = |specifier: &str| match specifier {
+                            "" => "(any)".to_string(),
+                            specifier => specifier.to_string(),
+                        };
+                        let name = match &to.marker {
+                            Some(marker) => format!("{}; {}", to.name, marker),
+                            None => to.name.clone(),
+                        };
+                        writeln!(
+                            f,
+                            "~ {}: {} -> {}",
+                            name,
+                            specifier(&from.specifier),
+                            specifier(&to.specifier)
+                        )?;
+                    }
+                }
+            }
+        }
+        if !self.extras.is_empty() {
+            writeln!(f, "Extras:")?;
+            for change in &self.extras {
+                writeln!(f, "{}", change)?;
+            }
+        }
+        if !self.scripts.is_empty() {
+            writeln!(f, "Scripts:")?;
+            for (name, change) in &self.scripts {
+                let change = match change {
+                    Change::Added(target) => format!("+ {} = {}", name, target),
+                    Change::Removed(target) => format!("- {} = {}", name, target),
+                    Change::Changed { from, to } => format!("~ {} = {} -> {}", name, from, to),
+                };
+                writeln!(f, "{}", change)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn pyproject(dependencies: &[&str], optional_dependencies: &[(&str, &[&str])]) -> PyProject {
+        let to_strings = |requirements: &[&str]| {
+            requirements
+                .iter()
+                .map(|requirement| requirement.to_string())
+                .collect::<Vec<String>>()
+        };
+        PyProject {
+            project: Some(Project {
+                dependencies: Some(to_strings(dependencies)),
+                optional_dependencies: Some(
+                    optional_dependencies
+                        .iter()
+                        .map(|(extra, requirements)| (extra.to_string(), to_strings(requirements)))
+                        .collect(),
+                ),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn normalize_requirements() {
+        let requirement = pep508::parse_requirement(
+            "Flask_SQLAlchemy[Async,asyncio] <4, >= 3.0 ; python_version<'3.12' and (os_name=='nt')",
+        )
+        .unwrap();
+        assert_eq!(
+            normalize_requirement(&requirement).to_string(),
+            "flask-sqlalchemy[async,asyncio]<4,>=3.0; python_version < \"3.12\" and (os_name == \"nt\")"
+        );
+    }
+
+    #[test]
+    fn compare_dependency_groups() {
+        let old = pyproject(
+            &["Flask>=3.0", "requests", "numpy>=1.26"],
+            &[("Test", &["pytest"])],
+        );
+        let new = pyproject(
+            &["flask >= 3.0", "numpy>=2.0", "rich"],
+            &[("test", &["pytest"]), ("docs", &["sphinx"])],
+        );
+        let report = compare_dependencies(&dependency_groups(&old), &dependency_groups(&new));
+        assert_eq!(
+            report.to_string(),
+            "~ numpy>=1.26 -> numpy>=2.0\n- requests\n+ rich\n[docs]\n+ sphinx\n"
+        );
+        let report = compare_dependencies(&dependency_groups(&new), &dependency_groups(&new));
+        assert!(report.is_empty());
+    }
+
+    #[test]
+    fn compare_duplicates_and_entries() {
+        let old = pyproject(
+            &[
+                "flask>=3.0",
+                "-e .",
+                "--index-url  https://example.com/simple",
+            ],
+            &[],
+        );
+        let new = pyproject(
+            &[
+                "flask>=3.0",
+                "Flask<4",
+                "--index-url https://example.com/simple",
+            ],
+            &[],
+        );
+        let report = compare_dependencies(&dependency_groups(&old), &dependency_groups(&new));
+        assert_eq!(report.to_string(), "+ flask<4\n- -e .\n");
+        let report = compare_dependencies(&dependency_groups(&new), &dependency_groups(&new));
+        assert!(report.is_empty());
+    }
+
+    #[test]
+    fn compare_spec_fields() {
+        let mut old = pyproject(&["flask>=3.0"], &[("test", &["pytest"])]);
+        let mut new = pyproject(&["flask>=3.1"], &[("docs", &["sphinx"])]);
+        let old_project = old.project.as_mut().unwrap();
+        old_project.name = Some("Demo_Pkg".to_string());
+        old_project.version = Some("1.0".to_string());
+        old_project.requires_python = Some(">=3.9, <4".to_string());
+        old_project.project_scripts = Some(BTreeMap::from([
+            ("demo".to_string(), "demo.cli:main".to_string()),
+            ("old".to_string(), "demo.old:main".to_string()),
+        ]));
+        let new_project = new.project.as_mut().unwrap();
+        new_project.name = Some("demo-pkg".to_string());
+        new_project.version = Some("1.1".to_string());
+        new_project.requires_python = Some("<4,>=3.9".to_string());
+        new_project.project_scripts = Some(BTreeMap::from([(
+            "demo".to_string(),
+            "demo.cli:run".to_string(),
+        )]));
+
+        let report = compare_specs(&old, &new);
+        assert_eq!(
+            report.to_string(),
+            "Metadata:\n~ version: 1.0 -> 1.1\n\
+             Dependencies:\n[docs]\n+ sphinx\n[test]\n- pytest\n\
+             Specifiers:\n~ flask: >=3.0 -> >=3.1\n\
+             Extras:\n+ docs\n- test\n\
+             Scripts:\n~ demo = demo.cli:main -> demo.cli:run\n- old = demo.old:main\n"
+        );
+        assert_eq!(
+            report.to_json()["specifiers"],
+            json!([{"extra": null, "name": "flask", "marker": null, "from": ">=3.0", "to": ">=3.1"}])
+        );
+        assert_eq!(
+            report.to_json()["dependencies"][0],
+            json!({"extra": "docs", "name": "sphinx", "added": "sphinx"})
+        );
+        assert!(compare_specs(&new, &new).is_empty());
+    }
+}
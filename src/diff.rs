@@ -0,0 +1,258 @@
+//! Unified diffs between the existing contents of a file and those babelone
+//! would write over it, to preview a translation before it is written.
+use std::fmt::Write;
+
+/// Lines of unchanged context shown around each change.
+const CONTEXT: usize = 3;
+
+/// Edits beyond which the lines between those the files share at either end
+/// are diffed as replaced outright, bounding the time and memory taken.
+const MAX_EDITS: usize = 1000;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Line {
+    Equal(usize, usize),
+    Delete(usize),
+    Insert(usize),
+}
+
+/// Returns the unified diff from `original` to `modified`, labelled with
+/// `original_name` and `modified_name`, or an empty string if they are equal.
+/// Lines are compared along with their endings, so a change from `\n` to
+/// `\r\n` is shown, as is a missing newline at the end of either file.
+pub fn unified_diff(
+    original: &str,
+    modified: &str,
+    original_name: &str,
+    modified_name: &str,
+) -> String {
+    let old: Vec<&str> = original.split_inclusive('\n').collect();
+    let new: Vec<&str> = modified.split_inclusive('\n').collect();
+    let lines = diff_lines(&old, &new);
+    let changes: Vec<usize> = (0..lines.len())
+        .filter(|&index| !matches!(lines[index], Line::Equal(..)))
+        .collect();
+    let Some(&first) = changes.first() else {
+        return String::new();
+    };
+
+    // Changes closer than twice the context apart share a hunk.
+    let mut hunks = vec![(first, first)];
+    for &change in &changes[1..] {
+        let last = hunks.last_mut().unwrap();
+        match change - last.1 <= 2 * CONTEXT {
+            true => last.1 = change,
+            false => hunks.push((change, change)),
+        }
+    }
+
+    let mut diff = format!("--- {}\n+++ {}\n", original_name, modified_name);
+    for (first, last) in hunks {
+        let start = first.saturating_sub(CONTEXT);
+        let end = (last + CONTEXT + 1).min(lines.len());
+        let hunk = &lines[start..end];
+        // Where the hunk begins in either file, had it no lines of its own.
+        let (mut old_start, mut new_start) = (0, 0);
+        for line in &lines[..start] {
+            match line {
+                Line::Equal(..) => {
+                    old_start += 1;
+                    new_start += 1;
+                }
+                Line::Delete(_) => old_start += 1,
+                Line::Insert(_) => new_start += 1,
+            }
+        }
+        let old_len = hunk
+            .iter()
+            .filter(|line| !matches!(line, Line::Insert(_)))
+            .count();
+        let new_len = hunk
+            .iter()
+            .filter(|line| !matches!(line, Line::Delete(_)))
+            .count();
+        let _ = writeln!(
+            diff,
+            "@@ -{} +{} @@",
+            hunk_range(old_start, old_len),
+            hunk_range(new_start, new_len)
+        );
+        for line in hunk {
+            let (prefix, text) = match *line {
+                Line::Equal(i, _) => (' ', old[i]),
+                Line::Delete(i) => ('-', old[i]),
+                Line::Insert(j) => ('+', new[j]),
+            };
+            diff.push(prefix);
+            diff.push_str(text);
+            if !text.ends_with('\n') {
+                diff.push_str("\n\\ No newline at end of file\n");
+            }
+        }
+    }
+    diff
+}
+
+/// The `start,length` of a hunk, where an empty hunk starts at the line
+/// before it.
+fn hunk_range(start: usize, len: usize) -> String {
+    match len {
+        0 => format!("{},0", start),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{}", start + 1, len),
+    }
+}
+
+/// Matches the lines of `old` and `new`, first those they share at either
+/// end and then those in between by the fewest edits.
+fn diff_lines(old: &[&str], new: &[&str]) -> Vec<Line> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let (old_end, new_end) = (old.len() - suffix, new.len() - suffix);
+
+    let mut lines: Vec<Line> = (0..prefix).map(|i| Line::Equal(i, i)).collect();
+    match shortest_edit(&old[prefix..old_end], &new[prefix..new_end], prefix) {
+        Some(edit) => lines.extend(edit),
+        None => {
+            lines.extend((prefix..old_end).map(Line::Delete));
+            lines.extend((prefix..new_end).map(Line::Insert));
+        }
+    }
+    lines.extend((0..suffix).map(|k| Line::Equal(old_end + k, new_end + k)));
+    lines
+}
+
+/// Finds the fewest deletions and insertions turning `old` into `new` with
+/// Myers' algorithm, numbering lines from `offset`, or `None` if it takes
+/// more than `MAX_EDITS`.
+fn shortest_edit(old: &[&str], new: &[&str], offset: usize) -> Option<Vec<Line>> {
+    let (n, m) = (old.len() as isize, new.len() as isize);
+    let max = (n + m).min(MAX_EDITS as isize);
+    let index = |k: isize| (max + k) as usize;
+    // furthest[index(k)] is the furthest line of `old` reached on diagonal
+    // k = x - y, and `trace` holds it as it was before each edit.
+    let mut furthest = vec![0isize; 2 * max as usize + 2];
+    let mut trace = Vec::new();
+    let mut found = false;
+    'search: for d in 0..=max {
+        trace.push(furthest.clone());
+        for k in (-d..=d).step_by(2) {
+            let mut x = match k == -d || (k != d && furthest[index(k - 1)] < furthest[index(k + 1)])
+            {
+                true => furthest[index(k + 1)],
+                false => furthest[index(k - 1)] + 1,
+            };
+            let mut y = x - k;
+            while x < n && y < m && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            furthest[index(k)] = x;
+            if x >= n && y >= m {
+                found = true;
+                break 'search;
+            }
+        }
+    }
+    if !found {
+        return None;
+    }
+
+    // Walks back from the end, through the diagonal each edit was made from.
+    let mut lines = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for (d, furthest) in trace.iter().enumerate().rev() {
+        let (d, k) = (d as isize, x - y);
+        let prev_k = match k == -d || (k != d && furthest[index(k - 1)] < furthest[index(k + 1)]) {
+            true => k + 1,
+            false => k - 1,
+        };
+        let prev_x = furthest[index(prev_k)];
+        let prev_y = prev_x - prev_k;
+        while x > prev_x && y > prev_y {
+            x -= 1;
+            y -= 1;
+            lines.push(Line::Equal(x as usize + offset, y as usize + offset));
+        }
+        if d > 0 {
+            match x == prev_x {
+                true => lines.push(Line::Insert(prev_y as usize + offset)),
+                false => lines.push(Line::Delete(prev_x as usize + offset)),
+            }
+        }
+        (x, y) = (prev_x, prev_y);
+    }
+    lines.reverse();
+    Some(lines)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn make_unified_diff() {
+        assert_eq!(unified_diff("flask\n", "flask\n", "a", "b"), "");
+
+        let original = "click\nflask\nnumpy\npandas\nrequests\nrich\nscipy\ntoml\nurllib3\n";
+        let modified =
+            "click\nflask==3.0\nnumpy\npandas\nrequests\nrich\nscipy\ntoml\nurllib3\nzstd\n";
+        assert_eq!(
+            unified_diff(
+                original,
+                modified,
+                "a/requirements.txt",
+                "b/requirements.txt"
+            ),
+            "--- a/requirements.txt\n+++ b/requirements.txt\n\
+             @@ -1,5 +1,5 @@\n click\n-flask\n+flask==3.0\n numpy\n pandas\n requests\n\
+             @@ -7,3 +7,4 @@\n scipy\n toml\n urllib3\n+zstd\n"
+        );
+
+        // A new file is diffed against nothing.
+        assert_eq!(
+            unified_diff("", "flask\n", "/dev/null", "b/requirements.txt"),
+            "--- /dev/null\n+++ b/requirements.txt\n@@ -0,0 +1 @@\n+flask\n"
+        );
+    }
+
+    #[test]
+    fn diff_line_endings() {
+        assert_eq!(
+            unified_diff("flask\nrich\n", "flask\nrich", "a", "b"),
+            "--- a\n+++ b\n@@ -1,2 +1,2 @@\n flask\n-rich\n+rich\n\\ No newline at end of file\n"
+        );
+        assert_eq!(
+            unified_diff("flask\n", "flask\r\n", "a", "b"),
+            "--- a\n+++ b\n@@ -1 +1 @@\n-flask\n+flask\r\n"
+        );
+    }
+
+    #[test]
+    fn diff_many_lines() {
+        let count = |diff: &str, prefix: char| {
+            diff.lines()
+                .skip(2)
+                .filter(|line| line.starts_with(prefix))
+                .count()
+        };
+        // Lines are matched however far apart the changes are.
+        let original: String = (0..5000).map(|i| format!("{}\n", i)).collect();
+        let modified = original
+            .replacen("10\n", "", 1)
+            .replacen("4990\n", "4990\nend\n", 1);
+        let diff = unified_diff(&original, &modified, "a", "b");
+        assert_eq!((count(&diff, '-'), count(&diff, '+')), (1, 1));
+
+        // Files differing throughout are diffed as replaced outright.
+        let modified: String = (0..5000).map(|i| format!("{}\n", i * 2)).collect();
+        let diff = unified_diff(&original, &modified, "a", "b");
+        assert!(diff.starts_with("--- a\n+++ b\n@@ -1,5000 +1,5000 @@\n 0\n-1\n-2\n"));
+        assert_eq!((count(&diff, '-'), count(&diff, '+')), (4999, 4999));
+    }
+}
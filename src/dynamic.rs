@@ -0,0 +1,477 @@
+//! Resolves versions which build specs read from a project's files rather
+//! than declare literally.
+use crate::evaluate::{Evaluator, PyValue};
+use regex::Regex;
+use rustpython_parser::{ast, Parse};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Finds `__version__ = "..."` (or `VERSION`), like hatch's default pattern.
+const DEFAULT_PATTERN: &str =
+    r#"(?im)^\s*(?:__version__|VERSION)\s*(?::\s*str\s*)?=\s*['"]v?(?P<version>[^'"]+)['"]"#;
+
+/// Imports followed when resolving a module attribute, e.g., from
+/// `spam/__init__.py` to `spam/_version.py`.
+const MAX_IMPORT_DEPTH: usize = 8;
+
+/// A version read from a project's files when the spec is built.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DynamicVersion {
+    /// An attribute of a module, e.g., `spam.__version__`, and the path of the
+    /// module relative to the project root, when found.
+    Attribute { attr: String, path: Option<String> },
+    /// A file holding nothing but the version, e.g., `VERSION`.
+    File(String),
+    /// A file in which a regex pattern finds the version, in its `version`
+    /// group (or else its first group). The default pattern finds `__version__`.
+    Pattern {
+        path: String,
+        pattern: Option<String>,
+    },
+}
+
+impl DynamicVersion {
+    /// An attribute of a module, located relative to the project root, if known.
+    pub fn attribute(attr: &str, root: Option<&Path>) -> Self {
+        let path = attr
+            .rsplit_once('.')
+            .zip(root)
+            .and_then(|((module, _), root)| Self::find_module(root, module));
+        Self::Attribute {
+            attr: attr.to_string(),
+            path,
+        }
+    }
+
+    /// Reads the version statically, without importing or running any code.
+    pub fn resolve(&self, root: &Path) -> Option<String> {
+        match self {
+            Self::Attribute { attr, path } => {
+                let (module, name) = attr.rsplit_once('.')?;
+                let path = match path {
+                    Some(path) => path.clone(),
+                    None => Self::find_module(root, module)?,
+                };
+                Self::resolve_attribute(root, module, &path, name, 0)
+            }
+            Self::File(path) => {
+                let contents = std::fs::read_to_string(root.join(path)).ok()?;
+                contents
+                    .lines()
+                    .map(|line| line.trim())
+                    .find(|line| !line.is_empty() && !line.starts_with('#'))
+                    .map(|line| line.to_string())
+            }
+            Self::Pattern { path, pattern } => {
+                let contents = std::fs::read_to_string(root.join(path)).ok()?;
+                let regex = Regex::new(pattern.as_deref().unwrap_or(DEFAULT_PATTERN)).ok()?;
+                let captures = regex.captures(&contents)?;
+                captures
+                    .name("version")
+                    .or_else(|| captures.get(1))
+                    .map(|version| version.as_str().trim().to_string())
+            }
+        }
+    }
+
+    /// Returns the path of a module's source relative to the project root,
+    /// looking in both flat and `src` layouts.
+    pub fn find_module(root: &Path, module: &str) -> Option<String> {
+        let module = module.replace('.', "/");
+        for base in ["", "src/"] {
+            for candidate in [
+                format!("{}{}/__init__.py", base, module),
+                format!("{}{}.py", base, module),
+            ] {
+                if root.join(&candidate).is_file() {
+                    return Some(candidate);
+                }
+            }
+        }
+        None
+    }
+
+    fn resolve_attribute(
+        root: &Path,
+        module: &str,
+        path: &str,
+        name: &str,
+        depth: usize,
+    ) -> Option<String> {
+        let contents = std::fs::read_to_string(root.join(path)).ok()?;
+        let statements = ast::Suite::parse(&contents, path).ok()?;
+        let mut assignments = BTreeMap::<String, ast::Expr>::new();
+        for statement in statements.iter() {
+            match statement {
+                ast::Stmt::Assign(assign) => {
+                    for target in assign.targets.iter() {
+                        if let ast::Expr::Name(target) = target {
+                            assignments.insert(target.id.to_string(), *assign.value.clone());
+                        }
+                    }
+                }
+                ast::Stmt::AnnAssign(assign) => {
+                    if let (ast::Expr::Name(target), Some(value)) =
+                        (assign.target.as_ref(), &assign.value)
+                    {
+                        assignments.insert(target.id.to_string(), *value.clone());
+                    }
+                }
+                _ => (),
+            }
+        }
+        // e.g., `__version__ = "%d.%d.%d" % VERSION_INFO`
+        if let Some(value) = assignments.get(name) {
+            if let Ok(PyValue::Str(version)) = Evaluator::new(&assignments).evaluate(value) {
+                return Some(version);
+            }
+        }
+
+        // The attribute may be re-exported, e.g., `from ._version import __version__`.
+        if depth >= MAX_IMPORT_DEPTH {
+            return None;
+        }
+        for statement in statements.iter() {
+            let ast::Stmt::ImportFrom(import) = statement else {
+                continue;
+            };
+            let Some(alias) = import
+                .names
+                .iter()
+                .find(|alias| alias.asname.as_ref().unwrap_or(&alias.name).as_str() == name)
+            else {
+                continue;
+            };
+            let level = import.level.as_ref().map_or(0, |level| level.to_u32()) as usize;
+            let target = if level == 0 {
+                import.module.as_ref()?.to_string()
+            } else {
+                // Relative imports are resolved against the importing module's package.
+                let mut package = module.split('.').collect::<Vec<&str>>();
+                if !path.ends_with("__init__.py") {
+                    package.pop();
+                }
+                package.truncate(package.len().checked_sub(level - 1)?);
+                if let Some(submodule) = &import.module {
+                    package.push(submodule.as_str());
+                }
+                package.join(".")
+            };
+            // `from spam import _version` imports a module rather than an attribute.
+            let submodule = format!("{}.{}", target, alias.name.as_str());
+            if let Some(submodule_path) = Self::find_module(root, &submodule) {
+                if let Some(version) =
+                    Self::resolve_attribute(root, &submodule, &submodule_path, name, depth + 1)
+                {
+                    return Some(version);
+                }
+            }
+            let target_path = Self::find_module(root, &target)?;
+            return Self::resolve_attribute(
+                root,
+                &target,
+                &target_path,
+                alias.name.as_str(),
+                depth + 1,
+            );
+        }
+        None
+    }
+
+    /// Reads the version source of a pyproject.toml's `[tool]` table for its
+    /// build backend.
+    pub fn from_tool_table(
+        tool: Option<&toml::Table>,
+        build_backend: &str,
+        project_name: Option<&str>,
+    ) -> Option<Self> {
+        let get = |keys: &[&str]| -> Option<&toml::Value> {
+            let mut value = tool?.get(keys[0])?;
+            for key in &keys[1..] {
+                value = value.get(key)?;
+            }
+            Some(value)
+        };
+        if let Some(version) = get(&["setuptools", "dynamic", "version"]) {
+            if let Some(attr) = version.get("attr").and_then(|attr| attr.as_str()) {
+                return Some(Self::Attribute {
+                    attr: attr.to_string(),
+                    path: None,
+                });
+            }
+            let file = match version.get("file") {
+                Some(toml::Value::Array(files)) => files.first().and_then(|file| file.as_str()),
+                Some(file) => file.as_str(),
+                None => None,
+            };
+            if let Some(file) = file {
+                return Some(Self::File(file.to_string()));
+            }
+        }
+        if let Some(version) = get(&["hatch", "version"]) {
+            if let Some(path) = version.get("path").and_then(|path| path.as_str()) {
+                return Some(Self::Pattern {
+                    path: path.to_string(),
+                    pattern: version
+                        .get("pattern")
+                        .and_then(|pattern| pattern.as_str())
+                        .map(|pattern| pattern.to_string()),
+                });
+            }
+        }
+        if let Some(version) = get(&["pdm", "version"]) {
+            if version.get("source").and_then(|source| source.as_str()) == Some("file") {
+                if let Some(path) = version.get("path").and_then(|path| path.as_str()) {
+                    return Some(Self::Pattern {
+                        path: path.to_string(),
+                        pattern: None,
+                    });
+                }
+            }
+        }
+        // Flit reads `__version__` from the module it builds.
+        if build_backend.starts_with("flit_core") {
+            let module = get(&["flit", "module", "name"])
+                .and_then(|name| name.as_str())
+                .map(|name| name.to_string())
+                .or_else(|| project_name.map(|name| name.replace(['-', '.'], "_")))?;
+            return Some(Self::Attribute {
+                attr: format!("{}.__version__", module),
+                path: None,
+            });
+        }
+        None
+    }
+
+    /// Returns the `[tool]` tables declaring this version source for a build
+    /// backend, defaulting to setuptools for unknown backends, or the reason
+    /// the backend cannot read the version this way.
+    pub fn to_tool_table(&self, build_backend: &str) -> Result<toml::Table, String> {
+        let string = |value: &str| toml::Value::String(value.to_string());
+        let mut version = toml::Table::new();
+        let mut tool = toml::Table::new();
+        match build_backend.split('.').next().unwrap_or_default() {
+            "hatchling" => {
+                match self {
+                    Self::Attribute { attr, path } => {
+                        let (module, name) = attr.rsplit_once('.').unwrap_or((attr, "__version__"));
+                        let path = path
+                            .clone()
+                            .unwrap_or_else(|| format!("{}/__init__.py", module.replace('.', "/")));
+                        version.insert("path".to_string(), string(&path));
+                        if name != "__version__" {
+                            version.insert(
+                                "pattern".to_string(),
+                                string(&Self::attribute_pattern(name)),
+                            );
+                        }
+                    }
+                    Self::File(path) => {
+                        version.insert("path".to_string(), string(path));
+                        version.insert("pattern".to_string(), string(r"(?P<version>\S+)"));
+                    }
+                    Self::Pattern { path, pattern } => {
+                        version.insert("path".to_string(), string(path));
+                        if let Some(pattern) = pattern {
+                            version.insert("pattern".to_string(), string(pattern));
+                        }
+                    }
+                }
+                let mut hatch = toml::Table::new();
+                hatch.insert("version".to_string(), toml::Value::Table(version));
+                tool.insert("hatch".to_string(), toml::Value::Table(hatch));
+            }
+            // pdm's file source only finds `__version__` in the file.
+            "pdm" => {
+                let path = match self {
+                    Self::Attribute { attr, path } => {
+                        let (module, name) = attr.rsplit_once('.').unwrap_or((attr, "__version__"));
+                        if name != "__version__" {
+                            return Err(format!("pdm only reads `__version__`, not `{}`", attr));
+                        }
+                        match path {
+                            Some(path) => path.clone(),
+                            None => format!("{}/__init__.py", module.replace('.', "/")),
+                        }
+                    }
+                    Self::File(path) => {
+                        return Err(format!(
+                            "pdm only reads `__version__`, not the whole of {}",
+                            path
+                        ));
+                    }
+                    Self::Pattern { path, pattern } => {
+                        if !Self::finds_dunder_version(pattern) {
+                            return Err(format!(
+                                "pdm cannot search {} with a custom pattern",
+                                path
+                            ));
+                        }
+                        path.clone()
+                    }
+                };
+                version.insert("source".to_string(), string("file"));
+                version.insert("path".to_string(), string(&path));
+                let mut pdm = toml::Table::new();
+                pdm.insert("version".to_string(), toml::Value::Table(version));
+                tool.insert("pdm".to_string(), toml::Value::Table(pdm));
+            }
+            // Flit needs no configuration beyond `dynamic = ["version"]`.
+            "flit_core" => (),
+            _ => {
+                match self {
+                    Self::Attribute { attr, .. } => {
+                        version.insert("attr".to_string(), string(attr));
+                    }
+                    // setuptools reads a version file whole, which a module is not.
+                    Self::File(path) | Self::Pattern { path, .. } if !path.ends_with(".py") => {
+                        version.insert("file".to_string(), string(path));
+                    }
+                    Self::File(path) => {
+                        return Err(format!(
+                            "setuptools cannot read a version from the whole of {}",
+                            path
+                        ));
+                    }
+                    // setuptools has no regex patterns, but finds `__version__` in a module.
+                    Self::Pattern { path, pattern } if Self::finds_dunder_version(pattern) => {
+                        let module = path
+                            .trim_start_matches("src/")
+                            .trim_end_matches(".py")
+                            .trim_end_matches("/__init__")
+                            .replace('/', ".");
+                        version.insert(
+                            "attr".to_string(),
+                            string(&format!("{}.__version__", module)),
+                        );
+                    }
+                    Self::Pattern { path, .. } => {
+                        return Err(format!(
+                            "setuptools cannot search {} with a custom pattern",
+                            path
+                        ));
+                    }
+                }
+                let mut dynamic = toml::Table::new();
+                dynamic.insert("version".to_string(), toml::Value::Table(version));
+                let mut setuptools = toml::Table::new();
+                setuptools.insert("dynamic".to_string(), toml::Value::Table(dynamic));
+                tool.insert("setuptools".to_string(), toml::Value::Table(setuptools));
+            }
+        }
+        Ok(tool)
+    }
+
+    /// Whether a pattern finds `__version__`, as the default pattern does.
+    fn finds_dunder_version(pattern: &Option<String>) -> bool {
+        match pattern {
+            Some(pattern) => pattern.contains("__version__"),
+            None => true,
+        }
+    }
+
+    /// A pattern finding the string assigned to a module-level name.
+    pub fn attribute_pattern(name: &str) -> String {
+        format!(
+            r#"(?m)^{}\s*(?::\s*str\s*)?=\s*['"](?P<version>[^'"]+)['"]"#,
+            name
+        )
+    }
+
+    /// The default pattern used when a `Pattern` gives none.
+    pub fn default_pattern() -> &'static str {
+        DEFAULT_PATTERN
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn attribute(attr: &str) -> DynamicVersion {
+        DynamicVersion::Attribute {
+            attr: attr.to_string(),
+            path: None,
+        }
+    }
+
+    fn pattern(path: &str, pattern: Option<&str>) -> DynamicVersion {
+        DynamicVersion::Pattern {
+            path: path.to_string(),
+            pattern: pattern.map(|pattern| pattern.to_string()),
+        }
+    }
+
+    #[test]
+    fn setuptools_tool_table() {
+        let version = |source: DynamicVersion| {
+            source
+                .to_tool_table("setuptools.build_meta")
+                .map(|tool| tool["setuptools"]["dynamic"]["version"].to_string())
+        };
+        assert_eq!(
+            version(attribute("spam.VERSION")),
+            Ok(r#"{ attr = "spam.VERSION" }"#.to_string())
+        );
+        assert_eq!(
+            version(DynamicVersion::File("VERSION".to_string())),
+            Ok(r#"{ file = "VERSION" }"#.to_string())
+        );
+        assert!(version(DynamicVersion::File("spam/_version.py".to_string())).is_err());
+        // A module is read through its `__version__`, never as a version file.
+        assert_eq!(
+            version(pattern("src/spam/__init__.py", None)),
+            Ok(r#"{ attr = "spam.__version__" }"#.to_string())
+        );
+        assert_eq!(
+            version(pattern(
+                "spam/_version.py",
+                Some(&DynamicVersion::attribute_pattern("__version__"))
+            )),
+            Ok(r#"{ attr = "spam._version.__version__" }"#.to_string())
+        );
+        assert!(version(pattern("spam/_version.py", Some(r"VERSION = '(.+)'"))).is_err());
+        assert_eq!(
+            version(pattern("VERSION.txt", Some(r"(.+)"))),
+            Ok(r#"{ file = "VERSION.txt" }"#.to_string())
+        );
+    }
+
+    #[test]
+    fn pdm_tool_table() {
+        let version = |source: DynamicVersion| {
+            source
+                .to_tool_table("pdm.backend")
+                .map(|tool| tool["pdm"]["version"].to_string())
+        };
+        assert_eq!(
+            version(attribute("spam.__version__")),
+            Ok(r#"{ path = "spam/__init__.py", source = "file" }"#.to_string())
+        );
+        // pdm's file source finds nothing but `__version__`.
+        assert!(version(attribute("spam.VERSION")).is_err());
+        assert!(version(DynamicVersion::File("VERSION".to_string())).is_err());
+        assert_eq!(
+            version(pattern("spam/_version.py", None)),
+            Ok(r#"{ path = "spam/_version.py", source = "file" }"#.to_string())
+        );
+        assert!(version(pattern("spam/_version.py", Some(r"VERSION = '(.+)'"))).is_err());
+    }
+
+    #[test]
+    fn hatch_tool_table() {
+        let version = |source: DynamicVersion| {
+            source
+                .to_tool_table("hatchling.build")
+                .map(|tool| tool["hatch"]["version"].to_string())
+        };
+        assert_eq!(
+            version(attribute("spam.__version__")),
+            Ok(r#"{ path = "spam/__init__.py" }"#.to_string())
+        );
+        assert_eq!(
+            version(DynamicVersion::File("VERSION".to_string())),
+            Ok(r#"{ path = "VERSION", pattern = '(?P<version>\S+)' }"#.to_string())
+        );
+    }
+}
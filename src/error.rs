@@ -0,0 +1,72 @@
+use std::{fmt, io};
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Errors raised while reading, translating or writing a build specification.
+#[derive(Debug)]
+pub enum Error {
+    /// A file could not be read or written, and the error which caused it.
+    Io {
+        message: String,
+        source: Option<io::Error>,
+    },
+    /// A file could not be parsed, or is not of a known format.
+    Parse(String),
+    /// An expression in a setup.py could not be evaluated without running it.
+    UnsupportedExpression(String),
+    /// A requirement is not a PEP 508 dependency specifier.
+    InvalidRequirement(String),
+    /// An argument is not valid for the operation, e.g., a file for a directory.
+    InvalidArgument(String),
+    /// The operation is not supported between the given formats.
+    Unsupported(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io { message, .. }
+            | Error::Parse(message)
+            | Error::UnsupportedExpression(message)
+            | Error::InvalidRequirement(message)
+            | Error::InvalidArgument(message)
+            | Error::Unsupported(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io {
+                source: Some(source),
+                ..
+            } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Error::Io {
+            message: error.to_string(),
+            source: Some(error),
+        }
+    }
+}
+
+#[cfg(feature = "python")]
+impl From<Error> for pyo3::PyErr {
+    fn from(error: Error) -> Self {
+        use pyo3::exceptions::{PyNotImplementedError, PyOSError, PyValueError};
+        match error {
+            Error::Io { message, .. } => PyOSError::new_err(message),
+            Error::Unsupported(message) => PyNotImplementedError::new_err(message),
+            Error::Parse(message)
+            | Error::UnsupportedExpression(message)
+            | Error::InvalidRequirement(message)
+            | Error::InvalidArgument(message) => PyValueError::new_err(message),
+        }
+    }
+}
@@ -0,0 +1,924 @@
+//! Folds the constant expressions of a setup.py into values, so that
+//! arguments built with concatenation, formatting or comprehensions can be
+//! read without running the file.
+use crate::error::{Error, Result};
+use rustpython_parser::{ast, Parse};
+use std::collections::BTreeMap;
+
+/// Names followed before an expression is taken to refer to itself.
+const MAX_DEPTH: usize = 100;
+
+/// Longest string or list folded from repetition or concatenation, e.g.,
+/// `"=" * 80` or `BASE + ["rich"]`.
+const MAX_VALUE_LEN: usize = 1 << 16;
+
+/// A value folded from a constant expression. Tuples and sets are folded
+/// into lists, and dicts keep the order of their keys.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum PyValue {
+    None,
+    Bool(bool),
+    Int(i64),
+    Str(String),
+    List(Vec<PyValue>),
+    Dict(Vec<(PyValue, PyValue)>),
+}
+
+impl PyValue {
+    /// Formats the value as `str()` does.
+    pub(crate) fn to_str(&self) -> String {
+        match self {
+            Self::Str(s) => s.clone(),
+            value => value.repr(),
+        }
+    }
+
+    /// Formats the value as `repr()` does.
+    pub(crate) fn repr(&self) -> String {
+        match self {
+            Self::None => "None".to_string(),
+            Self::Bool(true) => "True".to_string(),
+            Self::Bool(false) => "False".to_string(),
+            Self::Int(i) => i.to_string(),
+            Self::Str(s) => {
+                let mut repr = String::from("'");
+                for c in s.chars() {
+                    match c {
+                        '\\' => repr.push_str("\\\\"),
+                        '\'' => repr.push_str("\\'"),
+                        '\n' => repr.push_str("\\n"),
+                        '\r' => repr.push_str("\\r"),
+                        '\t' => repr.push_str("\\t"),
+                        c if c.is_control() => repr.push_str(&format!("\\u{:04x}", c as u32)),
+                        c => repr.push(c),
+                    }
+                }
+                repr.push('\'');
+                repr
+            }
+            Self::List(values) => format!(
+                "[{}]",
+                values
+                    .iter()
+                    .map(|v| v.repr())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Self::Dict(items) => format!(
+                "{{{}}}",
+                items
+                    .iter()
+                    .map(|(k, v)| format!("{}: {}", k.repr(), v.repr()))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }
+    }
+
+    /// Returns the literal expression of the value, e.g., to bind it to a name.
+    pub(crate) fn to_expr(&self) -> Option<ast::Expr> {
+        ast::Expr::parse(&self.repr(), "<folded>").ok()
+    }
+
+    pub(crate) fn is_truthy(&self) -> bool {
+        match self {
+            Self::None => false,
+            Self::Bool(b) => *b,
+            Self::Int(i) => *i != 0,
+            Self::Str(s) => !s.is_empty(),
+            Self::List(values) => !values.is_empty(),
+            Self::Dict(items) => !items.is_empty(),
+        }
+    }
+
+    /// Returns the items iterated over by a `for` loop over the value.
+    fn iterate(self) -> Option<Vec<PyValue>> {
+        match self {
+            Self::List(values) => Some(values),
+            Self::Dict(items) => Some(items.into_iter().map(|(k, _)| k).collect()),
+            Self::Str(s) => Some(s.chars().map(|c| Self::Str(c.to_string())).collect()),
+            _ => None,
+        }
+    }
+}
+
+/// Evaluates expressions against the module-level assignments of a setup.py.
+pub(crate) struct Evaluator<'a> {
+    assignments: &'a BTreeMap<String, ast::Expr>,
+    /// Names bound by the comprehension being evaluated, shadowing assignments.
+    scope: BTreeMap<String, PyValue>,
+}
+
+impl<'a> Evaluator<'a> {
+    pub(crate) fn new(assignments: &'a BTreeMap<String, ast::Expr>) -> Self {
+        Self {
+            assignments,
+            scope: BTreeMap::new(),
+        }
+    }
+
+    pub(crate) fn evaluate(&self, expr: &ast::Expr) -> Result<PyValue> {
+        self.evaluate_at(expr, 0)
+    }
+
+    fn evaluate_at(&self, expr: &ast::Expr, depth: usize) -> Result<PyValue> {
+        if depth > MAX_DEPTH {
+            return Err(Self::unsupported(expr));
+        }
+        let eval = |expr: &ast::Expr| self.evaluate_at(expr, depth + 1);
+        match expr {
+            ast::Expr::Constant(constant) => {
+                Self::constant(&constant.value).ok_or_else(|| Self::unsupported(expr))
+            }
+            ast::Expr::Name(name) => {
+                if let Some(value) = self.scope.get(name.id.as_str()) {
+                    return Ok(value.clone());
+                }
+                match self.assignments.get(name.id.as_str()) {
+                    Some(value) => eval(value),
+                    None => Err(Self::unsupported(expr)),
+                }
+            }
+            ast::Expr::JoinedStr(joined) => {
+                let mut result = String::new();
+                for value in joined.values.iter() {
+                    match value {
+                        ast::Expr::FormattedValue(formatted) => {
+                            let spec = match &formatted.format_spec {
+                                Some(spec) => eval(spec)?.to_str(),
+                                None => String::new(),
+                            };
+                            let conversion = match formatted.conversion {
+                                ast::ConversionFlag::None => "",
+                                ast::ConversionFlag::Str => "s",
+                                ast::ConversionFlag::Repr => "r",
+                                ast::ConversionFlag::Ascii => "a",
+                            };
+                            let value = Self::convert(eval(&formatted.value)?, conversion, expr)?;
+                            result.push_str(&Self::format_value(&value, &spec, expr)?);
+                        }
+                        value => result.push_str(&eval(value)?.to_str()),
+                    }
+                }
+                Ok(PyValue::Str(result))
+            }
+            ast::Expr::List(ast::ExprList { elts, .. })
+            | ast::Expr::Tuple(ast::ExprTuple { elts, .. })
+            | ast::Expr::Set(ast::ExprSet { elts, .. }) => {
+                let mut values = Vec::new();
+                for elt in elts.iter() {
+                    match elt {
+                        ast::Expr::Starred(starred) => values.extend(
+                            eval(&starred.value)?
+                                .iterate()
+                                .ok_or_else(|| Self::unsupported(elt))?,
+                        ),
+                        elt => values.push(eval(elt)?),
+                    }
+                }
+                Ok(PyValue::List(values))
+            }
+            ast::Expr::Dict(dict) => {
+                let mut items = Vec::new();
+                for (key, value) in dict.keys.iter().zip(dict.values.iter()) {
+                    match key {
+                        Some(key) => Self::insert(&mut items, eval(key)?, eval(value)?),
+                        // `{**other}`
+                        None => match eval(value)? {
+                            PyValue::Dict(other) => {
+                                for (key, value) in other {
+                                    Self::insert(&mut items, key, value);
+                                }
+                            }
+                            _ => return Err(Self::unsupported(value)),
+                        },
+                    }
+                }
+                Ok(PyValue::Dict(items))
+            }
+            ast::Expr::BinOp(binop) => {
+                let (left, right) = (eval(&binop.left)?, eval(&binop.right)?);
+                match (binop.op, left, right) {
+                    (ast::Operator::Add, PyValue::Str(l), PyValue::Str(r)) => {
+                        if l.len() + r.len() > MAX_VALUE_LEN {
+                            return Err(Self::unsupported(expr));
+                        }
+                        Ok(PyValue::Str(l + &r))
+                    }
+                    (ast::Operator::Add, PyValue::List(mut l), PyValue::List(r)) => {
+                        if l.len() + r.len() > MAX_VALUE_LEN {
+                            return Err(Self::unsupported(expr));
+                        }
+                        l.extend(r);
+                        Ok(PyValue::List(l))
+                    }
+                    (ast::Operator::Add, PyValue::Int(l), PyValue::Int(r)) => l
+                        .checked_add(r)
+                        .map(PyValue::Int)
+                        .ok_or_else(|| Self::unsupported(expr)),
+                    (ast::Operator::Mult, PyValue::Str(s), PyValue::Int(n))
+                    | (ast::Operator::Mult, PyValue::Int(n), PyValue::Str(s)) => {
+                        let n = usize::try_from(n.max(0)).map_err(|_| Self::unsupported(expr))?;
+                        match s.len().checked_mul(n) {
+                            Some(len) if len <= MAX_VALUE_LEN => Ok(PyValue::Str(s.repeat(n))),
+                            _ => Err(Self::unsupported(expr)),
+                        }
+                    }
+                    (ast::Operator::BitOr, PyValue::Dict(mut l), PyValue::Dict(r)) => {
+                        for (key, value) in r {
+                            Self::insert(&mut l, key, value);
+                        }
+                        Ok(PyValue::Dict(l))
+                    }
+                    (ast::Operator::Mod, PyValue::Str(template), args) => {
+                        Ok(PyValue::Str(Self::percent_format(&template, args, expr)?))
+                    }
+                    _ => Err(Self::unsupported(expr)),
+                }
+            }
+            ast::Expr::Call(call) => self.call(call, expr, depth),
+            ast::Expr::ListComp(comp) => {
+                let mut values = Vec::new();
+                self.comprehend(&comp.elt, &comp.generators, &mut values, depth)?;
+                Ok(PyValue::List(values))
+            }
+            ast::Expr::Compare(compare) => {
+                let mut left = eval(&compare.left)?;
+                for (op, comparator) in compare.ops.iter().zip(compare.comparators.iter()) {
+                    let right = eval(comparator)?;
+                    let result = match op {
+                        ast::CmpOp::Eq => left == right,
+                        ast::CmpOp::NotEq => left != right,
+                        ast::CmpOp::In | ast::CmpOp::NotIn => {
+                            let contained = match (&left, &right) {
+                                (PyValue::Str(l), PyValue::Str(r)) => r.contains(l.as_str()),
+                                (value, PyValue::List(values)) => values.contains(value),
+                                (key, PyValue::Dict(items)) => items.iter().any(|(k, _)| k == key),
+                                _ => return Err(Self::unsupported(expr)),
+                            };
+                            contained == matches!(op, ast::CmpOp::In)
+                        }
+                        _ => return Err(Self::unsupported(expr)),
+                    };
+                    if !result {
+                        return Ok(PyValue::Bool(false));
+                    }
+                    left = right;
+                }
+                Ok(PyValue::Bool(true))
+            }
+            ast::Expr::BoolOp(boolop) => {
+                let mut value = PyValue::None;
+                for operand in boolop.values.iter() {
+                    value = eval(operand)?;
+                    let done = match boolop.op {
+                        ast::BoolOp::And => !value.is_truthy(),
+                        ast::BoolOp::Or => value.is_truthy(),
+                    };
+                    if done {
+                        break;
+                    }
+                }
+                Ok(value)
+            }
+            ast::Expr::UnaryOp(unary) if matches!(unary.op, ast::UnaryOp::Not) => {
+                Ok(PyValue::Bool(!eval(&unary.operand)?.is_truthy()))
+            }
+            ast::Expr::Subscript(subscript) => {
+                match (eval(&subscript.value)?, eval(&subscript.slice)?) {
+                    (PyValue::Dict(items), key) => items
+                        .into_iter()
+                        .find(|(k, _)| *k == key)
+                        .map(|(_, value)| value)
+                        .ok_or_else(|| Self::unsupported(expr)),
+                    (PyValue::List(values), PyValue::Int(i)) => {
+                        let i = if i < 0 { values.len() as i64 + i } else { i };
+                        usize::try_from(i)
+                            .ok()
+                            .and_then(|i| values.into_iter().nth(i))
+                            .ok_or_else(|| Self::unsupported(expr))
+                    }
+                    _ => Err(Self::unsupported(expr)),
+                }
+            }
+            ast::Expr::UnaryOp(unary) if matches!(unary.op, ast::UnaryOp::USub) => {
+                match eval(&unary.operand)? {
+                    PyValue::Int(i) => i
+                        .checked_neg()
+                        .map(PyValue::Int)
+                        .ok_or_else(|| Self::unsupported(expr)),
+                    _ => Err(Self::unsupported(expr)),
+                }
+            }
+            ast::Expr::IfExp(ifexp) => {
+                if eval(&ifexp.test)?.is_truthy() {
+                    eval(&ifexp.body)
+                } else {
+                    eval(&ifexp.orelse)
+                }
+            }
+            _ => Err(Self::unsupported(expr)),
+        }
+    }
+
+    fn call(&self, call: &ast::ExprCall, expr: &ast::Expr, depth: usize) -> Result<PyValue> {
+        let eval = |expr: &ast::Expr| self.evaluate_at(expr, depth + 1);
+        let mut args = Vec::new();
+        for arg in call.args.iter() {
+            match arg {
+                ast::Expr::Starred(starred) => args.extend(
+                    eval(&starred.value)?
+                        .iterate()
+                        .ok_or_else(|| Self::unsupported(arg))?,
+                ),
+                arg => args.push(eval(arg)?),
+            }
+        }
+        let mut kwargs = Vec::new();
+        for keyword in call.keywords.iter() {
+            match &keyword.arg {
+                Some(arg) => Self::insert(
+                    &mut kwargs,
+                    PyValue::Str(arg.to_string()),
+                    eval(&keyword.value)?,
+                ),
+                // `f(**other)`
+                None => match eval(&keyword.value)? {
+                    PyValue::Dict(other) => {
+                        for (key, value) in other {
+                            Self::insert(&mut kwargs, key, value);
+                        }
+                    }
+                    _ => return Err(Self::unsupported(&keyword.value)),
+                },
+            }
+        }
+        match call.func.as_ref() {
+            ast::Expr::Name(name) => match (name.id.as_str(), args.as_slice()) {
+                ("dict", []) => Ok(PyValue::Dict(kwargs)),
+                ("dict", [PyValue::Dict(items)]) => {
+                    let mut items = items.clone();
+                    for (key, value) in kwargs {
+                        Self::insert(&mut items, key, value);
+                    }
+                    Ok(PyValue::Dict(items))
+                }
+                ("dict", [PyValue::List(pairs)]) => {
+                    let mut items = Vec::new();
+                    for pair in pairs.iter() {
+                        match pair {
+                            PyValue::List(pair) if pair.len() == 2 => {
+                                Self::insert(&mut items, pair[0].clone(), pair[1].clone())
+                            }
+                            _ => return Err(Self::unsupported(expr)),
+                        }
+                    }
+                    for (key, value) in kwargs {
+                        Self::insert(&mut items, key, value);
+                    }
+                    Ok(PyValue::Dict(items))
+                }
+                ("list" | "tuple", []) => Ok(PyValue::List(Vec::new())),
+                ("list" | "tuple", [value]) => Ok(PyValue::List(
+                    value
+                        .clone()
+                        .iterate()
+                        .ok_or_else(|| Self::unsupported(expr))?,
+                )),
+                ("sorted", [value]) => {
+                    let mut values = value
+                        .clone()
+                        .iterate()
+                        .ok_or_else(|| Self::unsupported(expr))?;
+                    if values.iter().all(|value| matches!(value, PyValue::Str(_))) {
+                        values.sort_by_key(|value| value.to_str());
+                        return Ok(PyValue::List(values));
+                    }
+                    Err(Self::unsupported(expr))
+                }
+                ("str", [value]) => Ok(PyValue::Str(value.to_str())),
+                _ => Err(Self::unsupported(expr)),
+            },
+            ast::Expr::Attribute(method) => {
+                let receiver = eval(&method.value)?;
+                self.call_method(receiver, method.attr.as_str(), args, kwargs, expr)
+            }
+            _ => Err(Self::unsupported(expr)),
+        }
+    }
+
+    fn call_method(
+        &self,
+        receiver: PyValue,
+        method: &str,
+        args: Vec<PyValue>,
+        kwargs: Vec<(PyValue, PyValue)>,
+        expr: &ast::Expr,
+    ) -> Result<PyValue> {
+        // Arguments which are not folded, e.g., `startswith(prefix, start)`,
+        // fail rather than being ignored.
+        let max_args = match method {
+            "format" => usize::MAX,
+            "join" | "strip" | "lstrip" | "rstrip" | "startswith" | "endswith" => 1,
+            "split" => 2,
+            "replace" => 3,
+            _ => 0,
+        };
+        if args.len() > max_args || (method != "format" && !kwargs.is_empty()) {
+            return Err(Self::unsupported(expr));
+        }
+        let s = match (&receiver, method) {
+            (PyValue::Dict(items), "items") => {
+                return Ok(PyValue::List(
+                    items
+                        .iter()
+                        .map(|(k, v)| PyValue::List(vec![k.clone(), v.clone()]))
+                        .collect(),
+                ))
+            }
+            (PyValue::Dict(items), "keys") => {
+                return Ok(PyValue::List(
+                    items.iter().map(|(k, _)| k.clone()).collect(),
+                ))
+            }
+            (PyValue::Dict(items), "values") => {
+                return Ok(PyValue::List(
+                    items.iter().map(|(_, v)| v.clone()).collect(),
+                ))
+            }
+            (PyValue::Str(s), _) => s,
+            _ => return Err(Self::unsupported(expr)),
+        };
+        let string_arg = |i: usize| match args.get(i) {
+            Some(PyValue::Str(arg)) => Ok(Some(arg.as_str())),
+            Some(PyValue::None) | None => Ok(None),
+            Some(_) => Err(Self::unsupported(expr)),
+        };
+        // A negative count, as by default, means no limit.
+        let count_arg = |i: usize| match args.get(i) {
+            Some(PyValue::Int(count)) => Ok(usize::try_from(*count).ok()),
+            None => Ok(None),
+            Some(_) => Err(Self::unsupported(expr)),
+        };
+        let value = match method {
+            "format" => PyValue::Str(Self::format(s, &args, &kwargs, expr)?),
+            "join" => {
+                let values = args
+                    .first()
+                    .cloned()
+                    .and_then(PyValue::iterate)
+                    .ok_or_else(|| Self::unsupported(expr))?;
+                let mut parts = Vec::new();
+                for value in values {
+                    match value {
+                        PyValue::Str(part) => parts.push(part),
+                        _ => return Err(Self::unsupported(expr)),
+                    }
+                }
+                PyValue::Str(parts.join(s))
+            }
+            "strip" | "lstrip" | "rstrip" => {
+                let chars = string_arg(0)?;
+                let is_stripped = |c: char| match chars {
+                    Some(chars) => chars.contains(c),
+                    None => c.is_whitespace(),
+                };
+                PyValue::Str(
+                    match method {
+                        "strip" => s.trim_matches(is_stripped),
+                        "lstrip" => s.trim_start_matches(is_stripped),
+                        _ => s.trim_end_matches(is_stripped),
+                    }
+                    .to_string(),
+                )
+            }
+            "lower" => PyValue::Str(s.to_lowercase()),
+            "upper" => PyValue::Str(s.to_uppercase()),
+            "replace" => match (string_arg(0)?, string_arg(1)?, count_arg(2)?) {
+                (Some(from), Some(to), Some(count)) => PyValue::Str(s.replacen(from, to, count)),
+                (Some(from), Some(to), None) => PyValue::Str(s.replace(from, to)),
+                _ => return Err(Self::unsupported(expr)),
+            },
+            "split" => PyValue::List(
+                match (string_arg(0)?, count_arg(1)?) {
+                    (Some(""), _) => return Err(Self::unsupported(expr)),
+                    (Some(separator), Some(max)) => {
+                        s.splitn(max + 1, separator).collect::<Vec<_>>()
+                    }
+                    (Some(separator), None) => s.split(separator).collect::<Vec<_>>(),
+                    (None, max) => Self::split_whitespace(s, max),
+                }
+                .into_iter()
+                .map(|part| PyValue::Str(part.to_string()))
+                .collect(),
+            ),
+            "splitlines" => PyValue::List(
+                s.lines()
+                    .map(|line| PyValue::Str(line.to_string()))
+                    .collect(),
+            ),
+            "startswith" | "endswith" => {
+                let affixes = match args.first() {
+                    Some(PyValue::Str(affix)) => vec![affix.clone()],
+                    Some(PyValue::List(affixes)) => affixes.iter().map(|a| a.to_str()).collect(),
+                    _ => return Err(Self::unsupported(expr)),
+                };
+                PyValue::Bool(affixes.iter().any(|affix| {
+                    if method == "startswith" {
+                        s.starts_with(affix.as_str())
+                    } else {
+                        s.ends_with(affix.as_str())
+                    }
+                }))
+            }
+            _ => return Err(Self::unsupported(expr)),
+        };
+        Ok(value)
+    }
+
+    /// Splits on runs of whitespace as `str.split()` does, at most `max` times,
+    /// leaving the rest, with its trailing whitespace, as the last part.
+    fn split_whitespace(s: &str, max: Option<usize>) -> Vec<&str> {
+        let mut parts = Vec::new();
+        let mut rest = s.trim_start();
+        while !rest.is_empty() && !matches!(max, Some(max) if parts.len() >= max) {
+            match rest.find(char::is_whitespace) {
+                Some(end) => {
+                    parts.push(&rest[..end]);
+                    rest = rest[end..].trim_start();
+                }
+                None => {
+                    parts.push(rest);
+                    rest = "";
+                }
+            }
+        }
+        if !rest.is_empty() {
+            parts.push(rest);
+        }
+        parts
+    }
+
+    /// Appends the values of `elt` for each combination of the generators'
+    /// items which passes their conditions.
+    fn comprehend(
+        &self,
+        elt: &ast::Expr,
+        generators: &[ast::Comprehension],
+        values: &mut Vec<PyValue>,
+        depth: usize,
+    ) -> Result<()> {
+        let Some((generator, rest)) = generators.split_first() else {
+            values.push(self.evaluate_at(elt, depth + 1)?);
+            return Ok(());
+        };
+        let items = self
+            .evaluate_at(&generator.iter, depth + 1)?
+            .iterate()
+            .ok_or_else(|| Self::unsupported(&generator.iter))?;
+        'items: for item in items {
+            let mut inner = Evaluator {
+                assignments: self.assignments,
+                scope: self.scope.clone(),
+            };
+            inner.bind(&generator.target, item)?;
+            for condition in generator.ifs.iter() {
+                if !inner.evaluate_at(condition, depth + 1)?.is_truthy() {
+                    continue 'items;
+                }
+            }
+            inner.comprehend(elt, rest, values, depth)?;
+        }
+        Ok(())
+    }
+
+    fn bind(&mut self, target: &ast::Expr, value: PyValue) -> Result<()> {
+        match (target, value) {
+            (ast::Expr::Name(name), value) => {
+                self.scope.insert(name.id.to_string(), value);
+                Ok(())
+            }
+            (
+                ast::Expr::Tuple(ast::ExprTuple { elts, .. })
+                | ast::Expr::List(ast::ExprList { elts, .. }),
+                PyValue::List(values),
+            ) if elts.len() == values.len() => {
+                for (elt, value) in elts.iter().zip(values) {
+                    self.bind(elt, value)?;
+                }
+                Ok(())
+            }
+            (target, _) => Err(Self::unsupported(target)),
+        }
+    }
+
+    /// Formats a string as `str.format()` does, with the conversions of
+    /// `convert` and the format specs of `format_value`.
+    fn format(
+        template: &str,
+        args: &[PyValue],
+        kwargs: &[(PyValue, PyValue)],
+        expr: &ast::Expr,
+    ) -> Result<String> {
+        let mut result = String::new();
+        let mut chars = template.chars().peekable();
+        let mut next_index = 0;
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    result.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    result.push('}');
+                }
+                '{' => {
+                    let mut field = String::new();
+                    for c in chars.by_ref() {
+                        if c == '}' {
+                            break;
+                        }
+                        field.push(c);
+                    }
+                    let (field, spec) = field.split_once(':').unwrap_or((&field, ""));
+                    let (field, conversion) = field.split_once('!').unwrap_or((field, ""));
+                    let value = if field.is_empty() {
+                        next_index += 1;
+                        args.get(next_index - 1)
+                    } else if let Ok(index) = field.parse::<usize>() {
+                        args.get(index)
+                    } else {
+                        kwargs
+                            .iter()
+                            .find(|(key, _)| key.to_str() == field)
+                            .map(|(_, value)| value)
+                    };
+                    let value = value.ok_or_else(|| Self::unsupported(expr))?;
+                    let value = Self::convert(value.clone(), conversion, expr)?;
+                    result.push_str(&Self::format_value(&value, spec, expr)?);
+                }
+                c => result.push(c),
+            }
+        }
+        Ok(result)
+    }
+
+    /// Formats a string as the `%` operator does, for `%s`, `%r`, `%d` and
+    /// `%(name)s` conversions.
+    fn percent_format(template: &str, args: PyValue, expr: &ast::Expr) -> Result<String> {
+        let (args, mapping) = match args {
+            PyValue::List(args) => (args, Vec::new()),
+            PyValue::Dict(items) => (Vec::new(), items),
+            arg => (vec![arg], Vec::new()),
+        };
+        let mut args = args.into_iter();
+        let mut result = String::new();
+        let mut chars = template.chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                result.push(c);
+                continue;
+            }
+            let mut conversion = chars.next().ok_or_else(|| Self::unsupported(expr))?;
+            let value = if conversion == '%' {
+                result.push('%');
+                continue;
+            } else if conversion == '(' {
+                let key = chars.by_ref().take_while(|c| *c != ')').collect::<String>();
+                conversion = chars.next().ok_or_else(|| Self::unsupported(expr))?;
+                mapping
+                    .iter()
+                    .find(|(k, _)| k.to_str() == key)
+                    .map(|(_, value)| value.clone())
+            } else {
+                args.next()
+            };
+            let value = value.ok_or_else(|| Self::unsupported(expr))?;
+            match (conversion, &value) {
+                ('s', value) => result.push_str(&value.to_str()),
+                ('r', value) => result.push_str(&value.repr()),
+                ('d' | 'i', PyValue::Int(i)) => result.push_str(&i.to_string()),
+                _ => return Err(Self::unsupported(expr)),
+            }
+        }
+        Ok(result)
+    }
+
+    /// Applies a `!s`, `!r` or `!a` conversion. `!r` and `!a` only convert
+    /// values whose repr Python writes alike, e.g., not a string holding a
+    /// quote, which Python writes in double quotes.
+    fn convert(value: PyValue, conversion: &str, expr: &ast::Expr) -> Result<PyValue> {
+        match conversion {
+            "" => Ok(value),
+            "s" => Ok(PyValue::Str(value.to_str())),
+            "r" | "a" if Self::has_plain_repr(&value) => Ok(PyValue::Str(value.repr())),
+            _ => Err(Self::unsupported(expr)),
+        }
+    }
+
+    fn has_plain_repr(value: &PyValue) -> bool {
+        match value {
+            PyValue::Str(s) => !s.chars().any(|c| {
+                c == '\'' || !c.is_ascii() || (c.is_control() && !matches!(c, '\n' | '\r' | '\t'))
+            }),
+            PyValue::List(values) => values.iter().all(Self::has_plain_repr),
+            PyValue::Dict(items) => items
+                .iter()
+                .all(|(k, v)| Self::has_plain_repr(k) && Self::has_plain_repr(v)),
+            _ => true,
+        }
+    }
+
+    /// Applies a format spec, supporting only the empty spec and `s`/`d`.
+    fn format_value(value: &PyValue, spec: &str, expr: &ast::Expr) -> Result<String> {
+        match (spec, value) {
+            ("" | "s", value) => Ok(value.to_str()),
+            ("d", PyValue::Int(i)) => Ok(i.to_string()),
+            _ => Err(Self::unsupported(expr)),
+        }
+    }
+
+    fn constant(constant: &ast::Constant) -> Option<PyValue> {
+        match constant {
+            ast::Constant::None => Some(PyValue::None),
+            ast::Constant::Bool(b) => Some(PyValue::Bool(*b)),
+            ast::Constant::Str(s) => Some(PyValue::Str(s.clone())),
+            ast::Constant::Int(i) => i.to_string().parse().ok().map(PyValue::Int),
+            ast::Constant::Tuple(values) => values
+                .iter()
+                .map(Self::constant)
+                .collect::<Option<Vec<_>>>()
+                .map(PyValue::List),
+            _ => None,
+        }
+    }
+
+    /// Inserts into a dict, replacing the value of an existing key in place.
+    fn insert(items: &mut Vec<(PyValue, PyValue)>, key: PyValue, value: PyValue) {
+        match items.iter_mut().find(|(k, _)| *k == key) {
+            Some(item) => item.1 = value,
+            None => items.push((key, value)),
+        }
+    }
+
+    fn unsupported(expr: &ast::Expr) -> Error {
+        Error::UnsupportedExpression(format!("Failed to evaluate Expr:\n{expr:#?}"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rustpython_parser::Parse;
+
+    /// Evaluates the last assignment of a module against those before it.
+    fn evaluate(source: &str) -> Result<PyValue> {
+        let statements = ast::Suite::parse(source, "<test>").unwrap();
+        let mut assignments = BTreeMap::<String, ast::Expr>::new();
+        let mut last = None;
+        for statement in statements {
+            if let ast::Stmt::Assign(assign) = statement {
+                if let ast::Expr::Name(target) = &assign.targets[0] {
+                    assignments.insert(target.id.to_string(), *assign.value.clone());
+                    last = Some(*assign.value);
+                }
+            }
+        }
+        Evaluator::new(&assignments).evaluate(&last.unwrap())
+    }
+
+    fn strings(values: &[&str]) -> PyValue {
+        PyValue::List(values.iter().map(|v| PyValue::Str(v.to_string())).collect())
+    }
+
+    #[test]
+    fn fold_strings() {
+        let source = r#"
+NAME = "spam"
+VERSION = (1, 4, 2)
+x = "%s-%d.%d.%d" % ((NAME,) + VERSION) + " {0} {name}!".format("lovely", name=NAME.upper())
+"#;
+        assert_eq!(
+            evaluate(source).unwrap(),
+            PyValue::Str("spam-1.4.2 lovely SPAM!".to_string())
+        );
+        let source = r#"
+parts = ["1", "4", "2"]
+x = ".".join(parts) + f"{'-dev' if False else ''}"
+"#;
+        assert_eq!(evaluate(source).unwrap(), PyValue::Str("1.4.2".to_string()));
+        assert_eq!(
+            evaluate("x = '%(name)s==%(version)s' % dict(name='spam', version='1.0')").unwrap(),
+            PyValue::Str("spam==1.0".to_string())
+        );
+    }
+
+    #[test]
+    fn reject_unbounded_values() {
+        assert_eq!(
+            evaluate("x = '=' * 3").unwrap(),
+            PyValue::Str("===".to_string())
+        );
+        assert!(evaluate("x = 'spam' * 9223372036854775807").is_err());
+        assert!(evaluate("x = 9223372036854775807 + 1").is_err());
+        assert!(evaluate("MIN = -9223372036854775807 + -1\nx = MIN").is_ok());
+        assert!(evaluate("MIN = -9223372036854775807 + -1\nx = -MIN").is_err());
+
+        // Concatenation is bounded as repetition is, e.g., when doubling.
+        assert!(evaluate("a = '=' * 65536\nx = a + '='").is_err());
+        let mut source = "a0 = [1]\n".to_string();
+        for i in 1..=17 {
+            source.push_str(&format!("a{} = a{} + a{}\n", i, i - 1, i - 1));
+        }
+        assert!(evaluate(&format!("{}x = a16", source)).is_ok());
+        assert!(evaluate(&format!("{}x = a17", source)).is_err());
+    }
+
+    #[test]
+    fn fold_collections() {
+        let source = r#"
+BASE = ["requests", "click"]
+EXTRA = ("rich",)
+x = [*BASE, *EXTRA] + ["pyyaml"]
+"#;
+        assert_eq!(
+            evaluate(source).unwrap(),
+            strings(&["requests", "click", "rich", "pyyaml"])
+        );
+        let source = r#"
+names = ["spam", "eggs", "ham"]
+x = [f"{name}>=1.0" for name in names if name != "eggs"]
+"#;
+        assert_eq!(
+            evaluate(source).unwrap(),
+            strings(&["spam>=1.0", "ham>=1.0"])
+        );
+        let source = r#"
+base = {"test": ["pytest"]}
+x = dict(base, docs=["sphinx"], **{"test": ["pytest", "hypothesis"]})
+"#;
+        assert_eq!(
+            evaluate(source).unwrap(),
+            PyValue::Dict(vec![
+                (
+                    PyValue::Str("test".to_string()),
+                    strings(&["pytest", "hypothesis"])
+                ),
+                (PyValue::Str("docs".to_string()), strings(&["sphinx"])),
+            ])
+        );
+        let source = r#"
+extras = {"test": ["pytest"], "docs": ["sphinx"]}
+x = [r for _, reqs in extras.items() for r in reqs]
+"#;
+        assert_eq!(evaluate(source).unwrap(), strings(&["pytest", "sphinx"]));
+    }
+
+    #[test]
+    fn round_trip_literals() {
+        let value = PyValue::Dict(vec![(
+            PyValue::Str("it's\n\\spam\t".to_string()),
+            PyValue::List(vec![PyValue::Int(-1), PyValue::Bool(true), PyValue::None]),
+        )]);
+        let expr = value.to_expr().unwrap();
+        let assignments = BTreeMap::new();
+        assert_eq!(Evaluator::new(&assignments).evaluate(&expr).unwrap(), value);
+    }
+
+    #[test]
+    fn fold_method_arguments() {
+        assert_eq!(
+            evaluate("x = 'a==1==2'.split('==', 1)").unwrap(),
+            strings(&["a", "1==2"])
+        );
+        assert_eq!(
+            evaluate("x = '  a  b  c '.split(None, 1)").unwrap(),
+            strings(&["a", "b  c "])
+        );
+        assert_eq!(
+            evaluate("V = '1.0.0'\nx = V.replace('.', '', 1)").unwrap(),
+            PyValue::Str("10.0".to_string())
+        );
+        assert_eq!(
+            evaluate("NAME = 'spam'\nx = f'{NAME!r} {NAME!s}' + ' {!r}'.format([NAME, 1])")
+                .unwrap(),
+            PyValue::Str("'spam' spam ['spam', 1]".to_string())
+        );
+    }
+
+    #[test]
+    fn reject_unsupported() {
+        assert!(evaluate("x = open('requirements.txt').read()").is_err());
+        assert!(evaluate("x = x + 'a'").is_err());
+        assert!(evaluate("x = '%d' % 'a'").is_err());
+        // Arguments are never ignored.
+        assert!(evaluate("x = 'a b'.split(maxsplit=1)").is_err());
+        assert!(evaluate("x = 'spam'.startswith('s', 1)").is_err());
+        assert!(evaluate("x = 'spam'.upper(1)").is_err());
+        assert!(evaluate("x = {'a': 1}.items(1)").is_err());
+        // Python would quote these reprs otherwise.
+        assert!(evaluate("S = \"it's\"\nx = f'{S!r}'").is_err());
+        assert!(evaluate("x = f'{\"café\"!a}'").is_err());
+    }
+}
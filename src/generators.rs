@@ -1,37 +1,282 @@
-use pyo3::exceptions::PyOSError;
-use pyo3::PyResult;
 use std::collections::BTreeMap;
 use std::fs;
-use std::path::Path;
+use std::io::{self, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 
+use crate::compare;
+use crate::dynamic::DynamicVersion;
+use crate::error::{Error, Result};
+use crate::layout::PackageLayout;
+use crate::parsers::SetupParser;
+use crate::pep508;
 use crate::specs::*;
+use crate::utils;
 
 pub struct RequirementsGenerator;
 pub struct SetupGenerator;
 pub struct PyProjectGenerator;
+pub struct CondaEnvironmentGenerator;
+pub struct MetadataGenerator;
 
 pub trait SpecGenerator<T> {
-    fn make_file(path: &Path, spec: &T) -> PyResult<()>;
+    fn make_string(spec: &T) -> Result<String>;
+
+    /// Writes the build specification to `path`. An existing file is only
+    /// replaced as given by `options`.
+    fn make_file(path: &Path, spec: &T, options: &WriteOptions) -> Result<()> {
+        write_file(path, &Self::make_string(spec)?, options)
+    }
+
+    /// Merges the requirements of the build specification into `contents`,
+    /// those of an existing file of its format, leaving the rest as it is.
+    fn merge_string(_contents: &str, _spec: &T) -> Result<String> {
+        Err(Error::Unsupported(
+            "Failed to perform operation. Only requirements.txt, setup.py and pyproject.toml can be synced.".to_string(),
+        ))
+    }
+}
+
+/// How a file which already exists at the destination is treated.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct WriteOptions {
+    /// Overwrite the existing file, rather than refusing to.
+    pub force: bool,
+    /// Keep a copy of the existing file as `<name>.bak` before overwriting it.
+    pub backup: bool,
+}
+
+/// Writes `contents` to `path` through a temporary file next to it, which is
+/// moved into place once complete, so an interrupted write never leaves a
+/// truncated file behind. A symlink at `path` is written through rather than
+/// replaced. Where hard links are unsupported, a new file is written in place.
+pub fn write_file(path: &Path, contents: &str, options: &WriteOptions) -> Result<()> {
+    let path = &resolve_symlink(path);
+    let existing = fs::metadata(path).ok();
+    if let Some(metadata) = &existing {
+        if !options.force {
+            return Err(already_exists(path));
+        }
+        if options.backup && metadata.is_file() {
+            fs::copy(path, with_suffix(path, ".bak"))?;
+        }
+    }
+    let temporary = with_suffix(path, &format!(".{}.tmp", std::process::id()));
+    let written = fs::write(&temporary, contents).and_then(|_| {
+        if let Some(metadata) = &existing {
+            fs::set_permissions(&temporary, metadata.permissions())?;
+        }
+        if options.force {
+            return fs::rename(&temporary, path);
+        }
+        // Unlike a rename, a link never replaces a file created since `path`
+        // was found not to exist.
+        match fs::hard_link(&temporary, path) {
+            Ok(()) => fs::remove_file(&temporary),
+            // Some filesystems have no hard links, e.g., FAT or some network
+            // shares, where the file is created exclusively instead.
+            Err(error)
+                if matches!(
+                    error.kind(),
+                    io::ErrorKind::Unsupported | io::ErrorKind::PermissionDenied
+                ) =>
+            {
+                fs::remove_file(&temporary)?;
+                let mut file = fs::OpenOptions::new()
+                    .write(true)
+                    .create_new(true)
+                    .open(path)?;
+                file.write_all(contents.as_bytes())
+            }
+            Err(error) => Err(error),
+        }
+    });
+    match written {
+        Ok(()) => Ok(()),
+        Err(error) => {
+            let _ = fs::remove_file(&temporary);
+            match error.kind() {
+                io::ErrorKind::AlreadyExists => Err(already_exists(path)),
+                _ => Err(error.into()),
+            }
+        }
+    }
+}
+
+fn already_exists(path: &Path) -> Error {
+    Error::InvalidArgument(format!(
+        "Failed to perform operation. {} already exists.",
+        path.display()
+    ))
+}
+
+/// Returns the file a symlink at `path` points to, even one which does not
+/// exist yet, or else `path` itself.
+fn resolve_symlink(path: &Path) -> PathBuf {
+    match fs::read_link(path) {
+        Ok(target) => {
+            fs::canonicalize(path).unwrap_or_else(|_| utils::directory_of(path).join(target))
+        }
+        Err(_) => path.to_path_buf(),
+    }
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(suffix);
+    PathBuf::from(path)
 }
 
 trait SetupKwarg {
     fn as_kwarg_string(&self, kw: &str) -> String;
 }
 
+impl RequirementsGenerator {
+    /// Writes a requirement with its hashes, followed by `comment` (e.g.,
+    /// `"  # pinned"`) and the requirements it is pulled in via, if known.
+    fn push_requirement(
+        contents: &mut String,
+        spec: &Requirements,
+        requirement: &str,
+        comment: &str,
+    ) {
+        contents.push_str(requirement);
+        let hashes = Requirements::hash_key(requirement).and_then(|key| spec.hashes.get(&key));
+        if let Some(hashes) = hashes {
+            for hash in hashes.iter() {
+                contents.push_str(&format!(" \\\n    --hash={}", hash));
+            }
+        }
+        contents.push_str(comment);
+        contents.push('\n');
+        match spec.via.get(requirement).map(|v| v.as_slice()) {
+            Some([source]) => contents.push_str(&format!("    # via {}\n", source)),
+            Some(sources) if !sources.is_empty() => {
+                contents.push_str("    # via\n");
+                for source in sources.iter() {
+                    contents.push_str(&format!("    #   {}\n", source));
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Identifies a requirement by its normalized name and marker, or an option,
+/// e.g., `-e .`, by its text, as a requirements.txt is merged.
+fn requirement_key(requirement: &str) -> String {
+    match pep508::parse_requirement(requirement) {
+        Some(requirement) => {
+            let requirement = compare::normalize_requirement(&requirement);
+            match requirement.marker {
+                Some(marker) => format!("{}; {}", requirement.name, marker),
+                None => requirement.name,
+            }
+        }
+        None => requirement
+            .split_whitespace()
+            .collect::<Vec<&str>>()
+            .join(" "),
+    }
+}
+
 impl SpecGenerator<Requirements> for RequirementsGenerator {
-    fn make_file(path: &Path, spec: &Requirements) -> PyResult<()> {
+    fn make_string(spec: &Requirements) -> Result<String> {
         let mut contents = String::new();
         for requirement in spec.requires.iter() {
-            contents.push_str(&requirement);
-            contents.push_str("\n");
+            Self::push_requirement(&mut contents, spec, requirement, "");
+        }
+        Ok(contents)
+    }
+
+    /// Replaces the requirements in place, keeping the inline comment of any
+    /// which is still required, and adds the others after the last of them.
+    /// Comments and options, e.g., `--index-url`, are kept as they are, but
+    /// for the `# via` annotations of pip-compile, which are written anew.
+    fn merge_string(contents: &str, spec: &Requirements) -> Result<String> {
+        // Requirements of the spec yet to be written, keyed as they are matched.
+        let mut pending = spec
+            .requires
+            .iter()
+            .map(|requirement| Some((requirement_key(requirement), requirement)))
+            .collect::<Vec<_>>();
+        let mut take = |key: &str| {
+            pending
+                .iter_mut()
+                .find(|entry| entry.as_ref().is_some_and(|(other, _)| other == key))
+                .and_then(|entry| entry.take())
+                .map(|(_, requirement)| requirement)
+        };
+
+        let mut merged = String::new();
+        let mut requirements_end = None;
+        let (mut annotated, mut in_via_block) = (false, false);
+        let mut lines = contents.split_inclusive('\n');
+        while let Some(first) = lines.next() {
+            // A line continued with `\\` is kept whole, e.g., an option and its value.
+            let mut line = first.to_string();
+            while line.trim_end().ends_with('\\') {
+                match lines.next() {
+                    Some(next) => line.push_str(next),
+                    None => break,
+                }
+            }
+            let trimmed = line.trim();
+            if trimmed.starts_with('#') {
+                let comment = trimmed.trim_start_matches('#').trim();
+                if annotated && comment == "via" {
+                    in_via_block = true;
+                    continue;
+                }
+                if annotated
+                    && (comment.starts_with("via ") || in_via_block && trimmed.starts_with("#   "))
+                {
+                    continue;
+                }
+            }
+            (annotated, in_via_block) = (false, false);
+            let joined = line.replace("\\\r\n", " ").replace("\\\n", " ");
+            let (entry, comment) = match joined.find(" #") {
+                Some(start) => {
+                    let start = joined[..start].trim_end().len();
+                    (&joined[..start], joined[start..].trim_end())
+                }
+                None => (joined.as_str(), ""),
+            };
+            let entry = entry
+                .split_whitespace()
+                .filter(|token| !token.starts_with("--hash="))
+                .collect::<Vec<&str>>()
+                .join(" ");
+            if entry.is_empty() || entry.starts_with('#') || entry.starts_with('-') {
+                // An option which the spec also holds is not written twice.
+                if entry.starts_with('-') {
+                    take(&requirement_key(&entry));
+                }
+                merged.push_str(&line);
+                if !line.ends_with('\n') {
+                    merged.push('\n');
+                }
+                continue;
+            }
+            if let Some(requirement) = take(&requirement_key(&entry)) {
+                Self::push_requirement(&mut merged, spec, requirement, comment);
+            }
+            annotated = true;
+            requirements_end = Some(merged.len());
         }
-        fs::write(path, contents)?;
-        Ok(())
+
+        let mut added = String::new();
+        for (_, requirement) in pending.iter().flatten() {
+            Self::push_requirement(&mut added, spec, requirement, "");
+        }
+        merged.insert_str(requirements_end.unwrap_or(merged.len()), &added);
+        Ok(merged)
     }
 }
 
 impl SpecGenerator<Setup> for SetupGenerator {
-    fn make_file(path: &Path, spec: &Setup) -> PyResult<()> {
+    fn make_string(spec: &Setup) -> Result<String> {
         let mut contents = String::new();
         let docstring_end = if spec.package_name.as_ref().is_some_and(|s| !s.is_empty()) {
             format!(" for {}", &spec.package_name.as_ref().unwrap())
@@ -42,15 +287,51 @@ impl SpecGenerator<Setup> for SetupGenerator {
             r#""""Installation configuration and package metadata{}.""""#,
             docstring_end
         );
-        let imports = "from setuptools import setup";
+        let mut imports = match &spec.layout {
+            Some(layout) if !layout.packages.is_empty() && layout.namespaces => {
+                "from setuptools import find_namespace_packages, setup"
+            }
+            Some(layout) if !layout.packages.is_empty() => {
+                "from setuptools import find_packages, setup"
+            }
+            _ => "from setuptools import setup",
+        }
+        .to_string();
+        // A version which could not be resolved is read when the package is built.
+        let version = match (&spec.version, &spec.dynamic_version) {
+            (None, Some(dynamic_version)) => {
+                // Only a file holding nothing but the version is read without a regex.
+                if !matches!(dynamic_version, DynamicVersion::File(_)) {
+                    imports.insert_str(0, "import re\n\n");
+                }
+                dynamic_version.as_kwarg_string("version")
+            }
+            _ => spec.version.as_kwarg_string("version"),
+        };
+        // Requirements read from a file are read from it again when built.
+        let install_requires = match &spec.requirements_file {
+            Some(requirements_file) => format!(
+                "install_requires=open({:?}).read().splitlines()",
+                requirements_file
+            ),
+            None => spec.install_requires.as_kwarg_string("install_requires"),
+        };
         let mut setup_call = String::from("    setup(\n");
         let kwargs: Vec<String> = vec![
-            spec.package_name.as_kwarg_string("package_name"),
-            spec.version.as_kwarg_string("version"),
-            spec.install_requires.as_kwarg_string("install_requires"),
+            spec.package_name.as_kwarg_string("name"),
+            version,
+            spec.description.as_kwarg_string("description"),
+            spec.readme.as_kwarg_string("long_description"),
+            spec.python_requires.as_kwarg_string("python_requires"),
+            spec.project_urls.as_kwarg_string("project_urls"),
+            install_requires,
             spec.setup_requires.as_kwarg_string("setup_requires"),
-            spec.extra_requires.as_kwarg_string("extra_requires"),
+            spec.extra_requires.as_kwarg_string("extras_require"),
             spec.entry_points.as_kwarg_string("entry_points"),
+            spec.layout.as_kwarg_string("packages"),
+            spec.layout.as_kwarg_string("package_dir"),
+            spec.layout.as_kwarg_string("py_modules"),
+            spec.layout.as_kwarg_string("package_data"),
         ];
         for kwarg in kwargs.iter() {
             if kwarg.is_empty() {
@@ -68,8 +349,127 @@ impl SpecGenerator<Setup> for SetupGenerator {
         contents.push_str(&entrypoint);
         contents.push_str("\n");
         contents.push_str(&setup_call);
-        fs::write(path, contents)?;
-        Ok(())
+        Ok(contents)
+    }
+
+    /// Replaces the values of `install_requires` and `extras_require` given to
+    /// the `setup()` call, or adds them to it. Extras are replaced as a whole,
+    /// so those the spec no longer declares are removed, and `extras_require`
+    /// is only added if there are any.
+    fn merge_string(contents: &str, spec: &Setup) -> Result<String> {
+        let call = SetupParser::setup_call_ranges(contents)?;
+        let indent_at = |offset: usize| {
+            let line_start = contents[..offset].rfind('\n').map_or(0, |i| i + 1);
+            contents[line_start..]
+                .chars()
+                .take_while(|c| *c == ' ' || *c == '\t')
+                .collect::<String>()
+        };
+        let mut kwargs = vec![(
+            vec!["install_requires"],
+            PyLiteral::List(spec.install_requires.clone().unwrap_or_default()),
+        )];
+        let extra_requires = spec.extra_requires.clone().unwrap_or_default();
+        let names = vec!["extras_require", "extra_requires"];
+        if !extra_requires.is_empty() || names.iter().any(|name| call.keywords.contains_key(*name))
+        {
+            kwargs.push((names, PyLiteral::Dict(extra_requires)));
+        }
+
+        let mut replacements = Vec::<(Range<usize>, String)>::new();
+        let mut insertions = Vec::<String>::new();
+        for (names, literal) in kwargs.iter() {
+            match names.iter().find_map(|name| call.keywords.get(*name)) {
+                Some((range, true)) => {
+                    let indent = indent_at(range.start);
+                    replacements.push((range.clone(), literal.format(&indent)));
+                }
+                Some((_, false)) => {
+                    return Err(Error::Unsupported(format!(
+                        "Failed to perform operation. {} of setup.py is not a literal, e.g., it is read from a file, and cannot be synced.",
+                        names[0]
+                    )))
+                }
+                None if call.unpacks => {
+                    return Err(Error::Unsupported(format!(
+                        "Failed to perform operation. {} is not given to setup() directly, but may be unpacked into it, and cannot be synced.",
+                        names[0]
+                    )))
+                }
+                None => insertions.push(names[0].to_string()),
+            }
+        }
+
+        let mut merged = contents.to_string();
+        if !insertions.is_empty() {
+            let has_arguments = !call.arguments.is_empty();
+            let multiline = contents[call.arguments.clone()].contains('\n');
+            let indent = indent_at(call.arguments.end);
+            let mut inserted = String::new();
+            for (i, name) in insertions.iter().enumerate() {
+                let (_, literal) = kwargs.iter().find(|(names, _)| names[0] == name).unwrap();
+                let separator = match (multiline, has_arguments || i > 0) {
+                    (true, _) => format!(",\n{}", indent),
+                    (false, true) => ", ".to_string(),
+                    (false, false) => String::new(),
+                };
+                let value = match multiline {
+                    true => literal.format(&indent),
+                    false => literal.format_inline(),
+                };
+                inserted.push_str(&format!("{}{}={}", separator, name, value));
+            }
+            merged.insert_str(call.arguments.end, &inserted);
+        }
+        // Values are replaced from the end, so that earlier ranges still hold.
+        replacements.sort_by_key(|(range, _)| std::cmp::Reverse(range.start));
+        for (range, value) in replacements {
+            merged.replace_range(range, &value);
+        }
+        Ok(merged)
+    }
+}
+
+/// A list of requirements or a dict of extras, written into a setup.py.
+enum PyLiteral {
+    List(Vec<Requirement>),
+    Dict(BTreeMap<String, Vec<Requirement>>),
+}
+
+impl PyLiteral {
+    /// Formats the literal across lines, indented from `indent`.
+    fn format(&self, indent: &str) -> String {
+        match self {
+            PyLiteral::List(items) if items.is_empty() => "[]".to_string(),
+            PyLiteral::List(items) => {
+                let mut list = String::from("[\n");
+                for item in items.iter() {
+                    list.push_str(&format!("{}    {:?},\n", indent, item));
+                }
+                list.push_str(indent);
+                list.push(']');
+                list
+            }
+            PyLiteral::Dict(extras) if extras.is_empty() => "{}".to_string(),
+            PyLiteral::Dict(extras) => {
+                let nested = format!("{}    ", indent);
+                let mut dict = String::from("{\n");
+                for (extra, items) in extras.iter() {
+                    let items = PyLiteral::List(items.clone()).format(&nested);
+                    dict.push_str(&format!("{}{:?}: {},\n", nested, extra, items));
+                }
+                dict.push_str(indent);
+                dict.push('}');
+                dict
+            }
+        }
+    }
+
+    fn format_inline(&self) -> String {
+        match self {
+            PyLiteral::List(items) => format!("{:?}", items),
+            PyLiteral::Dict(extras) => format!("{:?}", extras),
+        }
     }
 }
 
@@ -100,6 +500,15 @@ impl SetupKwarg for Option<BTreeMap<String, Vec<Requirement>>> {
     }
 }
 
+impl SetupKwarg for Option<BTreeMap<String, String>> {
+    fn as_kwarg_string(&self, kw: &str) -> String {
+        match self {
+            Some(s) => format!("{}={:?}", kw, s),
+            None => String::new(),
+        }
+    }
+}
+
 impl SetupKwarg for Option<Entrypoints> {
     fn as_kwarg_string(&self, kw: &str) -> String {
         match self {
@@ -109,17 +518,260 @@ impl SetupKwarg for Option<Entrypoints> {
     }
 }
 
+impl SetupKwarg for Option<Readme> {
+    fn as_kwarg_string(&self, kw: &str) -> String {
+        let readme = match self {
+            Some(readme) => readme,
+            None => return String::new(),
+        };
+        let description = match (readme.file(), readme.text()) {
+            (Some(file), _) => format!("{}=open({:?}, encoding=\"utf-8\").read()", kw, file),
+            (None, Some(text)) => format!("{}={:#?}", kw, text),
+            (None, None) => return String::new(),
+        };
+        match readme.content_type() {
+            Some(content_type) => format!(
+                "{},\n        {}_content_type={:?}",
+                description, kw, content_type
+            ),
+            None => description,
+        }
+    }
+}
+
+impl SetupKwarg for DynamicVersion {
+    fn as_kwarg_string(&self, kw: &str) -> String {
+        let search = |pattern: &str, path: &str| {
+            let group = if pattern.contains("(?P<version>") {
+                "\"version\"".to_string()
+            } else {
+                "1".to_string()
+            };
+            format!(
+                "{}=re.search(r\"\"\"{}\"\"\", open({:?}).read()).group({})",
+                kw, pattern, path, group
+            )
+        };
+        match self {
+            // The module is read rather than imported, as its dependencies
+            // may not be installed yet.
+            DynamicVersion::Attribute { attr, path } => {
+                let (module, name) = attr.rsplit_once('.').unwrap_or((attr, "__version__"));
+                let path = path
+                    .clone()
+                    .unwrap_or_else(|| format!("{}/__init__.py", module.replace('.', "/")));
+                search(&DynamicVersion::attribute_pattern(name), &path)
+            }
+            DynamicVersion::File(path) => format!("{}=open({:?}).read().strip()", kw, path),
+            DynamicVersion::Pattern { path, pattern } => search(
+                pattern
+                    .as_deref()
+                    .unwrap_or(DynamicVersion::default_pattern()),
+                path,
+            ),
+        }
+    }
+}
+
+/// Each of the `packages`, `package_dir`, `py_modules` and `package_data`
+/// kwargs is taken from the layout by its name.
+impl SetupKwarg for Option<PackageLayout> {
+    fn as_kwarg_string(&self, kw: &str) -> String {
+        let layout = match self {
+            Some(layout) => layout,
+            None => return String::new(),
+        };
+        match kw {
+            "packages" if !layout.packages.is_empty() => {
+                let find = if layout.namespaces {
+                    "find_namespace_packages"
+                } else {
+                    "find_packages"
+                };
+                let mut args = Vec::<String>::new();
+                if let Some(package_dir) = &layout.package_dir {
+                    args.push(format!("where={:?}", package_dir));
+                }
+                args.push(format!("include={:?}", layout.include_patterns()));
+                format!("{}={}({})", kw, find, args.join(", "))
+            }
+            "package_dir" => match &layout.package_dir {
+                Some(package_dir) => format!("{}={{\"\": {:?}}}", kw, package_dir),
+                None => String::new(),
+            },
+            "py_modules" if !layout.py_modules.is_empty() => {
+                format!("{}={:?}", kw, layout.py_modules)
+            }
+            "package_data" if !layout.package_data.is_empty() => {
+                format!("{}={:?}", kw, layout.package_data)
+            }
+            _ => String::new(),
+        }
+    }
+}
+
 impl SpecGenerator<PyProject> for PyProjectGenerator {
-    fn make_file(path: &Path, spec: &PyProject) -> PyResult<()> {
-        if let Ok(contents) = toml::to_string_pretty::<PyProject>(&spec) {
-            fs::write(path, contents)?;
-            return Ok(());
+    fn make_string(spec: &PyProject) -> Result<String> {
+        toml::to_string_pretty::<PyProject>(&spec).map_err(|_| Error::Io {
+            message: format!("Failed to write pyproject definition:\n{:#?}", spec),
+            source: None,
+        })
+    }
+
+    /// Replaces `project.dependencies` and `project.optional-dependencies`,
+    /// whose extras the spec no longer declares are removed, keeping the
+    /// formatting and comments of everything else.
+    fn merge_string(contents: &str, spec: &PyProject) -> Result<String> {
+        let mut document = contents.parse::<toml_edit::DocumentMut>().map_err(|e| {
+            Error::Parse(format!(
+                "Failed to parse toml file \"pyproject.toml\": {}",
+                e
+            ))
+        })?;
+        let project = spec.project.clone().unwrap_or_default();
+        let table = document
+            .entry("project")
+            .or_insert(toml_edit::table())
+            .as_table_like_mut()
+            .ok_or_else(|| {
+                Error::Parse(
+                    "Failed to perform operation. project of pyproject.toml is not a table."
+                        .to_string(),
+                )
+            })?;
+        let dynamic = table.get("dynamic").and_then(|dynamic| dynamic.as_array());
+        if dynamic.is_some_and(|dynamic| {
+            dynamic
+                .iter()
+                .any(|field| field.as_str() == Some("dependencies"))
+        }) {
+            return Err(Error::Unsupported(
+                "Failed to perform operation. dependencies of pyproject.toml are dynamic, e.g., read from a file, and cannot be synced.".to_string(),
+            ));
+        }
+        let dependencies = toml_array(&project.dependencies.unwrap_or_default());
+        match table
+            .get_mut("dependencies")
+            .and_then(|item| item.as_value_mut())
+        {
+            Some(value) => {
+                let decor = value.decor().clone();
+                *value = dependencies;
+                *value.decor_mut() = decor;
+            }
+            None => {
+                table.insert("dependencies", toml_edit::Item::Value(dependencies));
+            }
+        }
+
+        let extras = project.optional_dependencies.unwrap_or_default();
+        if extras.is_empty() && !table.contains_key("optional-dependencies") {
+            return Ok(document.to_string());
+        }
+        let optional = table
+            .entry("optional-dependencies")
+            .or_insert(toml_edit::table())
+            .as_table_like_mut()
+            .ok_or_else(|| Error::Parse("Failed to perform operation. project.optional-dependencies of pyproject.toml is not a table.".to_string()))?;
+        // Extras are matched by their normalized names, e.g., `Test` is `test`.
+        let existing = optional
+            .iter()
+            .map(|(extra, _)| extra.to_string())
+            .collect::<Vec<String>>();
+        for extra in existing.iter() {
+            let declared = extras
+                .keys()
+                .any(|name| pep508::canonicalize_name(name) == pep508::canonicalize_name(extra));
+            if !declared {
+                optional.remove(extra);
+            }
+        }
+        for (extra, requirements) in extras.iter() {
+            let requirements = toml_array(requirements);
+            let key = existing
+                .iter()
+                .find(|name| pep508::canonicalize_name(name) == pep508::canonicalize_name(extra))
+                .unwrap_or(extra);
+            match optional.get_mut(key).and_then(|item| item.as_value_mut()) {
+                Some(value) => {
+                    let decor = value.decor().clone();
+                    *value = requirements;
+                    *value.decor_mut() = decor;
+                }
+                None => {
+                    optional.insert(extra, toml_edit::Item::Value(requirements));
+                }
+            }
+        }
+        if optional.is_empty() {
+            table.remove("optional-dependencies");
+        }
+        Ok(document.to_string())
+    }
+}
+
+/// An array of requirements, one per line as `toml::to_string_pretty` writes them.
+fn toml_array(requirements: &[Requirement]) -> toml_edit::Value {
+    let mut array = requirements.iter().collect::<toml_edit::Array>();
+    if !array.is_empty() {
+        for value in array.iter_mut() {
+            value.decor_mut().set_prefix("\n    ");
         }
-        Err(PyOSError::new_err(format!(
-            "Failed to write {:#?} with pyproject definition:\n{:#?}",
-            path.to_str(),
-            spec
-        )))
+        array.set_trailing("\n");
+        array.set_trailing_comma(true);
+    }
+    toml_edit::Value::Array(array)
+}
+
+impl SpecGenerator<CondaEnvironment> for CondaEnvironmentGenerator {
+    fn make_string(spec: &CondaEnvironment) -> Result<String> {
+        serde_yaml::to_string::<CondaEnvironment>(&spec).map_err(|_| Error::Io {
+            message: format!("Failed to write environment definition:\n{:#?}", spec),
+            source: None,
+        })
+    }
+}
+
+impl SpecGenerator<Metadata> for MetadataGenerator {
+    fn make_string(spec: &Metadata) -> Result<String> {
+        let mut contents = String::new();
+        let mut push_header = |key: &str, value: &str| {
+            contents.push_str(&format!("{}: {}\n", key, value));
+        };
+        push_header(
+            "Metadata-Version",
+            spec.metadata_version.as_deref().unwrap_or("2.1"),
+        );
+        if let Some(name) = &spec.name {
+            push_header("Name", name);
+        }
+        if let Some(version) = &spec.version {
+            push_header("Version", version);
+        }
+        if let Some(summary) = &spec.summary {
+            push_header("Summary", summary);
+        }
+        for (label, url) in spec.project_urls.iter() {
+            push_header("Project-URL", &format!("{}, {}", label, url));
+        }
+        if let Some(requires_python) = &spec.requires_python {
+            push_header("Requires-Python", requires_python);
+        }
+        for extra in spec.provides_extra.iter() {
+            push_header("Provides-Extra", extra);
+        }
+        for requirement in spec.requires_dist.iter() {
+            push_header("Requires-Dist", requirement);
+        }
+        if let Some(content_type) = &spec.description_content_type {
+            push_header("Description-Content-Type", content_type);
+        }
+        if let Some(description) = &spec.description {
+            contents.push('\n');
+            contents.push_str(description);
+            contents.push('\n');
+        }
+        Ok(contents)
     }
 }
 
@@ -128,6 +780,12 @@ mod tests {
     use super::*;
     use std::env;
 
+    /// Outputs are regenerated on every run.
+    const OVERWRITE: WriteOptions = WriteOptions {
+        force: true,
+        backup: false,
+    };
+
     #[test]
     fn generate_requirements() {
         let curr_dir = env::current_dir().unwrap();
@@ -138,8 +796,9 @@ mod tests {
         let path = Path::new(&path_str);
         let spec = Requirements {
             requires: vec!["flask".to_string(), "pydantic==2.6.1".to_string()],
+            ..Default::default()
         };
-        let result = RequirementsGenerator::make_file(&path, &spec);
+        let result = RequirementsGenerator::make_file(&path, &spec, &OVERWRITE);
         assert!(result.is_ok());
     }
 
@@ -154,6 +813,9 @@ mod tests {
         let spec = Setup {
             package_name: Some("babelone-test".to_string()),
             version: Some("v0.1.1".to_string()),
+            description: None,
+            python_requires: None,
+            project_urls: None,
             extra_requires: Some(BTreeMap::from([(
                 "dev".to_string(),
                 vec!["pytest".to_string(), "hypothesis>=6.98.1".to_string()],
@@ -164,8 +826,12 @@ mod tests {
                 console_scripts: Some(vec!["hello-world = timmins:hello_world".to_string()]),
                 gui_scripts: None,
             }),
+            layout: None,
+            dynamic_version: None,
+            readme: None,
+            requirements_file: None,
         };
-        let result = SetupGenerator::make_file(&path, &spec);
+        let result = SetupGenerator::make_file(&path, &spec, &OVERWRITE);
         assert!(result.is_ok());
     }
 
@@ -181,6 +847,10 @@ mod tests {
             project: Some(Project {
                 name: Some("test".to_string()),
                 version: Some("2.1".to_string()),
+                dynamic: None,
+                description: None,
+                readme: None,
+                requires_python: None,
                 dependencies: Some(vec!["pydantic==2.x".to_string(), "flask".to_string()]),
                 optional_dependencies: Some(BTreeMap::from([(
                     "dev".to_string(),
@@ -188,10 +858,160 @@ mod tests {
                 )])),
                 project_scripts: None,
                 project_gui_scripts: None,
+                urls: None,
             }),
             build_system: None,
+            tool: None,
+        };
+        let result = PyProjectGenerator::make_file(&path, &spec, &OVERWRITE);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn generate_conda_environment() {
+        let curr_dir = env::current_dir().unwrap();
+        let path_str = format!(
+            "{}/tests/outputs/environment__generate_environment.yml",
+            curr_dir.to_str().unwrap()
+        );
+        let path = Path::new(&path_str);
+        let spec = CondaEnvironment {
+            name: Some("test".to_string()),
+            channels: Some(vec!["conda-forge".to_string()]),
+            dependencies: Some(vec![
+                CondaDependency::Package("numpy=1.26".to_string()),
+                CondaDependency::Package("pip".to_string()),
+                CondaDependency::Pip {
+                    pip: vec!["flask".to_string(), "pydantic==2.6.1".to_string()],
+                },
+            ]),
         };
-        let result = PyProjectGenerator::make_file(&path, &spec);
+        let result = CondaEnvironmentGenerator::make_file(&path, &spec, &OVERWRITE);
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn merge_specs() {
+        let requirements = Requirements {
+            requires: vec!["flask>=3.0".to_string(), "rich".to_string()],
+            ..Default::default()
+        };
+        let contents =
+            "# pinned\n--index-url https://example.org\nold==1.0\n    # via spam\n-e .\n";
+        assert_eq!(
+            RequirementsGenerator::merge_string(contents, &requirements).unwrap(),
+            "# pinned\n--index-url https://example.org\nflask>=3.0\nrich\n-e .\n"
+        );
+        // Comments, inline ones included, and continued options are kept.
+        let contents =
+            "--extra-index-url \\\n    https://example.org\nFlask>=2.0  # web\n# rendering\nold\n";
+        assert_eq!(
+            RequirementsGenerator::merge_string(contents, &requirements).unwrap(),
+            "--extra-index-url \\\n    https://example.org\nflask>=3.0  # web\n# rendering\nrich\n"
+        );
+
+        let setup = Setup::from_requirements(requirements.clone());
+        let contents = "from setuptools import setup\n\nsetup(\n    name=\"spam\",  # name\n    install_requires=[\"old\"],\n)\n";
+        assert_eq!(
+            SetupGenerator::merge_string(contents, &setup).unwrap(),
+            "from setuptools import setup\n\nsetup(\n    name=\"spam\",  # name\n    install_requires=[\n        \"flask>=3.0\",\n        \"rich\",\n    ],\n)\n"
+        );
+        let contents = "from setuptools import setup\nsetup(name=\"spam\")\n";
+        assert_eq!(
+            SetupGenerator::merge_string(contents, &setup).unwrap(),
+            "from setuptools import setup\nsetup(name=\"spam\", install_requires=[\"flask>=3.0\", \"rich\"])\n"
+        );
+        let contents = "from setuptools import setup\nsetup(install_requires=open(\"requirements.txt\").read().splitlines())\n";
+        assert!(SetupGenerator::merge_string(contents, &setup).is_err());
+        // Extras the spec no longer declares are removed.
+        let contents = "from setuptools import setup\nsetup(install_requires=[], extras_require={\"test\": [\"pytest\"]})\n";
+        assert_eq!(
+            SetupGenerator::merge_string(contents, &setup).unwrap(),
+            "from setuptools import setup\nsetup(install_requires=[\n    \"flask>=3.0\",\n    \"rich\",\n], extras_require={})\n"
+        );
+
+        let pyproject = PyProject::from_requirements(requirements);
+        let contents = "[project]\nname = \"spam\"  # name\ndependencies = [\"old\"]\n\n[tool.black]\nline-length = 88\n";
+        assert_eq!(
+            PyProjectGenerator::merge_string(contents, &pyproject).unwrap(),
+            "[project]\nname = \"spam\"  # name\ndependencies = [\n    \"flask>=3.0\",\n    \"rich\",\n]\n\n[tool.black]\nline-length = 88\n"
+        );
+        let contents = "[project]\nname = \"spam\"\ndynamic = [\"dependencies\"]\n";
+        assert!(PyProjectGenerator::merge_string(contents, &pyproject).is_err());
+
+        let mut pyproject = pyproject;
+        pyproject.project.as_mut().unwrap().optional_dependencies = Some(BTreeMap::from([(
+            "test".to_string(),
+            vec!["pytest".to_string()],
+        )]));
+        let contents = "[project]\ndependencies = []\n\n[project.optional-dependencies]\nTest = [\"nose\"]  # tests\ndocs = [\"sphinx\"]\n";
+        assert_eq!(
+            PyProjectGenerator::merge_string(contents, &pyproject).unwrap(),
+            "[project]\ndependencies = [\n    \"flask>=3.0\",\n    \"rich\",\n]\n\n[project.optional-dependencies]\nTest = [\n    \"pytest\",\n]  # tests\n"
+        );
+        pyproject.project.as_mut().unwrap().optional_dependencies = None;
+        assert_eq!(
+            PyProjectGenerator::merge_string(contents, &pyproject).unwrap(),
+            "[project]\ndependencies = [\n    \"flask>=3.0\",\n    \"rich\",\n]\n"
+        );
+    }
+
+    #[test]
+    fn write_file_options() {
+        let curr_dir = env::current_dir().unwrap();
+        let path_str = format!(
+            "{}/tests/outputs/requirements__write_file_options.txt",
+            curr_dir.to_str().unwrap()
+        );
+        let path = Path::new(&path_str);
+        let backup = with_suffix(path, ".bak");
+        let _ = fs::remove_file(path);
+        let _ = fs::remove_file(&backup);
+
+        let options = WriteOptions::default();
+        assert!(write_file(path, "flask\n", &options).is_ok());
+        // An existing file is left untouched unless forced.
+        assert!(write_file(path, "click\n", &options).is_err());
+        assert_eq!(fs::read_to_string(path).unwrap(), "flask\n");
+
+        let options = WriteOptions {
+            force: true,
+            backup: true,
+        };
+        assert!(write_file(path, "click\n", &options).is_ok());
+        assert_eq!(fs::read_to_string(path).unwrap(), "click\n");
+        assert_eq!(fs::read_to_string(&backup).unwrap(), "flask\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn write_file_through_symlink() {
+        let curr_dir = env::current_dir().unwrap();
+        let dir = format!(
+            "{}/tests/outputs/write_file__symlink",
+            curr_dir.to_str().unwrap()
+        );
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let target = Path::new(&dir).join("requirements.txt");
+        let link = Path::new(&dir).join("link.txt");
+        std::os::unix::fs::symlink("requirements.txt", &link).unwrap();
+
+        // A dangling link is written through to the file it names.
+        let options = WriteOptions::default();
+        assert!(write_file(&link, "flask\n", &options).is_ok());
+        assert!(write_file(&link, "click\n", &options).is_err());
+        let options = WriteOptions {
+            force: true,
+            ..Default::default()
+        };
+        assert!(write_file(&link, "click\n", &options).is_ok());
+        assert!(fs::symlink_metadata(&link)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "click\n");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+        fs::remove_dir_all(&dir).unwrap();
+    }
 }
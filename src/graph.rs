@@ -0,0 +1,433 @@
+//! Computes the dependency tree of an installed environment from the
+//! `Requires-Dist` metadata of its distributions.
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use crate::pep508::{self, MarkerEnvironment, ParsedRequirement, Version};
+use crate::specs::{InstalledEnvironment, Requirements};
+
+/// The installed packages of an environment, keyed by their canonical name,
+/// and the requirements between them.
+pub struct DependencyTree {
+    pub packages: BTreeMap<String, InstalledPackage>,
+}
+
+pub struct InstalledPackage {
+    pub name: String,
+    pub version: Option<String>,
+    /// Requirements whose markers hold for the environment and the extras requested of the package.
+    pub dependencies: Vec<ParsedRequirement>,
+}
+
+/// A requirement of an installed package which the environment does not satisfy.
+#[derive(Debug, PartialEq)]
+pub enum Problem {
+    Missing {
+        package: String,
+        requirement: String,
+    },
+    Conflicting {
+        package: String,
+        requirement: String,
+        installed: String,
+    },
+}
+
+impl DependencyTree {
+    pub fn from_installed_environment(environment: &InstalledEnvironment) -> Self {
+        let marker_environment =
+            MarkerEnvironment::for_platform(environment.python_version.as_deref());
+        let mut requires_dist = BTreeMap::<String, Vec<ParsedRequirement>>::new();
+        let mut packages = BTreeMap::<String, InstalledPackage>::new();
+        for distribution in &environment.distributions {
+            let name = match &distribution.metadata.name {
+                Some(name) => name,
+                None => continue,
+            };
+            let key = pep508::canonicalize_name(name);
+            requires_dist.insert(
+                key.clone(),
+                distribution
+                    .metadata
+                    .requires_dist
+                    .iter()
+                    .filter_map(|requirement| pep508::parse_requirement(requirement))
+                    .collect(),
+            );
+            packages.insert(
+                key,
+                InstalledPackage {
+                    name: name.clone(),
+                    version: distribution.metadata.version.clone(),
+                    dependencies: Vec::new(),
+                },
+            );
+        }
+
+        // Requesting an extra of a package may pull in further requirements, which
+        // can themselves request extras, so these are propagated until none are added.
+        let mut extras = BTreeMap::<String, BTreeSet<String>>::new();
+        loop {
+            let mut changed = false;
+            for (key, requirements) in &requires_dist {
+                let requested = extras.get(key).cloned().unwrap_or_default();
+                for requirement in requirements {
+                    if !Self::is_active(requirement, &requested, &marker_environment) {
+                        continue;
+                    }
+                    let dependency = pep508::canonicalize_name(&requirement.name);
+                    for extra in &requirement.extras {
+                        changed |= extras
+                            .entry(dependency.clone())
+                            .or_default()
+                            .insert(pep508::canonicalize_name(extra));
+                    }
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+
+        for (key, requirements) in requires_dist {
+            let requested = extras.get(&key).cloned().unwrap_or_default();
+            if let Some(package) = packages.get_mut(&key) {
+                package.dependencies = requirements
+                    .into_iter()
+                    .filter(|requirement| {
+                        Self::is_active(requirement, &requested, &marker_environment)
+                    })
+                    .collect();
+            }
+        }
+        Self { packages }
+    }
+
+    fn is_active(
+        requirement: &ParsedRequirement,
+        extras: &BTreeSet<String>,
+        environment: &MarkerEnvironment,
+    ) -> bool {
+        let marker = match &requirement.marker {
+            Some(marker) => marker,
+            None => return true,
+        };
+        if pep508::evaluate_marker(marker, environment) {
+            return true;
+        }
+        extras.iter().any(|extra| {
+            let environment = MarkerEnvironment {
+                extra: Some(extra.clone()),
+                ..environment.clone()
+            };
+            pep508::evaluate_marker(marker, &environment)
+        })
+    }
+
+    /// Packages which no other installed package requires. Where packages only
+    /// require each other in a cycle, the first of them by name is taken as top-level.
+    pub fn top_level(&self) -> Vec<&InstalledPackage> {
+        let mut required = BTreeSet::<String>::new();
+        for (key, package) in &self.packages {
+            for dependency in &package.dependencies {
+                let dependency = pep508::canonicalize_name(&dependency.name);
+                if &dependency != key {
+                    required.insert(dependency);
+                }
+            }
+        }
+        let mut roots: Vec<&String> = self
+            .packages
+            .keys()
+            .filter(|key| !required.contains(*key))
+            .collect();
+        let mut reached = BTreeSet::<&String>::new();
+        for root in &roots {
+            self.reach(root, &mut reached);
+        }
+        for key in self.packages.keys() {
+            if !reached.contains(key) {
+                roots.push(key);
+                self.reach(key, &mut reached);
+            }
+        }
+        roots.sort();
+        roots.iter().map(|key| &self.packages[*key]).collect()
+    }
+
+    fn reach<'a>(&'a self, key: &'a String, reached: &mut BTreeSet<&'a String>) {
+        if !reached.insert(key) {
+            return;
+        }
+        if let Some(package) = self.packages.get(key) {
+            for dependency in &package.dependencies {
+                let dependency = pep508::canonicalize_name(&dependency.name);
+                if let Some((key, _)) = self.packages.get_key_value(&dependency) {
+                    self.reach(key, reached);
+                }
+            }
+        }
+    }
+
+    /// Packages which are only installed as a requirement of another.
+    pub fn transitive(&self) -> Vec<&InstalledPackage> {
+        let top_level: BTreeSet<&str> = self
+            .top_level()
+            .iter()
+            .map(|package| package.name.as_str())
+            .collect();
+        self.packages
+            .values()
+            .filter(|package| !top_level.contains(package.name.as_str()))
+            .collect()
+    }
+
+    /// Requirements which are not installed, or whose installed version does not
+    /// match, as would be reported by `pip check`.
+    pub fn problems(&self) -> Vec<Problem> {
+        let mut problems = Vec::<Problem>::new();
+        for package in self.packages.values() {
+            let name = match &package.version {
+                Some(version) => format!("{} {}", package.name, version),
+                None => package.name.clone(),
+            };
+            for dependency in &package.dependencies {
+                let requirement = ParsedRequirement {
+                    marker: None,
+                    ..dependency.clone()
+                }
+                .to_string();
+                let installed = match self
+                    .packages
+                    .get(&pep508::canonicalize_name(&dependency.name))
+                {
+                    Some(installed) => installed,
+                    None => {
+                        problems.push(Problem::Missing {
+                            package: name.clone(),
+                            requirement,
+                        });
+                        continue;
+                    }
+                };
+                let version = match &installed.version {
+                    Some(version) => version,
+                    None => continue,
+                };
+                let satisfied = dependency.url.is_some()
+                    || match version.parse::<Version>() {
+                        Ok(version) => version.satisfies(&dependency.specifier),
+                        Err(_) => true,
+                    };
+                if !satisfied {
+                    problems.push(Problem::Conflicting {
+                        package: name.clone(),
+                        requirement,
+                        installed: format!("{} {}", installed.name, version),
+                    });
+                }
+            }
+        }
+        problems
+    }
+
+    /// Reduces a frozen list of requirements to those not already required by
+    /// another installed package. A requirement which pip-compile annotates as
+    /// pinned explicitly, e.g., `# via -r requirements.in`, is always kept.
+    pub fn minimize(&self, requirements: Requirements) -> Requirements {
+        let explicit: BTreeSet<String> = requirements
+            .via
+            .iter()
+            .filter(|(_, sources)| {
+                sources
+                    .iter()
+                    .any(|source| source.starts_with("-r ") || source.starts_with("-c "))
+            })
+            .filter_map(|(requirement, _)| pep508::parse_requirement(requirement))
+            .map(|parsed| pep508::canonicalize_name(&parsed.name))
+            .collect();
+        let transitive: BTreeSet<String> = self
+            .transitive()
+            .iter()
+            .map(|package| pep508::canonicalize_name(&package.name))
+            .filter(|name| !explicit.contains(name))
+            .collect();
+        let is_transitive = |requirement: &String| match pep508::parse_requirement(requirement) {
+            Some(parsed) => transitive.contains(&pep508::canonicalize_name(&parsed.name)),
+            None => false,
+        };
+        let requires = requirements
+            .requires
+            .into_iter()
+            .filter(|requirement| !is_transitive(requirement))
+            .collect();
+        let hashes = requirements
+            .hashes
+            .into_iter()
+            .filter(|(name, _)| !transitive.contains(name))
+            .collect();
+        let via = requirements
+            .via
+            .into_iter()
+            .filter(|(requirement, _)| !is_transitive(requirement))
+            .collect();
+        Requirements {
+            requires,
+            hashes,
+            via,
+        }
+    }
+
+    fn fmt_package(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        package: &InstalledPackage,
+        depth: usize,
+        ancestors: &mut Vec<String>,
+    ) -> fmt::Result {
+        let key = pep508::canonicalize_name(&package.name);
+        ancestors.push(key);
+        for dependency in &package.dependencies {
+            let dependency_key = pep508::canonicalize_name(&dependency.name);
+            let installed = self.packages.get(&dependency_key);
+            let required = match (&dependency.url, dependency.specifier.as_str()) {
+                (Some(url), _) => url.as_str(),
+                (None, "") => "Any",
+                (None, specifier) => specifier,
+            };
+            writeln!(
+                f,
+                "{}- {} [required: {}, installed: {}]",
+                "  ".repeat(depth + 1),
+                installed.map_or(dependency.name.as_str(), |installed| {
+                    installed.name.as_str()
+                }),
+                required,
+                installed
+                    .and_then(|installed| installed.version.as_deref())
+                    .unwrap_or("?"),
+            )?;
+            if let Some(installed) = installed {
+                if !ancestors.contains(&dependency_key) {
+                    self.fmt_package(f, installed, depth + 1, ancestors)?;
+                }
+            }
+        }
+        ancestors.pop();
+        Ok(())
+    }
+}
+
+/// Renders the tree from each top-level package, e.g.,
+///
+/// ```text
+/// Flask==3.0.2
+///   - Jinja2 [required: >=3.1.2, installed: 3.1.3]
+///     - MarkupSafe [required: >=2.0, installed: 2.1.5]
+/// ```
+impl fmt::Display for DependencyTree {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for package in self.top_level() {
+            match &package.version {
+                Some(version) => writeln!(f, "{}=={}", package.name, version)?,
+                None => writeln!(f, "{}", package.name)?,
+            }
+            self.fmt_package(f, package, 0, &mut Vec::new())?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::Missing {
+                package,
+                requirement,
+            } => write!(
+                f,
+                "{} requires {}, which is not installed",
+                package, requirement
+            ),
+            Problem::Conflicting {
+                package,
+                requirement,
+                installed,
+            } => write!(
+                f,
+                "{} requires {}, but {} is installed",
+                package, requirement, installed
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parsers::{SitePackagesParser, SpecParser};
+    use std::{env, path::Path};
+
+    #[test]
+    fn make_dependency_tree() {
+        let curr_dir = env::current_dir().unwrap();
+        let path_str = format!("{}/tests/inputs/venv", curr_dir.to_str().unwrap());
+        let environment = SitePackagesParser::from_file(Path::new(&path_str)).unwrap();
+        let tree = DependencyTree::from_installed_environment(&environment);
+        let names = |packages: Vec<&InstalledPackage>| {
+            packages
+                .iter()
+                .map(|package| package.name.clone())
+                .collect::<Vec<String>>()
+        };
+        assert_eq!(
+            names(tree.top_level()),
+            vec!["attrs", "pip", "six", "spam-eggs"]
+        );
+        assert!(names(tree.transitive()).contains(&"Flask".to_string()));
+        assert_eq!(
+            tree.problems(),
+            vec![
+                Problem::Missing {
+                    package: "Flask 3.0.2".to_string(),
+                    requirement: "itsdangerous>=2.1.2".to_string(),
+                },
+                Problem::Conflicting {
+                    package: "Flask 3.0.2".to_string(),
+                    requirement: "blinker>=1.6.2".to_string(),
+                    installed: "blinker 1.4".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn minimize_keeps_explicit_requirements() {
+        let curr_dir = env::current_dir().unwrap();
+        let path_str = format!("{}/tests/inputs/venv", curr_dir.to_str().unwrap());
+        let environment = SitePackagesParser::from_file(Path::new(&path_str)).unwrap();
+        let tree = DependencyTree::from_installed_environment(&environment);
+        let requirements = Requirements {
+            requires: vec![
+                "click==8.1.7".to_string(),
+                "Flask==3.0.2".to_string(),
+                "Jinja2==3.1.3".to_string(),
+            ],
+            via: BTreeMap::from([
+                (
+                    "click==8.1.7".to_string(),
+                    vec!["-r requirements.in".to_string(), "flask".to_string()],
+                ),
+                ("Flask==3.0.2".to_string(), vec!["spam-eggs".to_string()]),
+                ("Jinja2==3.1.3".to_string(), vec!["flask".to_string()]),
+            ]),
+            ..Default::default()
+        };
+        let requirements = tree.minimize(requirements);
+        assert_eq!(requirements.requires, vec!["click==8.1.7"]);
+        assert_eq!(
+            requirements.via.keys().collect::<Vec<_>>(),
+            vec!["click==8.1.7"]
+        );
+    }
+}
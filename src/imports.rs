@@ -0,0 +1,625 @@
+//! Infers the dependencies of a package from the imports in its source code.
+use crate::error::{Error, Result};
+use rustpython_parser::{ast, Parse};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::pep508;
+use crate::specs::Requirement;
+use crate::utils;
+
+/// Top-level modules of the Python 3 standard library, including those
+/// removed in recent versions which older packages may still import. Private
+/// modules, e.g., `_thread` or `__future__`, are left out (see [`is_stdlib`]).
+const STDLIB_MODULES: &[&str] = &[
+    "abc",
+    "aifc",
+    "argparse",
+    "array",
+    "ast",
+    "asynchat",
+    "asyncio",
+    "asyncore",
+    "atexit",
+    "audioop",
+    "base64",
+    "bdb",
+    "binascii",
+    "bisect",
+    "builtins",
+    "bz2",
+    "calendar",
+    "cgi",
+    "cgitb",
+    "chunk",
+    "cmath",
+    "cmd",
+    "code",
+    "codecs",
+    "codeop",
+    "collections",
+    "colorsys",
+    "compileall",
+    "concurrent",
+    "configparser",
+    "contextlib",
+    "contextvars",
+    "copy",
+    "copyreg",
+    "cProfile",
+    "crypt",
+    "csv",
+    "ctypes",
+    "curses",
+    "dataclasses",
+    "datetime",
+    "dbm",
+    "decimal",
+    "difflib",
+    "dis",
+    "distutils",
+    "doctest",
+    "email",
+    "encodings",
+    "ensurepip",
+    "enum",
+    "errno",
+    "faulthandler",
+    "fcntl",
+    "filecmp",
+    "fileinput",
+    "fnmatch",
+    "fractions",
+    "ftplib",
+    "functools",
+    "gc",
+    "genericpath",
+    "getopt",
+    "getpass",
+    "gettext",
+    "glob",
+    "graphlib",
+    "grp",
+    "gzip",
+    "hashlib",
+    "heapq",
+    "hmac",
+    "html",
+    "http",
+    "imaplib",
+    "imghdr",
+    "imp",
+    "importlib",
+    "inspect",
+    "io",
+    "ipaddress",
+    "itertools",
+    "json",
+    "keyword",
+    "lib2to3",
+    "linecache",
+    "locale",
+    "logging",
+    "lzma",
+    "mailbox",
+    "mailcap",
+    "marshal",
+    "math",
+    "mimetypes",
+    "mmap",
+    "modulefinder",
+    "msilib",
+    "msvcrt",
+    "multiprocessing",
+    "netrc",
+    "nis",
+    "nntplib",
+    "ntpath",
+    "numbers",
+    "opcode",
+    "operator",
+    "optparse",
+    "os",
+    "ossaudiodev",
+    "pathlib",
+    "pdb",
+    "pickle",
+    "pickletools",
+    "pipes",
+    "pkgutil",
+    "platform",
+    "plistlib",
+    "poplib",
+    "posix",
+    "posixpath",
+    "pprint",
+    "profile",
+    "pstats",
+    "pty",
+    "pwd",
+    "py_compile",
+    "pyclbr",
+    "pydoc",
+    "pyexpat",
+    "queue",
+    "quopri",
+    "random",
+    "re",
+    "readline",
+    "reprlib",
+    "resource",
+    "rlcompleter",
+    "runpy",
+    "sched",
+    "secrets",
+    "select",
+    "selectors",
+    "shelve",
+    "shlex",
+    "shutil",
+    "signal",
+    "site",
+    "smtpd",
+    "smtplib",
+    "sndhdr",
+    "socket",
+    "socketserver",
+    "spwd",
+    "sqlite3",
+    "sre_compile",
+    "sre_constants",
+    "sre_parse",
+    "ssl",
+    "stat",
+    "statistics",
+    "string",
+    "stringprep",
+    "struct",
+    "subprocess",
+    "sunau",
+    "symtable",
+    "sys",
+    "sysconfig",
+    "syslog",
+    "tabnanny",
+    "tarfile",
+    "telnetlib",
+    "tempfile",
+    "termios",
+    "textwrap",
+    "threading",
+    "time",
+    "timeit",
+    "tkinter",
+    "token",
+    "tokenize",
+    "tomllib",
+    "trace",
+    "traceback",
+    "tracemalloc",
+    "tty",
+    "turtle",
+    "turtledemo",
+    "types",
+    "typing",
+    "unicodedata",
+    "unittest",
+    "urllib",
+    "uu",
+    "uuid",
+    "venv",
+    "warnings",
+    "wave",
+    "weakref",
+    "webbrowser",
+    "winreg",
+    "winsound",
+    "wsgiref",
+    "xdrlib",
+    "xml",
+    "xmlrpc",
+    "zipapp",
+    "zipfile",
+    "zipimport",
+    "zlib",
+    "zoneinfo",
+];
+
+/// Whether a top-level module belongs to the standard library. Modules with a
+/// leading underscore are private to CPython, e.g., `_collections_abc`.
+fn is_stdlib(module: &str) -> bool {
+    module.starts_with('_') || STDLIB_MODULES.contains(&module)
+}
+
+/// Well-known distributions whose import name differs from their project name.
+const KNOWN_DISTRIBUTIONS: &[(&str, &str)] = &[
+    ("attr", "attrs"),
+    ("bs4", "beautifulsoup4"),
+    ("cv2", "opencv-python"),
+    ("dateutil", "python-dateutil"),
+    ("dotenv", "python-dotenv"),
+    ("google.protobuf", "protobuf"),
+    ("jose", "python-jose"),
+    ("jwt", "PyJWT"),
+    ("magic", "python-magic"),
+    ("MySQLdb", "mysqlclient"),
+    ("OpenSSL", "pyOpenSSL"),
+    ("pkg_resources", "setuptools"),
+    ("PIL", "Pillow"),
+    ("serial", "pyserial"),
+    ("skimage", "scikit-image"),
+    ("sklearn", "scikit-learn"),
+    ("usb", "pyusb"),
+    ("win32api", "pywin32"),
+    ("yaml", "PyYAML"),
+    ("zmq", "pyzmq"),
+];
+
+/// Directories which never hold a package's own sources.
+const SKIPPED_DIRS: &[&str] = &[
+    "__pycache__",
+    "build",
+    "dist",
+    "env",
+    "node_modules",
+    "site-packages",
+    "venv",
+];
+
+/// Walks the `.py` files of a package to collect the modules they import.
+pub struct ImportScanner {
+    /// Maps an import name (e.g., `yaml`) to the distribution providing it (e.g., `PyYAML`).
+    pub distributions: BTreeMap<String, String>,
+}
+
+/// Third-party modules imported by a package, keyed by their top-level name.
+#[derive(Debug, Default)]
+pub struct Imports {
+    pub modules: BTreeMap<String, BTreeSet<PathBuf>>,
+    /// Files which could not be parsed and so were left out.
+    pub unparsable: Vec<PathBuf>,
+}
+
+/// Compares the distributions a package imports against those its build spec declares.
+#[derive(Debug, Default, PartialEq)]
+pub struct ImportReport {
+    /// Distributions which are imported but not declared, and the modules they provide.
+    pub undeclared: BTreeMap<String, BTreeSet<String>>,
+    /// Declared requirements whose distribution is never imported.
+    pub unused: Vec<Requirement>,
+    /// Files which could not be parsed, and so whose imports were left out.
+    pub unparsable: Vec<PathBuf>,
+}
+
+impl Default for ImportScanner {
+    fn default() -> Self {
+        Self {
+            distributions: KNOWN_DISTRIBUTIONS
+                .iter()
+                .map(|(module, distribution)| (module.to_string(), distribution.to_string()))
+                .collect(),
+        }
+    }
+}
+
+impl ImportScanner {
+    /// Adds (or overrides) the distribution providing an import name.
+    pub fn map(&mut self, module: &str, distribution: &str) -> &mut Self {
+        self.distributions
+            .insert(module.to_string(), distribution.to_string());
+        self
+    }
+
+    /// Returns the distribution providing a module, defaulting to the module's own name.
+    pub fn distribution(&self, module: &str) -> String {
+        match self.mapped_prefix(module) {
+            Some(prefix) => self.distributions[prefix].clone(),
+            None => module.split('.').next().unwrap_or(module).to_string(),
+        }
+    }
+
+    /// Returns the longest mapped prefix of a module's dotted name, as namespace
+    /// packages are mapped by their dotted name, e.g., `google.protobuf`.
+    fn mapped_prefix<'a>(&self, module: &'a str) -> Option<&'a str> {
+        let mut prefix = module;
+        loop {
+            if self.distributions.contains_key(prefix) {
+                return Some(prefix);
+            }
+            prefix = &prefix[..prefix.rfind('.')?];
+        }
+    }
+
+    /// Collects the third-party imports of the `.py` files under `root`, leaving out
+    /// the standard library and the package's own (local) modules.
+    pub fn scan(&self, root: &Path) -> Result<Imports> {
+        if !root.is_dir() {
+            return Err(Error::InvalidArgument(format!(
+                "Failed to scan imports. {:#?} is not a directory.",
+                root.to_str()
+            )));
+        }
+        let mut files = Vec::new();
+        Self::find_sources(root, &mut files);
+        files.sort();
+
+        let local = Self::local_modules(root, &files);
+        let mut imports = Imports::default();
+        for file in files {
+            let contents = utils::read_file(&file)?;
+            let statements = match ast::Suite::parse(&contents, file.to_str().unwrap_or_default()) {
+                Ok(statements) => statements,
+                Err(_) => {
+                    imports.unparsable.push(file);
+                    continue;
+                }
+            };
+            let mut modules = BTreeSet::new();
+            Self::collect_imports(&statements, &mut modules);
+            for module in modules {
+                let top_level = module.split('.').next().unwrap_or_default();
+                if is_stdlib(top_level) || local.contains(top_level) {
+                    continue;
+                }
+                // Keep the dotted name only when it is mapped to a distribution of its own.
+                let key = self.mapped_prefix(&module).unwrap_or(top_level).to_string();
+                imports
+                    .modules
+                    .entry(key)
+                    .or_default()
+                    .insert(file.strip_prefix(root).unwrap_or(&file).to_path_buf());
+            }
+        }
+        Ok(imports)
+    }
+
+    /// Reports imported distributions missing from `requires` or `optional`, and
+    /// those in `requires` which are never imported.
+    pub fn compare(
+        &self,
+        imports: &Imports,
+        requires: &[Requirement],
+        optional: &[Requirement],
+    ) -> ImportReport {
+        let declared_name = |requirement: &Requirement| {
+            pep508::parse_requirement(requirement)
+                .map(|requirement| pep508::canonicalize_name(&requirement.name))
+        };
+        let declared = requires
+            .iter()
+            .chain(optional.iter())
+            .filter_map(declared_name)
+            .collect::<BTreeSet<String>>();
+
+        let mut report = ImportReport {
+            unparsable: imports.unparsable.clone(),
+            ..Default::default()
+        };
+        let mut imported = BTreeSet::new();
+        for module in imports.modules.keys() {
+            let distribution = self.distribution(module);
+            let name = pep508::canonicalize_name(&distribution);
+            if !declared.contains(&name) {
+                report
+                    .undeclared
+                    .entry(distribution)
+                    .or_default()
+                    .insert(module.clone());
+            }
+            imported.insert(name);
+        }
+        report.unused = requires
+            .iter()
+            .filter(|requirement| {
+                declared_name(requirement).is_some_and(|name| !imported.contains(&name))
+            })
+            .cloned()
+            .collect();
+        report
+    }
+
+    fn find_sources(dir: &Path, files: &mut Vec<PathBuf>) {
+        let entries = match dir.read_dir() {
+            Ok(entries) => entries,
+            Err(_) => return,
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().to_string();
+            // Symlinked directories are not followed, as they may loop back.
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            if file_type.is_symlink() && path.is_dir() {
+                continue;
+            }
+            if file_type.is_dir() {
+                if name.starts_with('.')
+                    || name.ends_with(".egg-info")
+                    || SKIPPED_DIRS.contains(&name.as_str())
+                    // A virtual environment under another name.
+                    || path.join("pyvenv.cfg").is_file()
+                {
+                    continue;
+                }
+                Self::find_sources(&path, files);
+            } else if name.ends_with(".py") {
+                files.push(path);
+            }
+        }
+    }
+
+    /// Names importable from the package itself, i.e., the top-level modules
+    /// and packages under `root` or its `src` directory.
+    fn local_modules(root: &Path, files: &[PathBuf]) -> BTreeSet<String> {
+        let src = root.join("src");
+        let mut local = BTreeSet::new();
+        for file in files {
+            for base in [root, src.as_path()] {
+                let relative = match file.strip_prefix(base) {
+                    Ok(relative) => relative,
+                    Err(_) => continue,
+                };
+                if let Some(first) = relative.components().next() {
+                    let first = first.as_os_str().to_string_lossy();
+                    local.insert(first.trim_end_matches(".py").to_string());
+                }
+            }
+        }
+        local
+    }
+
+    /// Collects the absolute imports of `statements`, including those nested in
+    /// functions, classes and control flow.
+    fn collect_imports(statements: &[ast::Stmt], modules: &mut BTreeSet<String>) {
+        for statement in statements {
+            match statement {
+                ast::Stmt::Import(ast::StmtImport { names, .. }) => {
+                    for alias in names {
+                        modules.insert(alias.name.to_string());
+                    }
+                }
+                ast::Stmt::ImportFrom(ast::StmtImportFrom {
+                    module: Some(module),
+                    level,
+                    ..
+                }) if level.as_ref().map_or(0, |level| level.to_u32()) == 0 => {
+                    modules.insert(module.to_string());
+                }
+                ast::Stmt::FunctionDef(ast::StmtFunctionDef { body, .. })
+                | ast::Stmt::AsyncFunctionDef(ast::StmtAsyncFunctionDef { body, .. })
+                | ast::Stmt::ClassDef(ast::StmtClassDef { body, .. })
+                | ast::Stmt::With(ast::StmtWith { body, .. })
+                | ast::Stmt::AsyncWith(ast::StmtAsyncWith { body, .. }) => {
+                    Self::collect_imports(body, modules);
+                }
+                ast::Stmt::If(ast::StmtIf { body, orelse, .. })
+                | ast::Stmt::For(ast::StmtFor { body, orelse, .. })
+                | ast::Stmt::AsyncFor(ast::StmtAsyncFor { body, orelse, .. })
+                | ast::Stmt::While(ast::StmtWhile { body, orelse, .. }) => {
+                    Self::collect_imports(body, modules);
+                    Self::collect_imports(orelse, modules);
+                }
+                ast::Stmt::Try(ast::StmtTry {
+                    body,
+                    handlers,
+                    orelse,
+                    finalbody,
+                    ..
+                })
+                | ast::Stmt::TryStar(ast::StmtTryStar {
+                    body,
+                    handlers,
+                    orelse,
+                    finalbody,
+                    ..
+                }) => {
+                    Self::collect_imports(body, modules);
+                    for ast::ExceptHandler::ExceptHandler(handler) in handlers {
+                        Self::collect_imports(&handler.body, modules);
+                    }
+                    Self::collect_imports(orelse, modules);
+                    Self::collect_imports(finalbody, modules);
+                }
+                ast::Stmt::Match(ast::StmtMatch { cases, .. }) => {
+                    for case in cases {
+                        Self::collect_imports(&case.body, modules);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl ImportReport {
+    /// Whether the imports agree with the declared dependencies. Unparsable
+    /// files are not counted, as they are only warned about.
+    pub fn is_empty(&self) -> bool {
+        self.undeclared.is_empty() && self.unused.is_empty()
+    }
+}
+
+impl fmt::Display for ImportReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (distribution, modules) in &self.undeclared {
+            let modules = modules.iter().cloned().collect::<Vec<String>>();
+            writeln!(
+                f,
+                "{} is imported (as {}) but not declared",
+                distribution,
+                modules.join(", ")
+            )?;
+        }
+        for requirement in &self.unused {
+            writeln!(f, "{} is declared but never imported", requirement)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn map_distributions() {
+        let mut scanner = ImportScanner::default();
+        assert_eq!(scanner.distribution("yaml"), "PyYAML");
+        assert_eq!(scanner.distribution("google.protobuf.message"), "protobuf");
+        assert_eq!(scanner.distribution("requests.adapters"), "requests");
+        scanner.map("ruamel.yaml", "ruamel.yaml");
+        assert_eq!(scanner.distribution("ruamel.yaml"), "ruamel.yaml");
+    }
+
+    #[test]
+    fn skip_stdlib_modules() {
+        for module in ["os", "ntpath", "sre_parse", "pyexpat", "_collections_abc"] {
+            assert!(is_stdlib(module));
+        }
+        assert!(!is_stdlib("psycopg2"));
+        assert_eq!(
+            ImportScanner::default().distribution("psycopg2"),
+            "psycopg2"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn find_sources_without_following_symlinks() {
+        let curr_dir = std::env::current_dir().unwrap();
+        let dir = curr_dir.join("tests/outputs/find_sources__symlink");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("spam")).unwrap();
+        std::fs::write(dir.join("spam/app.py"), "import flask\n").unwrap();
+        std::os::unix::fs::symlink("..", dir.join("spam/loop")).unwrap();
+
+        let mut files = Vec::new();
+        ImportScanner::find_sources(&dir, &mut files);
+        assert_eq!(files, vec![dir.join("spam/app.py")]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn compare_imports() {
+        let scanner = ImportScanner::default();
+        let mut imports = Imports::default();
+        for module in ["yaml", "Flask", "pytest"] {
+            imports.modules.insert(
+                module.to_string(),
+                BTreeSet::from([PathBuf::from("app.py")]),
+            );
+        }
+        let report = scanner.compare(
+            &imports,
+            &["flask>=3.0".to_string(), "numpy".to_string()],
+            &["pytest".to_string()],
+        );
+        assert_eq!(
+            report.undeclared,
+            BTreeMap::from([("PyYAML".to_string(), BTreeSet::from(["yaml".to_string()]))])
+        );
+        assert_eq!(report.unused, vec!["numpy".to_string()]);
+    }
+}
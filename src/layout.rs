@@ -0,0 +1,385 @@
+//! Discovers the packages, modules and data files of a project directory so
+//! that scaffolded build specs can declare them.
+use crate::error::{Error, Result};
+#[cfg(feature = "python")]
+use pyo3::pyclass;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Directories of a flat layout which are never packages to distribute.
+const EXCLUDED_DIRS: &[&str] = &[
+    "benchmark",
+    "benchmarks",
+    "bin",
+    "build",
+    "ci",
+    "dist",
+    "doc",
+    "docs",
+    "env",
+    "example",
+    "examples",
+    "node_modules",
+    "scripts",
+    "site-packages",
+    "test",
+    "tests",
+    "tools",
+    "venv",
+];
+
+/// Modules of a flat layout which configure the project rather than belong to it.
+const EXCLUDED_MODULES: &[&str] = &[
+    "conanfile",
+    "conftest",
+    "dodo",
+    "fabfile",
+    "manage",
+    "noxfile",
+    "pavement",
+    "setup",
+    "tasks",
+    "test",
+    "tests",
+    "toxfile",
+];
+
+/// How the importable code of a project is laid out on disk.
+#[cfg_attr(feature = "python", pyclass(get_all, set_all))]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PackageLayout {
+    /// Directory holding the code relative to the project root (e.g., `src`),
+    /// or `None` for a flat layout.
+    pub package_dir: Option<String>,
+    /// Top-level packages, e.g., `spam` for `src/spam/__init__.py`.
+    pub packages: Vec<String>,
+    /// Whether any package is a namespace package, i.e., has no `__init__.py`.
+    pub namespaces: bool,
+    /// Top-level modules, e.g., `eggs` for `src/eggs.py`.
+    pub py_modules: Vec<String>,
+    /// Files other than Python sources (including `py.typed`) keyed by the
+    /// dotted name of their package, relative to the package's directory.
+    pub package_data: BTreeMap<String, Vec<String>>,
+}
+
+impl PackageLayout {
+    /// Inspects a project directory, preferring a `src` layout when `src/` holds
+    /// any package or module.
+    pub fn discover(root: &Path) -> Result<Self> {
+        if !root.is_dir() {
+            return Err(Error::InvalidArgument(format!(
+                "Failed to discover packages. {:#?} is not a directory.",
+                root.to_str()
+            )));
+        }
+        let src = root.join("src");
+        if src.is_dir() {
+            let mut layout = Self::discover_in(&src, false);
+            if !layout.is_empty() {
+                layout.package_dir = Some("src".to_string());
+                return Ok(layout);
+            }
+        }
+        Ok(Self::discover_in(root, true))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.packages.is_empty() && self.py_modules.is_empty()
+    }
+
+    fn discover_in(base: &Path, flat: bool) -> Self {
+        let mut layout = Self::default();
+        for (name, path, is_dir) in Self::sorted_entries(base) {
+            if is_dir {
+                if !Self::is_identifier(&name)
+                    || (flat && EXCLUDED_DIRS.contains(&name.as_str()))
+                    || path.join("pyvenv.cfg").is_file()
+                {
+                    continue;
+                }
+                if !Self::contains_python(&path) {
+                    continue;
+                }
+                layout.namespaces |= !path.join("__init__.py").is_file();
+                layout.collect_package(&name, &path);
+                layout.packages.push(name);
+            } else if let Some(module) = name.strip_suffix(".py") {
+                if !Self::is_identifier(module) || (flat && EXCLUDED_MODULES.contains(&module)) {
+                    continue;
+                }
+                layout.py_modules.push(module.to_string());
+            }
+        }
+        layout
+    }
+
+    /// Records the data files of a package, and recurses into its subpackages.
+    fn collect_package(&mut self, package: &str, dir: &Path) {
+        let mut data = Vec::new();
+        self.collect_data(package, dir, dir, &mut data);
+        if !data.is_empty() {
+            self.package_data.insert(package.to_string(), data);
+        }
+    }
+
+    fn collect_data(
+        &mut self,
+        package: &str,
+        package_root: &Path,
+        dir: &Path,
+        data: &mut Vec<String>,
+    ) {
+        for (name, path, is_dir) in Self::sorted_entries(dir) {
+            if is_dir {
+                if name == "__pycache__" || name.starts_with('.') {
+                    continue;
+                }
+                // A directory holding Python sources is a (possibly namespace)
+                // subpackage, whose data is recorded separately.
+                if Self::is_identifier(&name) && Self::contains_python(&path) {
+                    self.namespaces |= !path.join("__init__.py").is_file();
+                    self.collect_package(&format!("{}.{}", package, name), &path);
+                } else {
+                    self.collect_data(package, package_root, &path, data);
+                }
+            } else if !name.ends_with(".py") && !name.ends_with(".pyc") && !name.starts_with('.') {
+                if let Ok(relative) = path.strip_prefix(package_root) {
+                    data.push(relative.to_string_lossy().replace('\\', "/"));
+                }
+            }
+        }
+    }
+
+    fn contains_python(dir: &Path) -> bool {
+        Self::sorted_entries(dir)
+            .into_iter()
+            .any(|(name, path, is_dir)| {
+                if is_dir {
+                    name != "__pycache__" && !name.starts_with('.') && Self::contains_python(&path)
+                } else {
+                    name.ends_with(".py")
+                }
+            })
+    }
+
+    fn is_identifier(name: &str) -> bool {
+        let mut chars = name.chars();
+        chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+            && chars.all(|c| c.is_alphanumeric() || c == '_')
+    }
+
+    /// The entries of `dir` sorted by name, and whether each is a directory.
+    /// Symlinked directories are left out rather than followed, as they may
+    /// loop back.
+    fn sorted_entries(dir: &Path) -> Vec<(String, std::path::PathBuf, bool)> {
+        let mut entries = match dir.read_dir() {
+            Ok(entries) => entries
+                .flatten()
+                .filter_map(|entry| {
+                    let file_type = entry.file_type().ok()?;
+                    let path = entry.path();
+                    if file_type.is_symlink() && path.is_dir() {
+                        return None;
+                    }
+                    Some((
+                        entry.file_name().to_string_lossy().to_string(),
+                        path,
+                        file_type.is_dir(),
+                    ))
+                })
+                .collect::<Vec<_>>(),
+            Err(_) => Vec::new(),
+        };
+        entries.sort();
+        entries
+    }
+
+    /// Returns the `[tool]` tables declaring this layout for a build backend,
+    /// defaulting to setuptools for unknown backends.
+    pub fn to_tool_table(&self, build_backend: &str) -> toml::Table {
+        let strings = |values: Vec<String>| {
+            toml::Value::Array(values.into_iter().map(toml::Value::String).collect())
+        };
+        let prefix = match &self.package_dir {
+            Some(package_dir) => format!("{}/", package_dir),
+            None => String::new(),
+        };
+        let mut tool = toml::Table::new();
+        match build_backend.split('.').next().unwrap_or_default() {
+            "hatchling" => {
+                let mut wheel = toml::Table::new();
+                let mut include = Vec::new();
+                for package in self.packages.iter() {
+                    include.push(format!("{}{}", prefix, package));
+                }
+                for module in self.py_modules.iter() {
+                    include.push(format!("{}{}.py", prefix, module));
+                }
+                wheel.insert("only-include".to_string(), strings(include));
+                if let Some(package_dir) = &self.package_dir {
+                    wheel.insert("sources".to_string(), strings(vec![package_dir.clone()]));
+                }
+                tool.insert(
+                    "hatch".to_string(),
+                    Self::nested(&["build", "targets", "wheel"], wheel),
+                );
+            }
+            "flit_core" => {
+                // Flit builds a single top-level package or module.
+                if let Some(name) = self.packages.iter().chain(self.py_modules.iter()).next() {
+                    let mut module = toml::Table::new();
+                    module.insert("name".to_string(), toml::Value::String(name.clone()));
+                    tool.insert("flit".to_string(), Self::nested(&["module"], module));
+                }
+            }
+            "poetry" => {
+                let mut packages = Vec::new();
+                for name in self.packages.iter().chain(self.py_modules.iter()) {
+                    let mut include = toml::Table::new();
+                    let file = if self.py_modules.contains(name) {
+                        format!("{}.py", name)
+                    } else {
+                        name.clone()
+                    };
+                    include.insert("include".to_string(), toml::Value::String(file));
+                    if let Some(package_dir) = &self.package_dir {
+                        include
+                            .insert("from".to_string(), toml::Value::String(package_dir.clone()));
+                    }
+                    packages.push(toml::Value::Table(include));
+                }
+                let mut poetry = toml::Table::new();
+                poetry.insert("packages".to_string(), toml::Value::Array(packages));
+                tool.insert("poetry".to_string(), toml::Value::Table(poetry));
+            }
+            "pdm" => {
+                let mut build = toml::Table::new();
+                if let Some(package_dir) = &self.package_dir {
+                    build.insert(
+                        "package-dir".to_string(),
+                        toml::Value::String(package_dir.clone()),
+                    );
+                }
+                let mut includes = Vec::new();
+                for package in self.packages.iter() {
+                    includes.push(format!("{}{}", prefix, package));
+                }
+                for module in self.py_modules.iter() {
+                    includes.push(format!("{}{}.py", prefix, module));
+                }
+                build.insert("includes".to_string(), strings(includes));
+                tool.insert("pdm".to_string(), Self::nested(&["build"], build));
+            }
+            _ => {
+                let mut setuptools = toml::Table::new();
+                if !self.py_modules.is_empty() {
+                    setuptools.insert("py-modules".to_string(), strings(self.py_modules.clone()));
+                }
+                if !self.packages.is_empty() {
+                    let mut find = toml::Table::new();
+                    if let Some(package_dir) = &self.package_dir {
+                        find.insert("where".to_string(), strings(vec![package_dir.clone()]));
+                    }
+                    find.insert("include".to_string(), strings(self.include_patterns()));
+                    find.insert(
+                        "namespaces".to_string(),
+                        toml::Value::Boolean(self.namespaces),
+                    );
+                    let mut packages = toml::Table::new();
+                    packages.insert("find".to_string(), toml::Value::Table(find));
+                    setuptools.insert("packages".to_string(), toml::Value::Table(packages));
+                } else if let Some(package_dir) = &self.package_dir {
+                    let mut dirs = toml::Table::new();
+                    dirs.insert(String::new(), toml::Value::String(package_dir.clone()));
+                    setuptools.insert("package-dir".to_string(), toml::Value::Table(dirs));
+                }
+                if !self.package_data.is_empty() {
+                    let package_data = self
+                        .package_data
+                        .iter()
+                        .map(|(package, files)| (package.clone(), strings(files.clone())))
+                        .collect::<toml::Table>();
+                    setuptools.insert("package-data".to_string(), toml::Value::Table(package_data));
+                }
+                tool.insert("setuptools".to_string(), toml::Value::Table(setuptools));
+            }
+        }
+        tool
+    }
+
+    /// Patterns matching the top-level packages and their subpackages, as given
+    /// to `find_packages(include=...)`.
+    pub fn include_patterns(&self) -> Vec<String> {
+        self.packages
+            .iter()
+            .flat_map(|package| [package.clone(), format!("{}.*", package)])
+            .collect()
+    }
+
+    fn nested(keys: &[&str], table: toml::Table) -> toml::Value {
+        keys.iter()
+            .rev()
+            .fold(toml::Value::Table(table), |value, key| {
+                let mut table = toml::Table::new();
+                table.insert(key.to_string(), value);
+                toml::Value::Table(table)
+            })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn discover_src_layout() {
+        let curr_dir = env::current_dir().unwrap();
+        let path_str = format!("{}/tests/inputs/layout_project", curr_dir.to_str().unwrap());
+        let layout = PackageLayout::discover(Path::new(&path_str)).unwrap();
+        assert_eq!(layout.package_dir, Some("src".to_string()));
+        assert_eq!(layout.packages, vec!["acme", "spam"]);
+        assert!(layout.namespaces);
+        assert_eq!(layout.py_modules, vec!["ham"]);
+        assert_eq!(
+            layout.package_data,
+            BTreeMap::from([(
+                "spam".to_string(),
+                vec!["data/schema.json".to_string(), "py.typed".to_string()]
+            )])
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn discover_without_following_symlinks() {
+        let curr_dir = env::current_dir().unwrap();
+        let dir = curr_dir.join("tests/outputs/layout__symlink");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("spam")).unwrap();
+        std::fs::write(dir.join("spam/__init__.py"), "").unwrap();
+        std::fs::write(dir.join("spam/schema.json"), "{}").unwrap();
+        std::os::unix::fs::symlink("..", dir.join("spam/loop")).unwrap();
+        std::os::unix::fs::symlink("spam", dir.join("eggs")).unwrap();
+
+        let layout = PackageLayout::discover(&dir).unwrap();
+        assert_eq!(layout.packages, vec!["spam"]);
+        assert_eq!(
+            layout.package_data,
+            BTreeMap::from([("spam".to_string(), vec!["schema.json".to_string()])])
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn discover_flat_layout() {
+        let curr_dir = env::current_dir().unwrap();
+        let path_str = format!("{}/tests/inputs/flat_project", curr_dir.to_str().unwrap());
+        let layout = PackageLayout::discover(Path::new(&path_str)).unwrap();
+        assert_eq!(layout.package_dir, None);
+        assert_eq!(layout.packages, vec!["spam"]);
+        assert!(!layout.namespaces);
+        assert_eq!(layout.py_modules, vec!["eggs"]);
+        assert!(layout.package_data.is_empty());
+    }
+}
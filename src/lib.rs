@@ -1,112 +1,628 @@
 use generators::SpecGenerator;
-use parsers::SpecParser;
-use pyo3::{
-    exceptions::{PyNotImplementedError, PyValueError},
-    prelude::*,
-};
+use parsers::{SpecParser, SpecStrParser};
 use std::path::Path;
 
+pub mod compare;
+pub mod diff;
+pub mod dynamic;
+pub mod error;
+mod evaluate;
 pub mod generators;
+pub mod graph;
+pub mod imports;
+pub mod layout;
 pub mod parsers;
+pub mod pep508;
+#[cfg(feature = "python")]
+mod python;
 pub mod specs;
 mod utils;
 
-fn get_spec_type(path: &Path) -> PyResult<specs::PyBuildSpec> {
-    if let Some(file_name) = path.file_name() {
-        if let Some(file_name) = file_name.to_str() {
-            let t = match file_name {
-                "requirements.txt" => Some(specs::PyBuildSpec::Requirements),
-                "setup.py" => Some(specs::PyBuildSpec::Setup),
-                "pyproject.toml" => Some(specs::PyBuildSpec::PyProject),
-                _ => None,
-            };
-            if t.is_some() {
-                return Ok(t.unwrap());
+pub use error::{Error, Result};
+pub use generators::WriteOptions;
+
+fn get_spec_type(path: &Path) -> Result<specs::PyBuildSpec> {
+    // A directory is read as a virtual environment or its site-packages.
+    if parsers::SitePackagesParser::is_environment(path) {
+        return Ok(specs::PyBuildSpec::InstalledEnvironment);
+    }
+    if path.is_dir() {
+        return Err(Error::Parse(format!(
+            "Failed to parse path. {} is a directory, but neither a virtual environment nor a site-packages directory.",
+            path.display()
+        )));
+    }
+    let file_name = path.file_name().and_then(|file_name| file_name.to_str());
+    spec_type_of_name(file_name.unwrap_or_default())
+}
+
+/// Looks up the format of a build specification by its file name, e.g.,
+/// `"pyproject.toml"` or `"docs/requirements.txt"`, without touching the
+/// filesystem.
+fn spec_type_of_name(name: &str) -> Result<specs::PyBuildSpec> {
+    let file_name = Path::new(name)
+        .file_name()
+        .and_then(|file_name| file_name.to_str())
+        .unwrap_or_default();
+    let spec_type = match file_name {
+        "requirements.txt" => Some(specs::PyBuildSpec::Requirements),
+        "setup.py" => Some(specs::PyBuildSpec::Setup),
+        "pyproject.toml" => Some(specs::PyBuildSpec::PyProject),
+        "environment.yml" | "environment.yaml" => Some(specs::PyBuildSpec::CondaEnvironment),
+        "uv.lock" => Some(specs::PyBuildSpec::UvLock),
+        "PKG-INFO" | "METADATA" => Some(specs::PyBuildSpec::Metadata),
+        // PEP 751 also allows named lockfiles, e.g., pylock.dev.toml.
+        name if name.starts_with("pylock.") && name.ends_with(".toml") => {
+            Some(specs::PyBuildSpec::PyLock)
+        }
+        name if name.ends_with(".whl")
+            || name.ends_with(".tar.gz")
+            || name.ends_with(".tgz")
+            || name.ends_with(".zip") =>
+        {
+            Some(specs::PyBuildSpec::Distribution)
+        }
+        _ => None,
+    };
+    spec_type.ok_or_else(|| Error::Parse(
+        "Failed to parse filename. Must be one of: requirements.txt, setup.py, pyproject.toml, environment.yml, uv.lock, pylock.toml, PKG-INFO, METADATA, a wheel/sdist archive, or a virtual environment directory.".to_string(),
+    ))
+}
+
+/// A build specification translated to another format, along with warnings
+/// about the entries of the source which were left out.
+#[derive(Debug)]
+pub struct Translation {
+    pub contents: String,
+    pub warnings: Vec<String>,
+}
+
+fn read_requirements(source: &Path, environment: Option<&Path>) -> Result<specs::Requirements> {
+    let requirements = parsers::RequirementsParser::from_file(source)?;
+    match environment {
+        Some(environment) => {
+            let environment = parsers::SitePackagesParser::from_file(environment)?;
+            Ok(
+                graph::DependencyTree::from_installed_environment(&environment)
+                    .minimize(requirements),
+            )
+        }
+        None => Ok(requirements),
+    }
+}
+
+/// Reads the dependency tree of an installed environment. Its requirements
+/// which are missing or conflicting are given by [`graph::DependencyTree::problems`].
+pub fn tree(environment: &Path) -> Result<graph::DependencyTree> {
+    let environment = parsers::SitePackagesParser::from_file(environment)?;
+    Ok(graph::DependencyTree::from_installed_environment(
+        &environment,
+    ))
+}
+
+/// Reports dependencies which are imported by a package's sources but not
+/// declared by the build specification at `source`, or declared but never
+/// imported. The two agree when the returned report is empty.
+///
+/// The sources are looked up under `package`, defaulting to the directory of
+/// `source`, and `mappings` adds import names provided by a distribution of
+/// another name, e.g., `("yaml", "PyYAML")`.
+pub fn check_imports(
+    source: &Path,
+    package: Option<&Path>,
+    mappings: &[(String, String)],
+) -> Result<imports::ImportReport> {
+    let (requires, optional) = match get_spec_type(source)? {
+        specs::PyBuildSpec::Requirements => {
+            let requirements = parsers::RequirementsParser::from_file(source)?;
+            (requirements.requires, Vec::new())
+        }
+        specs::PyBuildSpec::Setup => {
+            let setup = parsers::SetupParser::from_file(source)?;
+            let mut optional = setup.setup_requires.unwrap_or_default();
+            for (_, mut extra_requires) in setup.extra_requires.unwrap_or_default() {
+                optional.append(&mut extra_requires);
             }
+            (setup.install_requires.unwrap_or_default(), optional)
         }
+        specs::PyBuildSpec::PyProject => {
+            let pyproject = parsers::PyProjectParser::from_file(source)?;
+            match pyproject.project {
+                Some(project) => {
+                    let mut optional = Vec::new();
+                    for (_, mut dependencies) in project.optional_dependencies.unwrap_or_default()
+                    {
+                        optional.append(&mut dependencies);
+                    }
+                    (project.dependencies.unwrap_or_default(), optional)
+                }
+                None => (Vec::new(), Vec::new()),
+            }
+        }
+        _ => {
+            return Err(Error::Unsupported(
+                "Failed to perform operation. Imports can only be checked against requirements.txt, setup.py or pyproject.toml.".to_string(),
+            ))
+        }
+    };
+    let package = package.unwrap_or_else(|| utils::directory_of(source));
+
+    let mut scanner = imports::ImportScanner::default();
+    for (module, distribution) in mappings.iter() {
+        scanner.map(module, distribution);
     }
-    return Err(PyValueError::new_err(
-        "Failed to parse filename. Must be one of: requirements.txt, setup.py, pyproject.toml.",
-    ));
+    let imports = scanner.scan(package)?;
+    Ok(scanner.compare(&imports, &requires, &optional))
 }
 
-/// Scaffolds a build specification file.
-#[pyfunction]
-fn create(destination: String) -> PyResult<()> {
-    let destination = Path::new(&destination);
-    let dest_type = get_spec_type(&destination)?;
+/// Discovers the packages of the project directory a spec is scaffolded in.
+fn discover_layout(root: &Path) -> Result<Option<layout::PackageLayout>> {
+    if !root.is_dir() {
+        return Ok(None);
+    }
+    let layout = layout::PackageLayout::discover(root)?;
+    Ok(if layout.is_empty() {
+        None
+    } else {
+        Some(layout)
+    })
+}
+
+/// Generates a scaffolded build specification of the format `dest_type`,
+/// declaring the packages found under `root`.
+fn scaffold(dest_type: specs::PyBuildSpec, root: &Path) -> Result<String> {
     match dest_type {
         specs::PyBuildSpec::Requirements => {
             let requirements = specs::Requirements::default();
-            generators::RequirementsGenerator::make_file(&destination, &requirements)?;
-            Ok(())
+            generators::RequirementsGenerator::make_string(&requirements)
         }
         specs::PyBuildSpec::Setup => {
-            let setup = specs::Setup::default();
-            generators::SetupGenerator::make_file(&destination, &setup)?;
-            Ok(())
+            let mut setup = specs::Setup::default();
+            setup.layout = discover_layout(root)?;
+            generators::SetupGenerator::make_string(&setup)
         }
         specs::PyBuildSpec::PyProject => {
-            let pyproject = specs::PyProject::default();
-            generators::PyProjectGenerator::make_file(&destination, &pyproject)?;
-            Ok(())
+            let mut pyproject = specs::PyProject::default();
+            if let Some(layout) = discover_layout(root)? {
+                pyproject.set_layout(&layout);
+            }
+            generators::PyProjectGenerator::make_string(&pyproject)
+        }
+        specs::PyBuildSpec::CondaEnvironment => {
+            let environment = specs::CondaEnvironment::default();
+            generators::CondaEnvironmentGenerator::make_string(&environment)
+        }
+        specs::PyBuildSpec::Metadata => {
+            let metadata = specs::Metadata::default();
+            generators::MetadataGenerator::make_string(&metadata)
+        }
+        specs::PyBuildSpec::InstalledEnvironment => Err(Error::Unsupported(
+            "Failed to perform operation. Installed environments are read-only.".to_string(),
+        )),
+        specs::PyBuildSpec::Distribution => Err(Error::Unsupported(
+            "Failed to perform operation. Wheels and sdists are read-only.".to_string(),
+        )),
+        specs::PyBuildSpec::UvLock | specs::PyBuildSpec::PyLock => Err(Error::Unsupported(
+            "Failed to perform operation. Lockfiles are read-only.".to_string(),
+        )),
+    }
+}
+
+/// Scaffolds a build specification file, declaring the packages found next to it.
+/// An existing file at `destination` is only overwritten as given by `options`.
+pub fn create(destination: &Path, options: &WriteOptions) -> Result<()> {
+    let dest_type = get_spec_type(&destination)?;
+    let root = utils::directory_of(destination);
+    generators::write_file(destination, &scaffold(dest_type, root)?, options)
+}
+
+/// Returns a scaffolded build specification of `target_format`, given as a
+/// file name (e.g., `"pyproject.toml"`), declaring the packages found under `root`.
+pub fn create_str(target_format: &str, root: &Path) -> Result<String> {
+    scaffold(spec_type_of_name(target_format)?, root)
+}
+
+/// A build specification read from a source of any supported format.
+enum Source {
+    Requirements(specs::Requirements),
+    Setup(specs::Setup),
+    PyProject(specs::PyProject),
+    CondaEnvironment(specs::CondaEnvironment),
+    Lockfile(specs::Lockfile),
+    /// Core metadata, as read from PKG-INFO/METADATA or a wheel/sdist.
+    Metadata(specs::Metadata),
+    InstalledEnvironment(specs::InstalledEnvironment),
+}
+
+impl Source {
+    /// Reads the build specification at `path`, which is of the format `source_type`.
+    fn from_file(
+        path: &Path,
+        source_type: specs::PyBuildSpec,
+        environment: Option<&Path>,
+        import_depth: usize,
+    ) -> Result<Self> {
+        if environment.is_some() && !matches!(source_type, specs::PyBuildSpec::Requirements) {
+            return Err(Error::InvalidArgument(
+                "Failed to perform operation. An environment can only be given when translating from requirements.txt.".to_string(),
+            ));
+        }
+        Ok(match source_type {
+            specs::PyBuildSpec::Requirements => {
+                Source::Requirements(read_requirements(path, environment)?)
+            }
+            specs::PyBuildSpec::Setup => Source::Setup(
+                parsers::SetupParser::from_file_with_import_depth(path, import_depth)?,
+            ),
+            specs::PyBuildSpec::PyProject => {
+                Source::PyProject(parsers::PyProjectParser::from_file(path)?)
+            }
+            specs::PyBuildSpec::CondaEnvironment => {
+                Source::CondaEnvironment(parsers::CondaEnvironmentParser::from_file(path)?)
+            }
+            specs::PyBuildSpec::UvLock => Source::Lockfile(parsers::UvLockParser::from_file(path)?),
+            specs::PyBuildSpec::PyLock => Source::Lockfile(parsers::PyLockParser::from_file(path)?),
+            specs::PyBuildSpec::Metadata => {
+                Source::Metadata(parsers::MetadataParser::from_file(path)?)
+            }
+            specs::PyBuildSpec::Distribution => {
+                Source::Metadata(parsers::DistributionParser::from_file(path)?)
+            }
+            specs::PyBuildSpec::InstalledEnvironment => {
+                Source::InstalledEnvironment(parsers::SitePackagesParser::from_file(path)?)
+            }
+        })
+    }
+
+    /// Parses the contents of a build specification of the format `source_type`.
+    fn from_str(contents: &str, source_type: specs::PyBuildSpec) -> Result<Self> {
+        Ok(match source_type {
+            specs::PyBuildSpec::Requirements => {
+                Source::Requirements(parsers::RequirementsParser::from_str(contents)?)
+            }
+            specs::PyBuildSpec::Setup => Source::Setup(parsers::SetupParser::from_str(contents)?),
+            specs::PyBuildSpec::PyProject => {
+                Source::PyProject(parsers::PyProjectParser::from_str(contents)?)
+            }
+            specs::PyBuildSpec::CondaEnvironment => {
+                Source::CondaEnvironment(parsers::CondaEnvironmentParser::from_str(contents)?)
+            }
+            specs::PyBuildSpec::UvLock => Source::Lockfile(parsers::UvLockParser::from_str(contents)?),
+            specs::PyBuildSpec::PyLock => Source::Lockfile(parsers::PyLockParser::from_str(contents)?),
+            specs::PyBuildSpec::Metadata => {
+                Source::Metadata(parsers::MetadataParser::from_str(contents)?)
+            }
+            specs::PyBuildSpec::Distribution | specs::PyBuildSpec::InstalledEnvironment => {
+                return Err(Error::Unsupported(
+                    "Failed to perform operation. Wheels, sdists and virtual environments can only be read from a path.".to_string(),
+                ))
+            }
+        })
+    }
+
+    /// Parses the contents of a build specification of the format `source_type`
+    /// as if they were of the file at `path`, reading the files they refer to,
+    /// e.g., a requirements file or a version file, relative to it.
+    fn from_str_at(contents: &str, source_type: specs::PyBuildSpec, path: &Path) -> Result<Self> {
+        Ok(match source_type {
+            specs::PyBuildSpec::Setup => {
+                Source::Setup(parsers::SetupParser::from_str_at(contents, path)?)
+            }
+            specs::PyBuildSpec::PyProject => {
+                Source::PyProject(parsers::PyProjectParser::from_str_at(contents, path)?)
+            }
+            _ => Self::from_str(contents, source_type)?,
+        })
+    }
+
+    /// Warnings about the entries which are left out when translating the build
+    /// specification to `dest_type`, e.g., the conda build strings of an
+    /// environment.yml, hashes of a lockfile which cannot be pinned on every
+    /// requirement, or a version source of a setup.py setuptools cannot read.
+    fn warnings(&self, dest_type: specs::PyBuildSpec) -> Vec<String> {
+        match self {
+            Source::Setup(specs::Setup {
+                version: None,
+                dynamic_version: Some(dynamic_version),
+                ..
+            }) if matches!(dest_type, specs::PyBuildSpec::PyProject) => dynamic_version
+                .to_tool_table("setuptools.build_meta")
+                .err()
+                .map(|reason| format!("leaving the dynamic version unconfigured, as {}", reason))
+                .into_iter()
+                .collect(),
+            Source::CondaEnvironment(environment) => environment
+                .partition()
+                .1
+                .iter()
+                .map(|entry| format!("skipping untranslatable entry {}", entry))
+                .collect(),
+            Source::Lockfile(lockfile) => match lockfile.unhashed_packages() {
+                unhashed if unhashed.is_empty() => Vec::new(),
+                unhashed => vec![format!(
+                    "leaving out all hashes, as pip requires one on every requirement and {} has none",
+                    unhashed.join(", ")
+                )],
+            },
+            _ => Vec::new(),
+        }
+    }
+
+    /// Generates the contents of a build specification of the format
+    /// `dest_type`, along with the warnings of the translation.
+    fn translation(
+        self,
+        dest_type: specs::PyBuildSpec,
+        root: Option<&Path>,
+    ) -> Result<Translation> {
+        let warnings = self.warnings(dest_type);
+        Ok(Translation {
+            contents: self.translate(dest_type, root)?,
+            warnings,
+        })
+    }
+
+    /// Converts the build specification to a pyproject.toml, the model through
+    /// which specs of different formats are compared.
+    fn into_pyproject(self) -> specs::PyProject {
+        match self {
+            Source::Requirements(requirements) => specs::PyProject::from_requirements(requirements),
+            Source::Setup(setup) => specs::PyProject::from_setup(setup),
+            Source::PyProject(pyproject) => pyproject,
+            Source::CondaEnvironment(environment) => {
+                specs::PyProject::from_conda_environment(environment)
+            }
+            Source::Lockfile(lockfile) => {
+                specs::PyProject::from_requirements(specs::Requirements::from_lockfile(lockfile))
+            }
+            Source::Metadata(metadata) => specs::PyProject::from_metadata(metadata),
+            Source::InstalledEnvironment(environment) => {
+                specs::PyProject::from_installed_environment(environment)
+            }
+        }
+    }
+
+    /// Generates the contents of a build specification of the format `dest_type`.
+    /// Files the source refers to, e.g., its readme, are read relative to `root`.
+    fn translate(self, dest_type: specs::PyBuildSpec, root: Option<&Path>) -> Result<String> {
+        match (self, dest_type) {
+            (Source::Requirements(requirements), specs::PyBuildSpec::PyProject) => {
+                let pyproject = specs::PyProject::from_requirements(requirements);
+                generators::PyProjectGenerator::make_string(&pyproject)
+            }
+            (Source::Setup(setup), specs::PyBuildSpec::PyProject) => {
+                let pyproject = specs::PyProject::from_setup(setup);
+                generators::PyProjectGenerator::make_string(&pyproject)
+            }
+            (Source::Requirements(requirements), specs::PyBuildSpec::Setup) => {
+                let setup = specs::Setup::from_requirements(requirements);
+                generators::SetupGenerator::make_string(&setup)
+            }
+            (Source::PyProject(pyproject), specs::PyBuildSpec::Setup) => {
+                let setup = specs::Setup::from_pyproject(pyproject);
+                generators::SetupGenerator::make_string(&setup)
+            }
+            (Source::Setup(setup), specs::PyBuildSpec::Requirements) => {
+                let requirements = specs::Requirements::from_setup(setup);
+                generators::RequirementsGenerator::make_string(&requirements)
+            }
+            (Source::PyProject(pyproject), specs::PyBuildSpec::Requirements) => {
+                let requirements = specs::Requirements::from_pyproject(pyproject);
+                generators::RequirementsGenerator::make_string(&requirements)
+            }
+            (Source::CondaEnvironment(environment), specs::PyBuildSpec::Requirements) => {
+                let requirements = specs::Requirements::from_conda_environment(environment);
+                generators::RequirementsGenerator::make_string(&requirements)
+            }
+            (Source::CondaEnvironment(environment), specs::PyBuildSpec::Setup) => {
+                let setup = specs::Setup::from_conda_environment(environment);
+                generators::SetupGenerator::make_string(&setup)
+            }
+            (Source::CondaEnvironment(environment), specs::PyBuildSpec::PyProject) => {
+                let pyproject = specs::PyProject::from_conda_environment(environment);
+                generators::PyProjectGenerator::make_string(&pyproject)
+            }
+            (Source::Requirements(requirements), specs::PyBuildSpec::CondaEnvironment) => {
+                let environment = specs::CondaEnvironment::from_requirements(requirements);
+                generators::CondaEnvironmentGenerator::make_string(&environment)
+            }
+            (Source::Setup(setup), specs::PyBuildSpec::CondaEnvironment) => {
+                let environment = specs::CondaEnvironment::from_setup(setup);
+                generators::CondaEnvironmentGenerator::make_string(&environment)
+            }
+            (Source::PyProject(pyproject), specs::PyBuildSpec::CondaEnvironment) => {
+                let environment = specs::CondaEnvironment::from_pyproject(pyproject);
+                generators::CondaEnvironmentGenerator::make_string(&environment)
+            }
+            (Source::Metadata(metadata), specs::PyBuildSpec::Requirements) => {
+                let requirements = specs::Requirements::from_metadata(metadata);
+                generators::RequirementsGenerator::make_string(&requirements)
+            }
+            (Source::Metadata(metadata), specs::PyBuildSpec::Setup) => {
+                let setup = specs::Setup::from_metadata(metadata);
+                generators::SetupGenerator::make_string(&setup)
+            }
+            (Source::Metadata(metadata), specs::PyBuildSpec::PyProject) => {
+                let pyproject = specs::PyProject::from_metadata(metadata);
+                generators::PyProjectGenerator::make_string(&pyproject)
+            }
+            (Source::Requirements(requirements), specs::PyBuildSpec::Metadata) => {
+                let metadata = specs::Metadata::from_requirements(requirements);
+                generators::MetadataGenerator::make_string(&metadata)
+            }
+            (Source::Setup(setup), specs::PyBuildSpec::Metadata) => {
+                let metadata = metadata_from_pyproject(specs::PyProject::from_setup(setup), root)?;
+                generators::MetadataGenerator::make_string(&metadata)
+            }
+            (Source::PyProject(pyproject), specs::PyBuildSpec::Metadata) => {
+                let metadata = metadata_from_pyproject(pyproject, root)?;
+                generators::MetadataGenerator::make_string(&metadata)
+            }
+            (Source::Lockfile(lockfile), specs::PyBuildSpec::Requirements) => {
+                let requirements = specs::Requirements::from_lockfile(lockfile);
+                generators::RequirementsGenerator::make_string(&requirements)
+            }
+            (Source::InstalledEnvironment(environment), specs::PyBuildSpec::Requirements) => {
+                let requirements = specs::Requirements::from_installed_environment(environment);
+                generators::RequirementsGenerator::make_string(&requirements)
+            }
+            (Source::InstalledEnvironment(environment), specs::PyBuildSpec::Setup) => {
+                let setup = specs::Setup::from_installed_environment(environment);
+                generators::SetupGenerator::make_string(&setup)
+            }
+            (Source::InstalledEnvironment(environment), specs::PyBuildSpec::PyProject) => {
+                let pyproject = specs::PyProject::from_installed_environment(environment);
+                generators::PyProjectGenerator::make_string(&pyproject)
+            }
+            _ => Err(Error::Unsupported("Failed to perform operation. Only unique conversions between requirements.txt, setup.py, pyproject.toml, environment.yml and PKG-INFO/METADATA, from uv.lock and pylock.toml to requirements.txt, or from wheels, sdists and virtual environments to requirements.txt, setup.py and pyproject.toml, are allowed.".to_string())),
         }
     }
 }
 
+/// Converts a pyproject.toml to the metadata of a distribution, reading the
+/// description from a readme given as a file relative to `root`, if known.
+fn metadata_from_pyproject(
+    pyproject: specs::PyProject,
+    root: Option<&Path>,
+) -> Result<specs::Metadata> {
+    let readme = pyproject
+        .project
+        .as_ref()
+        .and_then(|project| project.readme.clone());
+    let mut metadata = specs::Metadata::from_pyproject(pyproject);
+    if let (None, Some(readme), Some(root)) = (&metadata.description, readme, root) {
+        metadata.description = readme.read(root)?;
+    }
+    Ok(metadata)
+}
+
 /// Transpiles a source Python package build specification file (e.g., setup.py)
 /// to another (e.g., pyproject.toml).
-#[pyfunction]
-fn translate(source: String, destination: String) -> PyResult<()> {
-    let source = Path::new(&source);
-    let destination = Path::new(&destination);
+///
+/// When translating from a frozen requirements.txt, an installed `environment`
+/// may be given to keep only the requirements no other package depends on.
+/// When translating from a setup.py, imports of local modules are followed
+/// `import_depth` modules deep. An existing file at `destination` is only
+/// overwritten as given by `options`. Returns warnings about the entries of
+/// `source` which were left out.
+pub fn translate(
+    source: &Path,
+    destination: &Path,
+    environment: Option<&Path>,
+    import_depth: usize,
+    options: &WriteOptions,
+) -> Result<Vec<String>> {
     let source_type = get_spec_type(&source)?;
     let dest_type = get_spec_type(&destination)?;
-    match (source_type, dest_type) {
-        (specs::PyBuildSpec::Requirements, specs::PyBuildSpec::PyProject) => {
-            let requirements = parsers::RequirementsParser::from_file(&source)?;
-            let pyproject = specs::PyProject::from_requirements(requirements);
-            generators::PyProjectGenerator::make_file(&destination, &pyproject)?;
-            Ok(())
-        }
-        (specs::PyBuildSpec::Setup, specs::PyBuildSpec::PyProject) => {
-            let setup = parsers::SetupParser::from_file(&source)?;
-            let pyproject = specs::PyProject::from_setup(setup);
-            generators::PyProjectGenerator::make_file(&destination, &pyproject)?;
-            Ok(())
-        }
-        (specs::PyBuildSpec::Requirements, specs::PyBuildSpec::Setup) => {
-            let requirements = parsers::RequirementsParser::from_file(&source)?;
-            let setup = specs::Setup::from_requirements(requirements);
-            generators::SetupGenerator::make_file(&destination, &setup)?;
-            Ok(())
-        }
-        (specs::PyBuildSpec::PyProject, specs::PyBuildSpec::Setup) => {
-            let pyproject = parsers::PyProjectParser::from_file(&source)?;
-            let setup = specs::Setup::from_pyproject(pyproject);
-            generators::SetupGenerator::make_file(&destination, &setup)?;
-            Ok(())
-        }
-        (specs::PyBuildSpec::Setup, specs::PyBuildSpec::Requirements) => {
-            let setup = parsers::SetupParser::from_file(&source)?;
-            let requirements = specs::Requirements::from_setup(setup);
-            generators::RequirementsGenerator::make_file(&destination, &requirements)?;
-            Ok(())
-        }
-        (specs::PyBuildSpec::PyProject, specs::PyBuildSpec::Requirements) => {
-            let pyproject = parsers::PyProjectParser::from_file(&source)?;
-            let requirements = specs::Requirements::from_pyproject(pyproject);
-            generators::RequirementsGenerator::make_file(&destination, &requirements)?;
-            Ok(())
-        }
-        _ => Err(PyNotImplementedError::new_err("Failed to perform operation. Only unique conversions between requirements.txt, setup.py and pyproject.toml are allowed.")),
-    }
+    let root = utils::directory_of(source);
+    let source = Source::from_file(source, source_type, environment, import_depth)?;
+    let translation = source.translation(dest_type, Some(root))?;
+    generators::write_file(destination, &translation.contents, options)?;
+    Ok(translation.warnings)
+}
+
+/// Compares the requirements of the build specification at `destination`
+/// against those it would hold if translated from `source`, normalizing them
+/// rather than comparing them as written. The two agree when the returned
+/// report is empty.
+pub fn check(
+    source: &Path,
+    destination: &Path,
+    environment: Option<&Path>,
+    import_depth: usize,
+) -> Result<compare::DependencyReport> {
+    let source_type = get_spec_type(source)?;
+    let dest_type = get_spec_type(destination)?;
+    let expected = Source::from_file(source, source_type, environment, import_depth)?
+        .translate(dest_type, Some(utils::directory_of(source)))?;
+    // The translation is read as it would be once written to `destination`.
+    let expected = Source::from_str_at(&expected, dest_type, destination)?.into_pyproject();
+    let actual = Source::from_file(destination, dest_type, None, import_depth)?.into_pyproject();
+    Ok(compare::compare_dependencies(
+        &compare::dependency_groups(&actual),
+        &compare::dependency_groups(&expected),
+    ))
+}
+
+/// Merges the requirements translated from `source` into the existing build
+/// specification file at `destination`, returning its contents with only the
+/// fields holding them replaced, e.g., `project.dependencies` of a
+/// pyproject.toml or `install_requires` of a setup.py.
+pub fn sync_file(
+    source: &Path,
+    destination: &Path,
+    environment: Option<&Path>,
+    import_depth: usize,
+) -> Result<Translation> {
+    let source_type = get_spec_type(source)?;
+    let dest_type = get_spec_type(destination)?;
+    let Translation {
+        contents: derived,
+        warnings,
+    } = Source::from_file(source, source_type, environment, import_depth)?
+        .translation(dest_type, Some(utils::directory_of(source)))?;
+    let contents = utils::read_file(destination)?;
+    let contents = match dest_type {
+        specs::PyBuildSpec::Requirements => generators::RequirementsGenerator::merge_string(
+            &contents,
+            &parsers::RequirementsParser::from_str(&derived)?,
+        ),
+        specs::PyBuildSpec::Setup => generators::SetupGenerator::merge_string(
+            &contents,
+            &parsers::SetupParser::from_str_at(&derived, destination)?,
+        ),
+        specs::PyBuildSpec::PyProject => generators::PyProjectGenerator::merge_string(
+            &contents,
+            &parsers::PyProjectParser::from_str_at(&derived, destination)?,
+        ),
+        _ => Err(Error::Unsupported(
+            "Failed to perform operation. Only requirements.txt, setup.py and pyproject.toml can be synced.".to_string(),
+        )),
+    }?;
+    Ok(Translation { contents, warnings })
+}
+
+/// Compares the build specification files at `old` and `new`, of any formats,
+/// reporting how their metadata, requirements, extras and scripts differ. The
+/// two agree when the returned report is empty.
+pub fn diff_specs(old: &Path, new: &Path, import_depth: usize) -> Result<compare::SpecReport> {
+    let old_spec = Source::from_file(old, get_spec_type(old)?, None, import_depth)?;
+    let new_spec = Source::from_file(new, get_spec_type(new)?, None, import_depth)?;
+    Ok(compare::compare_specs(
+        &old_spec.into_pyproject(),
+        &new_spec.into_pyproject(),
+    ))
+}
+
+/// Reads the build specification file at `source` and returns its translation
+/// to `target_format`, given as a file name (e.g., `"pyproject.toml"`). The
+/// format of `source` is detected from its name, unless `source_format` is given,
+/// e.g., `"requirements.txt"` for a requirements-dev.txt.
+pub fn translate_file(
+    source: &Path,
+    source_format: Option<&str>,
+    target_format: &str,
+    environment: Option<&Path>,
+    import_depth: usize,
+) -> Result<Translation> {
+    let source_type = match source_format {
+        Some(source_format) => spec_type_of_name(source_format)?,
+        None => get_spec_type(source)?,
+    };
+    let dest_type = spec_type_of_name(target_format)?;
+    Source::from_file(source, source_type, environment, import_depth)?
+        .translation(dest_type, Some(utils::directory_of(source)))
 }
 
-/// A Python module implemented in Rust.
-#[pymodule]
-fn _babelone_core(_py: Python, m: &PyModule) -> PyResult<()> {
-    m.add_function(wrap_pyfunction!(create, m)?)?;
-    m.add_function(wrap_pyfunction!(translate, m)?)?;
-    Ok(())
+/// Transpiles the contents of a build specification to those of another,
+/// without touching the filesystem. Formats are given by their file names,
+/// e.g., `"setup.py"` or `"pyproject.toml"`.
+pub fn translate_str(
+    source_text: &str,
+    source_format: &str,
+    target_format: &str,
+) -> Result<Translation> {
+    let source_type = spec_type_of_name(source_format)?;
+    let dest_type = spec_type_of_name(target_format)?;
+    Source::from_str(source_text, source_type)?.translation(dest_type, None)
 }
@@ -0,0 +1,424 @@
+//! Standalone command line interface, mirroring the Python CLI in
+//! `python/babelone_cli` for environments without a Python interpreter.
+use clap::{Args, Parser, Subcommand};
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use babelone::generators;
+use babelone::parsers::SetupParser;
+use babelone::WriteOptions;
+
+/// babelone /ˌbæbɪˈloʊn/
+///
+/// Scaffold or translate between Python package build specification files,
+/// including requirements.txt, setup.py, pyproject.toml, and environment.yml.
+#[derive(Parser)]
+#[command(name = "babelone", version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Scaffold a build spec file and save at the OUTPUT path.
+    ///
+    /// The packages, modules and data files found in its directory are declared
+    /// in a scaffolded setup.py or pyproject.toml. OUTPUT may be - to write
+    /// to stdout, declaring those of the current directory.
+    Create {
+        output: PathBuf,
+        /// Format of OUTPUT, given as a file name, e.g., pyproject.toml.
+        /// Required when OUTPUT is -.
+        #[arg(long)]
+        to: Option<String>,
+        #[command(flatten)]
+        write: WriteArgs,
+    },
+    /// Translate the file at the INPUT path to another format saved at
+    /// the OUTPUT path.
+    ///
+    /// INPUT may also be a wheel (.whl) or sdist (.tar.gz), whose metadata is
+    /// read without unpacking the archive, or a virtual environment directory,
+    /// whose installed packages are frozen. Either path may be - to read from
+    /// stdin or write to stdout.
+    Translate {
+        #[arg(value_parser = existing_path_or_stdin)]
+        input: PathBuf,
+        output: PathBuf,
+        /// Format of INPUT, given as a file name, e.g., setup.py. Required when
+        /// INPUT is -.
+        #[arg(long)]
+        from: Option<String>,
+        /// Format of OUTPUT, given as a file name, e.g., pyproject.toml.
+        /// Required when OUTPUT is -.
+        #[arg(long)]
+        to: Option<String>,
+        /// Virtual environment in which a frozen requirements.txt INPUT is
+        /// installed, used to keep only its top-level requirements.
+        #[arg(long, value_parser = existing_dir)]
+        env: Option<PathBuf>,
+        /// How many modules deep imports of local modules in a setup.py INPUT
+        /// are followed.
+        #[arg(long, default_value_t = SetupParser::DEFAULT_IMPORT_DEPTH)]
+        import_depth: usize,
+        #[command(flatten)]
+        write: WriteArgs,
+    },
+    /// Check that the requirements declared at the DEST path agree with those
+    /// translated from the SOURCE path.
+    ///
+    /// Requirements are normalized rather than compared as written, and any
+    /// which differ are reported, e.g., to catch a generated requirements.txt
+    /// drifting from its pyproject.toml in CI.
+    Check {
+        #[arg(value_parser = existing_path)]
+        source: PathBuf,
+        #[arg(value_parser = existing_path)]
+        dest: PathBuf,
+        /// Virtual environment in which a frozen requirements.txt SOURCE is
+        /// installed, used to keep only its top-level requirements.
+        #[arg(long, value_parser = existing_dir)]
+        env: Option<PathBuf>,
+        /// How many modules deep imports of local modules in a setup.py are
+        /// followed.
+        #[arg(long, default_value_t = SetupParser::DEFAULT_IMPORT_DEPTH)]
+        import_depth: usize,
+    },
+    /// Update the requirements of the existing file at the DEST path to those
+    /// translated from the SOURCE path.
+    ///
+    /// Only project.dependencies and project.optional-dependencies of a
+    /// pyproject.toml, install_requires and extras_require of a setup.py, or
+    /// the requirements of a requirements.txt are replaced, and everything
+    /// else is left as it is.
+    Sync {
+        #[arg(value_parser = existing_path)]
+        source: PathBuf,
+        #[arg(value_parser = existing_path)]
+        dest: PathBuf,
+        /// Virtual environment in which a frozen requirements.txt SOURCE is
+        /// installed, used to keep only its top-level requirements.
+        #[arg(long, value_parser = existing_dir)]
+        env: Option<PathBuf>,
+        /// How many modules deep imports of local modules in a setup.py
+        /// SOURCE are followed.
+        #[arg(long, default_value_t = SetupParser::DEFAULT_IMPORT_DEPTH)]
+        import_depth: usize,
+        #[command(flatten)]
+        write: WriteArgs,
+    },
+    /// Show how the build specification at the NEW path differs from the
+    /// one at the OLD path, which may be of different formats.
+    ///
+    /// Added, removed and changed requirements, extras, scripts and metadata
+    /// fields are reported, and the exit code is 1 if there are any.
+    Diff {
+        #[arg(value_parser = existing_path)]
+        old: PathBuf,
+        #[arg(value_parser = existing_path)]
+        new: PathBuf,
+        /// Print the differences as JSON.
+        #[arg(long)]
+        json: bool,
+        /// How many modules deep imports of local modules in a setup.py are
+        /// followed.
+        #[arg(long, default_value_t = SetupParser::DEFAULT_IMPORT_DEPTH)]
+        import_depth: usize,
+    },
+    /// Show the dependency tree of the virtual environment at the
+    /// ENVIRONMENT path.
+    ///
+    /// Warns of missing or conflicting requirements.
+    Tree {
+        #[arg(default_value = ".venv", value_parser = existing_dir)]
+        environment: PathBuf,
+    },
+    /// Compare the dependencies declared at the INPUT path against those
+    /// imported by the package's .py files.
+    ///
+    /// INPUT may be a requirements.txt, setup.py or pyproject.toml, and any
+    /// dependencies which are undeclared or unused are reported.
+    Imports {
+        #[arg(value_parser = existing_path)]
+        input: PathBuf,
+        /// Directory holding the package's sources. Defaults to that of INPUT.
+        #[arg(long, value_parser = existing_dir)]
+        package: Option<PathBuf>,
+        /// Distribution providing an import name, e.g., yaml=PyYAML.
+        #[arg(long = "map", value_name = "IMPORT=DISTRIBUTION", value_parser = mapping)]
+        mappings: Vec<(String, String)>,
+    },
+}
+
+/// How OUTPUT is written, shared by the commands which write one.
+#[derive(Args)]
+struct WriteArgs {
+    /// Print what would be written to OUTPUT to stdout instead.
+    #[arg(long)]
+    dry_run: bool,
+    /// Overwrite OUTPUT if it already exists.
+    #[arg(long)]
+    force: bool,
+    /// Keep a copy of OUTPUT as OUTPUT.bak when overwriting it with --force.
+    #[arg(long)]
+    backup: bool,
+    /// Show a unified diff of OUTPUT against what would be written to it,
+    /// instead of writing it. Exits with 1 if OUTPUT would change.
+    #[arg(long, conflicts_with_all = ["dry_run", "force", "backup"])]
+    diff: bool,
+}
+
+impl WriteArgs {
+    fn options(&self) -> WriteOptions {
+        WriteOptions {
+            force: self.force,
+            backup: self.backup,
+        }
+    }
+}
+
+fn existing_path(path: &str) -> Result<PathBuf, String> {
+    match Path::new(path).exists() {
+        true => Ok(PathBuf::from(path)),
+        false => Err(format!("Path {:?} does not exist.", path)),
+    }
+}
+
+/// An existing path, or - for the commands which can read from stdin.
+fn existing_path_or_stdin(path: &str) -> Result<PathBuf, String> {
+    match path {
+        "-" => Ok(PathBuf::from(path)),
+        _ => existing_path(path),
+    }
+}
+
+fn existing_dir(path: &str) -> Result<PathBuf, String> {
+    match Path::new(path).is_dir() {
+        true => Ok(PathBuf::from(path)),
+        false => Err(format!("Directory {:?} does not exist.", path)),
+    }
+}
+
+fn mapping(mapping: &str) -> Result<(String, String), String> {
+    match mapping.split_once('=') {
+        Some((module, distribution))
+            if !module.trim().is_empty() && !distribution.trim().is_empty() =>
+        {
+            Ok((module.trim().to_string(), distribution.trim().to_string()))
+        }
+        _ => Err(format!("expected IMPORT=DISTRIBUTION, got {:?}", mapping)),
+    }
+}
+
+fn is_stdio(path: &Path) -> bool {
+    path == Path::new("-")
+}
+
+/// The format of the `argument` at `path`, as given by `flag`, else its file
+/// name. There is no file name to go by for stdin and stdout.
+fn format_of<'a>(
+    argument: &str,
+    path: &'a Path,
+    flag: &str,
+    format: Option<&'a str>,
+) -> babelone::Result<&'a str> {
+    match (format, is_stdio(path)) {
+        (Some(format), _) => Ok(format),
+        (None, true) => Err(babelone::Error::InvalidArgument(format!(
+            "Failed to perform operation. {} must be given when {} is -.",
+            flag, argument
+        ))),
+        (None, false) => Ok(path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default()),
+    }
+}
+
+fn warn(warnings: &[String]) {
+    for warning in warnings {
+        eprintln!("warning: {}", warning);
+    }
+}
+
+/// Writes `contents` to `output`, or shows how they differ from it with
+/// `--diff`. Returns whether `output` is unchanged.
+fn write_output(output: &Path, contents: &str, write: &WriteArgs) -> babelone::Result<bool> {
+    if write.diff {
+        if is_stdio(output) {
+            return Err(babelone::Error::InvalidArgument(
+                "Failed to perform operation. --diff cannot be given when OUTPUT is -.".to_string(),
+            ));
+        }
+        let (original, original_name) = match output.exists() {
+            true => (
+                fs::read_to_string(output)?,
+                format!("a/{}", output.display()),
+            ),
+            false => (String::new(), "/dev/null".to_string()),
+        };
+        let modified_name = format!("b/{}", output.display());
+        let diff =
+            babelone::diff::unified_diff(&original, contents, &original_name, &modified_name);
+        io::stdout().write_all(diff.as_bytes())?;
+        return Ok(diff.is_empty());
+    }
+    match is_stdio(output) || write.dry_run {
+        true => io::stdout().write_all(contents.as_bytes())?,
+        false => generators::write_file(output, contents, &write.options())?,
+    }
+    Ok(true)
+}
+
+fn translate(
+    input: &Path,
+    output: &Path,
+    from: Option<&str>,
+    to: Option<&str>,
+    env: Option<&Path>,
+    import_depth: usize,
+    write: &WriteArgs,
+) -> babelone::Result<bool> {
+    let target_format = format_of("OUTPUT", output, "--to", to)?;
+    let translation = match is_stdio(input) {
+        true => {
+            if env.is_some() {
+                return Err(babelone::Error::InvalidArgument(
+                    "Failed to perform operation. --env cannot be given when INPUT is -."
+                        .to_string(),
+                ));
+            }
+            let source_format = format_of("INPUT", input, "--from", from)?;
+            let mut source_text = String::new();
+            io::stdin().read_to_string(&mut source_text)?;
+            babelone::translate_str(&source_text, source_format, target_format)?
+        }
+        false => babelone::translate_file(input, from, target_format, env, import_depth)?,
+    };
+    warn(&translation.warnings);
+    write_output(output, &translation.contents, write)
+}
+
+/// The directory whose packages a scaffold at `output` declares, which is the
+/// current directory for stdout.
+fn scaffold_root(output: &Path) -> &Path {
+    match output.parent() {
+        Some(parent) if parent != Path::new("") => parent,
+        _ => Path::new("."),
+    }
+}
+
+fn create(output: &Path, to: Option<&str>, write: &WriteArgs) -> babelone::Result<bool> {
+    let root = scaffold_root(output);
+    let contents = babelone::create_str(format_of("OUTPUT", output, "--to", to)?, root)?;
+    write_output(output, &contents, write)
+}
+
+fn run(command: Command) -> babelone::Result<bool> {
+    match command {
+        Command::Create { output, to, write } => create(&output, to.as_deref(), &write),
+        Command::Translate {
+            input,
+            output,
+            from,
+            to,
+            env,
+            import_depth,
+            write,
+        } => translate(
+            &input,
+            &output,
+            from.as_deref(),
+            to.as_deref(),
+            env.as_deref(),
+            import_depth,
+            &write,
+        ),
+        Command::Check {
+            source,
+            dest,
+            env,
+            import_depth,
+        } => {
+            let report = babelone::check(&source, &dest, env.as_deref(), import_depth)?;
+            if !report.is_empty() {
+                println!(
+                    "{} is out of sync with {} (+ missing, - unexpected, ~ different):",
+                    dest.display(),
+                    source.display()
+                );
+                print!("{}", report);
+            }
+            Ok(report.is_empty())
+        }
+        Command::Sync {
+            source,
+            dest,
+            env,
+            import_depth,
+            write,
+        } => {
+            let translation = babelone::sync_file(&source, &dest, env.as_deref(), import_depth)?;
+            warn(&translation.warnings);
+            // DEST is updated in place, so it is always overwritten.
+            let write = WriteArgs {
+                force: true,
+                ..write
+            };
+            write_output(&dest, &translation.contents, &write)
+        }
+        Command::Diff {
+            old,
+            new,
+            json,
+            import_depth,
+        } => {
+            let report = babelone::diff_specs(&old, &new, import_depth)?;
+            match json {
+                true => println!("{:#}", report.to_json()),
+                false => print!("{}", report),
+            }
+            Ok(report.is_empty())
+        }
+        Command::Tree { environment } => {
+            let tree = babelone::tree(&environment)?;
+            print!("{}", tree);
+            warn(
+                &tree
+                    .problems()
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>(),
+            );
+            Ok(true)
+        }
+        Command::Imports {
+            input,
+            package,
+            mappings,
+        } => {
+            let report = babelone::check_imports(&input, package.as_deref(), &mappings)?;
+            for path in &report.unparsable {
+                eprintln!("warning: skipping unparsable file {}", path.display());
+            }
+            print!("{}", report);
+            Ok(report.is_empty())
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(cli.command) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        // 1 is left to mean that files differ, as it does for diff(1).
+        Err(error) => {
+            eprintln!("error: {}", error);
+            ExitCode::from(2)
+        }
+    }
+}
@@ -1,325 +1,2585 @@
 //! Defines parsers used to exract Python package build specifications
-//! from applicable file types, e.g., requirements.txt, setup.py, and
-//! pyproject.toml
-use pyo3::exceptions::{PyTypeError, PyValueError};
-use pyo3::PyResult;
-use rustpython_parser::{ast, Parse};
-use std::collections::BTreeMap;
+//! from applicable file types, e.g., requirements.txt, setup.py,
+//! pyproject.toml, environment.yml, PKG-INFO/METADATA, and built
+//! distributions (wheels and sdists)
+use rustpython_parser::ast::{self, Ranged};
+use rustpython_parser::Parse;
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io::Read;
+use std::ops::Range;
 use std::path::Path;
 
+use crate::dynamic::DynamicVersion;
+use crate::error::{Error, Result};
+use crate::evaluate::{Evaluator, PyValue};
 use crate::specs::*;
 use crate::utils;
 
 pub struct RequirementsParser;
 pub struct SetupParser;
 pub struct PyProjectParser;
+pub struct CondaEnvironmentParser;
+pub struct UvLockParser;
+pub struct PyLockParser;
+pub struct MetadataParser;
+pub struct DistributionParser;
+pub struct SitePackagesParser;
+
+/// Byte ranges of the `setup()` call of a setup.py, with which its keyword
+/// arguments are edited in place.
+pub struct SetupCallRanges {
+    /// From the opening parenthesis to the end of the last argument, where
+    /// further keyword arguments are inserted.
+    pub arguments: Range<usize>,
+    /// Values of the keyword arguments given directly, and whether each is a
+    /// literal, e.g., a list rather than a file read when built.
+    pub keywords: BTreeMap<String, (Range<usize>, bool)>,
+    /// Whether keyword arguments are unpacked into the call, e.g., `**config`.
+    pub unpacks: bool,
+}
+
+/// Local modules which `from x import y` statements of a setup.py are followed
+/// into, and how much deeper imports are still followed.
+struct LocalImports<'a> {
+    /// Directory of setup.py, where local modules are looked for.
+    root: &'a Path,
+    /// Package of the module being walked, against which relative imports are
+    /// resolved. Empty for setup.py.
+    package: Vec<String>,
+    depth: usize,
+}
 
 enum PyAssignment<'a> {
     Annotated(&'a ast::StmtAnnAssign),
     Unannotated(&'a ast::StmtAssign),
 }
 
+/// The subset of a uv.lock file needed to pin its packages.
+#[derive(Deserialize)]
+struct UvLockFile {
+    #[serde(default)]
+    package: Vec<UvPackage>,
+}
+
+#[derive(Deserialize)]
+struct UvPackage {
+    name: String,
+    version: Option<String>,
+    source: Option<UvSource>,
+    #[serde(rename = "resolution-markers", default)]
+    resolution_markers: Vec<String>,
+    #[serde(default)]
+    dependencies: Vec<UvDependency>,
+    #[serde(rename = "optional-dependencies", default)]
+    optional_dependencies: BTreeMap<String, Vec<UvDependency>>,
+    #[serde(rename = "dev-dependencies", default)]
+    dev_dependencies: BTreeMap<String, Vec<UvDependency>>,
+    sdist: Option<UvArtifact>,
+    #[serde(default)]
+    wheels: Vec<UvArtifact>,
+}
+
+#[derive(Deserialize)]
+struct UvSource {
+    registry: Option<String>,
+    git: Option<String>,
+    url: Option<String>,
+    path: Option<String>,
+    directory: Option<String>,
+    editable: Option<String>,
+    #[serde(rename = "virtual")]
+    virtual_: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct UvDependency {
+    name: String,
+    marker: Option<String>,
+    #[serde(default)]
+    extra: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct UvArtifact {
+    hash: Option<String>,
+}
+
+/// The subset of a pylock.toml file (PEP 751) needed to pin its packages.
+#[derive(Deserialize)]
+struct PyLockFile {
+    #[serde(default)]
+    packages: Vec<PyLockPackage>,
+}
+
+#[derive(Deserialize)]
+struct PyLockPackage {
+    name: String,
+    version: Option<String>,
+    marker: Option<String>,
+    index: Option<String>,
+    vcs: Option<PyLockVcs>,
+    directory: Option<PyLockDirectory>,
+    archive: Option<PyLockArchive>,
+    sdist: Option<PyLockArchive>,
+    #[serde(default)]
+    wheels: Vec<PyLockArchive>,
+}
+
+#[derive(Deserialize)]
+struct PyLockVcs {
+    #[serde(rename = "type")]
+    vcs_type: String,
+    url: Option<String>,
+    path: Option<String>,
+    #[serde(rename = "commit-id")]
+    commit_id: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct PyLockDirectory {
+    path: String,
+    #[serde(default)]
+    editable: bool,
+}
+
+#[derive(Deserialize)]
+struct PyLockArchive {
+    url: Option<String>,
+    path: Option<String>,
+    #[serde(default)]
+    hashes: BTreeMap<String, String>,
+}
+
+/// The direct_url.json recorded for a distribution not installed from an index,
+/// as specified by PEP 610.
+#[derive(Deserialize)]
+struct DirectUrl {
+    url: String,
+    vcs_info: Option<DirectUrlVcsInfo>,
+    dir_info: Option<DirectUrlDirInfo>,
+}
+
+#[derive(Deserialize)]
+struct DirectUrlVcsInfo {
+    vcs: String,
+    commit_id: String,
+}
+
+#[derive(Deserialize)]
+struct DirectUrlDirInfo {
+    #[serde(default)]
+    editable: bool,
+}
+
 /// A build specification for a Python package, e.g., setup.py.
 pub trait SpecParser<T> {
-    fn from_file(path: &Path) -> PyResult<T>
+    fn from_file(path: &Path) -> Result<T>
+    where
+        Self: Sized;
+}
+
+/// A build specification held in a single text file, which can also be parsed
+/// from its contents, e.g., as read from stdin. `from_str` touches no files:
+/// files the contents refer to, e.g., a requirements file or a version file,
+/// and local modules they import from are left unresolved.
+pub trait SpecStrParser<T>: SpecParser<T> {
+    fn from_str(contents: &str) -> Result<T>
     where
         Self: Sized;
 }
 
+/// A symbol table of the names bound so far as a setup.py is walked in order.
+/// Values are folded where they are bound, so that a name holds the value
+/// Python would see at that point, e.g., after `REQUIRES += ["click"]`.
 trait IdentValueMap {
-    fn insert_assignments(&mut self, assignment: PyAssignment) -> PyResult<&mut Self>;
+    fn insert_assignments(&mut self, assignment: PyAssignment) -> &mut Self;
 
-    fn parse_ident(expr: &ast::Expr) -> PyResult<String> {
-        match expr {
-            ast::Expr::Name(e) => Ok(e.id.to_string()),
-            _ => Err(PyTypeError::new_err(format!(
-                "Expected Expr::Name in assignment parsing. Found:\n{expr:#?}"
-            ))),
-        }
-    }
+    fn insert_aug_assignment(&mut self, assignment: &ast::StmtAugAssign) -> &mut Self;
+
+    /// Applies calls which mutate a bound list or dict in place, e.g.,
+    /// `REQUIRES.append("click")` or `EXTRAS.update(test=["pytest"])`.
+    fn insert_mutation(&mut self, expr: &ast::Expr) -> &mut Self;
+
+    fn remove_targets(&mut self, target: &ast::Expr) -> &mut Self;
+
+    fn bind(&mut self, target: &ast::Expr, value: &ast::Expr) -> &mut Self;
 }
 
 impl SpecParser<Requirements> for RequirementsParser {
-    fn from_file(path: &Path) -> PyResult<Requirements> {
+    fn from_file(path: &Path) -> Result<Requirements> {
+        let contents = utils::read_file(&path)?;
+        Self::from_str(&contents)
+    }
+}
+
+impl SpecStrParser<Requirements> for RequirementsParser {
+    fn from_str(contents: &str) -> Result<Requirements> {
         let mut requires = Vec::<Requirement>::new();
-        let lines = utils::read_file(&path)?;
-        let lines = lines.split("\n").map(|s| s.to_string());
-        for line in lines {
+        let mut hashes = BTreeMap::<String, Vec<String>>::new();
+        let mut via = BTreeMap::<Requirement, Vec<String>>::new();
+        // Hashes are typically listed on continuation lines, e.g., `flask==3.0.0 \`.
+        let contents = contents.replace("\\\r\n", " ").replace("\\\n", " ");
+        let mut in_via_block = false;
+        for line in contents.split("\n") {
+            let line = line.trim();
             if line.is_empty() {
                 continue;
             }
-            requires.push(line.trim().replace(" ", "").to_string());
+            if line.starts_with('#') {
+                // pip-compile annotates each pin with the requirements that pulled it in,
+                // either as `# via flask` or as a `# via` block of `#   flask` lines.
+                let comment = line.trim_start_matches('#').trim();
+                if let Some(requirement) = requires.last() {
+                    if comment == "via" {
+                        in_via_block = true;
+                        continue;
+                    }
+                    if let Some(source) = comment.strip_prefix("via ") {
+                        via.entry(requirement.clone())
+                            .or_default()
+                            .push(source.trim().to_string());
+                        in_via_block = false;
+                        continue;
+                    }
+                    if in_via_block && line.starts_with("#   ") {
+                        via.entry(requirement.clone())
+                            .or_default()
+                            .push(comment.to_string());
+                        continue;
+                    }
+                }
+                in_via_block = false;
+                continue;
+            }
+            in_via_block = false;
+            let line = line.split(" #").next().unwrap_or(line);
+            let mut tokens = Vec::<&str>::new();
+            let mut requirement_hashes = Vec::<String>::new();
+            for token in line.split_whitespace() {
+                match token.strip_prefix("--hash=") {
+                    Some(hash) => requirement_hashes.push(hash.to_string()),
+                    None => tokens.push(token),
+                }
+            }
+            // Tokens stay apart, e.g., in `-e .` or `spam @ https://...`.
+            let requirement = tokens.join(" ");
+            if let Some(key) = Requirements::hash_key(&requirement) {
+                if !requirement_hashes.is_empty() {
+                    hashes.insert(key, requirement_hashes);
+                }
+            }
+            requires.push(requirement);
         }
-        Ok(Requirements { requires })
+        Ok(Requirements {
+            requires,
+            hashes,
+            via,
+        })
     }
 }
 
 impl SpecParser<Setup> for SetupParser {
-    fn from_file(path: &Path) -> PyResult<Setup>
+    fn from_file(path: &Path) -> Result<Setup>
     where
         Self: Sized,
     {
+        Self::from_file_with_import_depth(path, Self::DEFAULT_IMPORT_DEPTH)
+    }
+}
+
+impl SpecStrParser<Setup> for SetupParser {
+    fn from_str(contents: &str) -> Result<Setup>
+    where
+        Self: Sized,
+    {
+        // Contents which are not of a file refer to no files or local modules.
+        Self::parse(contents, None, 0)
+    }
+}
+
+impl SetupParser {
+    /// How many modules deep `from x import y` statements are followed by default.
+    pub const DEFAULT_IMPORT_DEPTH: usize = 3;
+
+    /// Parses a setup.py, following imports of local modules next to it (e.g.,
+    /// `from spam.meta import VERSION`) up to `import_depth` modules deep.
+    pub fn from_file_with_import_depth(path: &Path, import_depth: usize) -> Result<Setup> {
         let contents = utils::read_file(&path)?;
-        match ast::Suite::parse(&contents, &path.to_str().unwrap()) {
-            Ok(statements) => Ok(Self::parse_ast(statements)?),
-            Err(_) => Err(PyValueError::new_err(format!(
-                "Failed to parse AST of {:#?}",
-                path.to_str()
-            ))),
+        Self::parse(&contents, Some(path), import_depth)
+    }
+
+    /// Parses the contents of a setup.py as if they were of the file at `path`,
+    /// reading the files they refer to relative to it, without following imports.
+    pub fn from_str_at(contents: &str, path: &Path) -> Result<Setup> {
+        Self::parse(contents, Some(path), 0)
+    }
+
+    /// Locates the `setup()` call of the contents of a setup.py, wherever it
+    /// is called, without following any imports.
+    pub fn setup_call_ranges(contents: &str) -> Result<SetupCallRanges> {
+        let statements = ast::Suite::parse(contents, "setup.py")
+            .map_err(|_| Error::Parse("Failed to parse AST of \"setup.py\"".to_string()))?;
+        let imports = LocalImports {
+            root: Path::new("."),
+            package: Vec::new(),
+            depth: 0,
+        };
+        let Some((setup, _)) = Self::get_setup_call(&statements, &imports)? else {
+            return Err(Error::Parse(
+                "Failed to perform operation. No setup() call was found in setup.py.".to_string(),
+            ));
+        };
+        let func_end = usize::from(setup.func.range().end());
+        let arguments_start = func_end
+            + contents[func_end..]
+                .find('(')
+                .map_or(0, |position| position + 1);
+        let mut arguments_end = arguments_start;
+        let mut keywords = BTreeMap::new();
+        let mut unpacks = false;
+        for arg in setup.args.iter() {
+            arguments_end = arguments_end.max(usize::from(arg.range().end()));
+        }
+        for keyword in setup.keywords.iter() {
+            let range = keyword.value.range();
+            let range = usize::from(range.start())..usize::from(range.end());
+            arguments_end = arguments_end.max(range.end);
+            match &keyword.arg {
+                Some(arg) => {
+                    let literal = matches!(
+                        keyword.value,
+                        ast::Expr::List(_) | ast::Expr::Tuple(_) | ast::Expr::Dict(_)
+                    );
+                    keywords.insert(arg.as_str().to_string(), (range, literal));
+                }
+                None => unpacks = true,
+            }
+        }
+        Ok(SetupCallRanges {
+            arguments: arguments_start..arguments_end,
+            keywords,
+            unpacks,
+        })
+    }
+
+    fn parse(contents: &str, path: Option<&Path>, import_depth: usize) -> Result<Setup> {
+        let name = path.map_or(Some("setup.py"), |path| path.to_str());
+        match ast::Suite::parse(contents, name.unwrap_or_default()) {
+            // Files read by setup.py are relative to its directory.
+            Ok(statements) => Ok(Self::parse_ast(
+                statements,
+                path.map(utils::directory_of),
+                import_depth,
+            )?),
+            Err(_) => Err(Error::Parse(format!("Failed to parse AST of {:#?}", name))),
         }
     }
 }
 
 impl SpecParser<PyProject> for PyProjectParser {
-    fn from_file(path: &Path) -> PyResult<PyProject>
+    fn from_file(path: &Path) -> Result<PyProject>
     where
         Self: Sized,
     {
         let contents = utils::read_file(&path)?;
-        let pyproject = toml::from_str::<PyProject>(&contents);
-        if pyproject.is_ok() {
-            return Ok(pyproject.unwrap());
+        Self::parse(&contents, Some(path))
+    }
+}
+
+impl SpecStrParser<PyProject> for PyProjectParser {
+    fn from_str(contents: &str) -> Result<PyProject>
+    where
+        Self: Sized,
+    {
+        Self::parse(contents, None)
+    }
+}
+
+impl PyProjectParser {
+    /// Parses the contents of a pyproject.toml as if they were of the file at
+    /// `path`, reading a dynamic version relative to it.
+    pub fn from_str_at(contents: &str, path: &Path) -> Result<PyProject> {
+        Self::parse(contents, Some(path))
+    }
+
+    fn parse(contents: &str, path: Option<&Path>) -> Result<PyProject> {
+        let pyproject = toml::from_str::<PyProject>(contents);
+        if let Ok(mut pyproject) = pyproject {
+            if let Some(path) = path {
+                Self::resolve_version(&mut pyproject, utils::directory_of(path));
+            }
+            return Ok(pyproject);
         }
-        Err(PyValueError::new_err(format!(
+        Err(Error::Parse(format!(
             "Failed to parse toml file {:#?}",
-            path.to_str()
+            path.map_or(Some("pyproject.toml"), |path| path.to_str())
         )))
     }
+
+    /// Replaces a dynamic version with the one its source holds, when it can be
+    /// read statically relative to the pyproject.toml's directory, `root`.
+    fn resolve_version(pyproject: &mut PyProject, root: &Path) {
+        let version = match pyproject.dynamic_version() {
+            Some(source) => source.resolve(root),
+            None => return,
+        };
+        if let (Some(version), Some(project)) = (version, pyproject.project.as_mut()) {
+            project.version = Some(version);
+            if let Some(dynamic) = project.dynamic.as_mut() {
+                dynamic.retain(|field| field != "version");
+                if dynamic.is_empty() {
+                    project.dynamic = None;
+                }
+            }
+        }
+    }
 }
 
-impl SetupParser {
-    fn parse_ast(statements: Vec<ast::Stmt>) -> PyResult<Setup> {
-        let mut assignments = BTreeMap::<String, ast::Expr>::new();
+impl SpecParser<CondaEnvironment> for CondaEnvironmentParser {
+    fn from_file(path: &Path) -> Result<CondaEnvironment>
+    where
+        Self: Sized,
+    {
+        let contents = utils::read_file(&path)?;
+        Self::parse(&contents, path)
+    }
+}
 
-        let mut package_name: Option<String> = None;
-        let mut version: Option<String> = None;
-        let mut install_requires: Option<Vec<Requirement>> = None;
-        let mut setup_requires: Option<Vec<Requirement>> = None;
-        let mut extra_requires: Option<BTreeMap<String, Vec<Requirement>>> = None;
-        let mut entry_points: Option<Entrypoints> = None;
+impl SpecStrParser<CondaEnvironment> for CondaEnvironmentParser {
+    fn from_str(contents: &str) -> Result<CondaEnvironment>
+    where
+        Self: Sized,
+    {
+        Self::parse(contents, Path::new("environment.yml"))
+    }
+}
 
-        if let Some((setup, assignments)) =
-            Self::get_setup_call(&statements, &mut 0, &mut assignments)?
-        {
-            for keyword in &setup.keywords {
-                let ident = keyword.arg.clone().unwrap();
-                match ident.as_str() {
-                    "name" => {
-                        package_name = Some(Self::parse_string(&keyword.value, &assignments)?)
-                    }
-                    "version" => version = Some(Self::parse_string(&keyword.value, &assignments)?),
-                    "install_requires" => {
-                        install_requires =
-                            Some(Self::parse_string_vec(&keyword.value, &assignments)?);
-                    }
-                    "setup_requires" => {
-                        setup_requires =
-                            Some(Self::parse_string_vec(&keyword.value, &assignments)?);
-                    }
-                    "extra_requires" => {
-                        extra_requires =
-                            Some(Self::parse_requires_map(&keyword.value, &assignments)?);
-                    }
-                    "entry_points" => {
-                        entry_points = Some(Self::parse_entrypoints(&keyword.value, &assignments)?);
-                    }
-                    _ => continue,
-                }
-            }
+impl CondaEnvironmentParser {
+    fn parse(contents: &str, path: &Path) -> Result<CondaEnvironment> {
+        match serde_yaml::from_str::<CondaEnvironment>(contents) {
+            Ok(environment) => Ok(environment),
+            Err(e) => Err(Error::Parse(format!(
+                "Failed to parse yaml file {:#?}: {}",
+                path.to_str(),
+                e
+            ))),
         }
-        Ok(Setup {
-            package_name,
-            version,
-            install_requires,
-            extra_requires,
-            setup_requires,
-            entry_points,
-        })
     }
+}
 
-    fn parse_string(
-        expr: &ast::Expr,
-        assignments: &BTreeMap<String, ast::Expr>,
-    ) -> PyResult<String> {
-        match expr {
-            ast::Expr::Constant(c) => {
-                if let ast::Constant::Str(s) = &c.value {
-                    return Ok(s.clone());
-                }
+impl SpecParser<Lockfile> for UvLockParser {
+    fn from_file(path: &Path) -> Result<Lockfile>
+    where
+        Self: Sized,
+    {
+        let contents = utils::read_file(&path)?;
+        Self::parse(&contents, path)
+    }
+}
+
+impl SpecStrParser<Lockfile> for UvLockParser {
+    fn from_str(contents: &str) -> Result<Lockfile>
+    where
+        Self: Sized,
+    {
+        Self::parse(contents, Path::new("uv.lock"))
+    }
+}
+
+impl UvLockParser {
+    fn parse(contents: &str, path: &Path) -> Result<Lockfile> {
+        let lock = match toml::from_str::<UvLockFile>(contents) {
+            Ok(lock) => lock,
+            Err(e) => {
+                return Err(Error::Parse(format!(
+                    "Failed to parse toml file {:#?}: {}",
+                    path.to_str(),
+                    e
+                )))
             }
-            ast::Expr::Name(name) => {
-                if let Some(v) = assignments.get(&name.id.to_string()) {
-                    return Ok(Self::parse_string(v, assignments)?);
-                }
+        };
+
+        // uv records markers on the edges between packages rather than on the
+        // packages, which are installed where any path to them is taken. As with
+        // `uv export`, extras are only followed where a dependency asks for them,
+        // and dev dependencies are left out, so the extras and dev dependencies
+        // of the project itself are not installed.
+        let mut parents = BTreeMap::<String, Vec<(String, Option<String>)>>::new();
+        let mut reachable = BTreeSet::<(String, Option<String>)>::new();
+        let mut queue = Self::roots(&lock.package)
+            .into_iter()
+            .map(|name| (name, None))
+            .collect::<Vec<(String, Option<String>)>>();
+        while let Some((name, extra)) = queue.pop() {
+            if !reachable.insert((name.clone(), extra.clone())) {
+                continue;
             }
-            ast::Expr::JoinedStr(joined) => {
-                let mut res = String::new();
-                for value in joined.values.iter() {
-                    let mut target = value;
-                    if let ast::Expr::FormattedValue(formatted) = value {
-                        target = formatted.value.as_ref();
+            for package in lock.package.iter().filter(|package| package.name == name) {
+                let edges = match &extra {
+                    Some(extra) => package.optional_dependencies.get(extra),
+                    None => Some(&package.dependencies),
+                };
+                for edge in edges.into_iter().flatten() {
+                    parents
+                        .entry(edge.name.clone())
+                        .or_default()
+                        .push((package.name.clone(), edge.marker.clone()));
+                    queue.push((edge.name.clone(), None));
+                    for extra in edge.extra.iter() {
+                        queue.push((edge.name.clone(), Some(extra.clone())));
                     }
-                    res.push_str(&Self::parse_string(target, assignments)?);
                 }
-                return Ok(res);
             }
-            _ => (),
         }
-        return Err(PyValueError::new_err(format!(
-            "Failed to parse String from Expr:\n{expr:#?}",
-        )));
-    }
 
-    fn parse_string_vec(
-        expr: &ast::Expr,
-        assignments: &BTreeMap<String, ast::Expr>,
-    ) -> PyResult<Vec<String>> {
-        match expr {
-            ast::Expr::List(list) => {
-                let mut result = Vec::<String>::new();
-                for element in &list.elts {
-                    result.push(Self::parse_string(element, assignments)?);
-                }
-                return Ok(result);
+        let mut markers = BTreeMap::<String, Option<String>>::new();
+        let mut packages = Vec::<LockedPackage>::new();
+        for package in lock.package {
+            if !reachable.contains(&(package.name.clone(), None)) {
+                continue;
             }
-            ast::Expr::Name(name) => {
-                if let Some(v) = assignments.get(&name.id.to_string()) {
-                    return Self::parse_string_vec(v, assignments);
+            let (marker, _) =
+                Self::transitive_marker(&package.name, &parents, &mut markers, &mut Vec::new());
+            let marker = marker.flatten();
+            let marker = match (package.resolution_markers.is_empty(), marker) {
+                (true, marker) => marker,
+                (false, None) => Some(Self::join_markers(&package.resolution_markers)),
+                (false, Some(marker)) => Some(format!(
+                    "{} and {}",
+                    Self::group_marker(&Self::join_markers(&package.resolution_markers)),
+                    Self::group_marker(&marker)
+                )),
+            };
+            let source = package.source.map(|source| {
+                if let Some(git) = source.git {
+                    // e.g., "https://github.com/me/spam?rev=v1.0#<commit>"
+                    let (url, commit) = match git.split_once('#') {
+                        Some((url, commit)) => (url, Some(commit.to_string())),
+                        None => (git.as_str(), None),
+                    };
+                    let url = url.split('?').next().unwrap_or(url);
+                    PackageSource::Vcs {
+                        url: format!("git+{}", url),
+                        commit,
+                    }
+                } else if let Some(url) = source.url {
+                    PackageSource::Url(url)
+                } else if let Some(path) = source.editable {
+                    PackageSource::Editable(path)
+                } else if let Some(path) = source.virtual_ {
+                    PackageSource::Virtual(path)
+                } else if let Some(path) = source.path.or(source.directory) {
+                    PackageSource::Path(path)
+                } else {
+                    PackageSource::Registry(source.registry.unwrap_or_default())
                 }
+            });
+            let hashes = package
+                .sdist
+                .iter()
+                .chain(package.wheels.iter())
+                .filter_map(|artifact| artifact.hash.clone())
+                .collect();
+            packages.push(LockedPackage {
+                name: package.name,
+                version: package.version,
+                source,
+                marker,
+                hashes,
+            });
+        }
+        Ok(Lockfile { packages })
+    }
+
+    /// The packages nothing depends on, e.g., the project itself, through its
+    /// dependencies, extras or dev dependencies.
+    fn roots(packages: &[UvPackage]) -> Vec<String> {
+        let dependencies = packages
+            .iter()
+            .flat_map(|package| {
+                package
+                    .dependencies
+                    .iter()
+                    .chain(package.optional_dependencies.values().flatten())
+                    .chain(package.dev_dependencies.values().flatten())
+            })
+            .map(|edge| edge.name.as_str())
+            .collect::<BTreeSet<&str>>();
+        packages
+            .iter()
+            .filter(|package| !dependencies.contains(package.name.as_str()))
+            .map(|package| package.name.clone())
+            .collect()
+    }
+
+    /// The marker under which the package `name` is installed, being any of
+    /// those of the edges to it, each joined with the marker of the package it
+    /// comes from, or `Some(None)` if it is always installed.
+    ///
+    /// Edges from a package still being `visiting` close a cycle and are left
+    /// out, returning `None` and the index of that package, as are markers which
+    /// are only partially known until the cycle has been walked.
+    fn transitive_marker(
+        name: &str,
+        parents: &BTreeMap<String, Vec<(String, Option<String>)>>,
+        markers: &mut BTreeMap<String, Option<String>>,
+        visiting: &mut Vec<String>,
+    ) -> (Option<Option<String>>, usize) {
+        if let Some(marker) = markers.get(name) {
+            return (Some(marker.clone()), usize::MAX);
+        }
+        if let Some(index) = visiting.iter().position(|package| package == name) {
+            return (None, index);
+        }
+        // A package nothing depends on is a root, e.g., the project itself.
+        let Some(edges) = parents.get(name) else {
+            return (Some(None), usize::MAX);
+        };
+        let index = visiting.len();
+        visiting.push(name.to_string());
+        let mut cycle = usize::MAX;
+        let mut always = false;
+        let mut alternatives = Vec::<String>::new();
+        for (parent, edge_marker) in edges {
+            let (parent_marker, parent_cycle) =
+                Self::transitive_marker(parent, parents, markers, visiting);
+            cycle = cycle.min(parent_cycle);
+            let Some(parent_marker) = parent_marker else {
+                continue;
+            };
+            match (parent_marker.as_ref(), edge_marker.as_ref()) {
+                (None, None) => always = true,
+                (Some(marker), None) | (None, Some(marker)) => alternatives.push(marker.clone()),
+                (Some(parent_marker), Some(edge_marker)) => alternatives.push(format!(
+                    "{} and {}",
+                    Self::group_marker(parent_marker),
+                    Self::group_marker(edge_marker)
+                )),
             }
-            _ => (),
         }
-        return Err(PyValueError::new_err(format!(
-            "Failed to parse Vec<String> from Expr:\n{expr:#?}"
-        )));
+        visiting.pop();
+        alternatives.sort();
+        alternatives.dedup();
+        let marker = match always || alternatives.is_empty() {
+            true => None,
+            false => Some(Self::join_markers(&alternatives)),
+        };
+        if cycle >= index {
+            markers.insert(name.to_string(), marker.clone());
+            return (Some(marker), usize::MAX);
+        }
+        match always || !alternatives.is_empty() {
+            true => (Some(marker), cycle),
+            false => (None, cycle),
+        }
     }
 
-    fn parse_requires_map(
-        expr: &ast::Expr,
-        assignments: &BTreeMap<String, ast::Expr>,
-    ) -> PyResult<BTreeMap<String, Vec<Requirement>>> {
-        let mut mapped = BTreeMap::<String, Vec<Requirement>>::new();
-        match expr {
-            ast::Expr::Dict(dict) => {
-                for (i, key) in dict.keys.iter().enumerate() {
-                    if let Some(key) = key {
-                        let value = &dict.values[i];
-                        mapped.insert(
-                            Self::parse_string(key, assignments)?,
-                            Self::parse_string_vec(value, assignments)?,
-                        );
-                    }
-                }
-                return Ok(mapped);
+    /// Parenthesizes a marker of alternatives, to be joined with another by `and`.
+    fn group_marker(marker: &str) -> String {
+        match marker.contains(" or ") {
+            true => format!("({})", marker),
+            false => marker.to_string(),
+        }
+    }
+
+    fn join_markers(markers: &[String]) -> String {
+        if markers.len() == 1 {
+            return markers[0].clone();
+        }
+        markers
+            .iter()
+            .map(|m| format!("({})", m))
+            .collect::<Vec<String>>()
+            .join(" or ")
+    }
+}
+
+impl SpecParser<Lockfile> for PyLockParser {
+    fn from_file(path: &Path) -> Result<Lockfile>
+    where
+        Self: Sized,
+    {
+        let contents = utils::read_file(&path)?;
+        Self::parse(&contents, path)
+    }
+}
+
+impl SpecStrParser<Lockfile> for PyLockParser {
+    fn from_str(contents: &str) -> Result<Lockfile>
+    where
+        Self: Sized,
+    {
+        Self::parse(contents, Path::new("pylock.toml"))
+    }
+}
+
+impl PyLockParser {
+    fn parse(contents: &str, path: &Path) -> Result<Lockfile> {
+        let lock = match toml::from_str::<PyLockFile>(contents) {
+            Ok(lock) => lock,
+            Err(e) => {
+                return Err(Error::Parse(format!(
+                    "Failed to parse toml file {:#?}: {}",
+                    path.to_str(),
+                    e
+                )))
             }
-            ast::Expr::Name(name) => {
-                if let Some(v) = assignments.get(&name.id.to_string()) {
-                    return Ok(Self::parse_requires_map(v, assignments)?);
+        };
+        let mut packages = Vec::<LockedPackage>::new();
+        for package in lock.packages {
+            let mut hashes = Vec::<String>::new();
+            let artifacts = package
+                .archive
+                .iter()
+                .chain(package.sdist.iter())
+                .chain(package.wheels.iter());
+            for artifact in artifacts {
+                for (algorithm, digest) in artifact.hashes.iter() {
+                    hashes.push(format!("{}:{}", algorithm, digest));
                 }
             }
-            _ => (),
+            let source = if let Some(vcs) = package.vcs {
+                let url = vcs.url.or(vcs.path).unwrap_or_default();
+                Some(PackageSource::Vcs {
+                    url: format!("{}+{}", vcs.vcs_type, url),
+                    commit: vcs.commit_id,
+                })
+            } else if let Some(directory) = package.directory {
+                if directory.editable {
+                    Some(PackageSource::Editable(directory.path))
+                } else {
+                    Some(PackageSource::Path(directory.path))
+                }
+            } else if let Some(archive) = package.archive {
+                match (archive.url, archive.path) {
+                    (Some(url), _) => Some(PackageSource::Url(url)),
+                    (None, Some(path)) => Some(PackageSource::Path(path)),
+                    (None, None) => None,
+                }
+            } else {
+                package.index.map(PackageSource::Registry)
+            };
+            packages.push(LockedPackage {
+                name: package.name,
+                version: package.version,
+                source,
+                marker: package.marker,
+                hashes,
+            });
         }
-        return Err(PyValueError::new_err(format!(
-            "Failed to parse BTreeMap<String, Vec<String>> from Expr:\n{expr:#?}"
-        )));
+        Ok(Lockfile { packages })
     }
+}
 
-    fn parse_entrypoints(
-        expr: &ast::Expr,
-        assignments: &BTreeMap<String, ast::Expr>,
-    ) -> PyResult<Entrypoints> {
-        match expr {
-            ast::Expr::Dict(dict) => {
-                let mut entry_points = Entrypoints {
-                    console_scripts: None,
-                    gui_scripts: None,
-                };
-                for (i, key) in dict.keys.iter().enumerate() {
-                    if let Some(key) = key {
-                        let key = Self::parse_string(key, assignments)?;
-                        if key == "console_scripts".to_string() {
-                            entry_points.console_scripts =
-                                Some(Self::parse_string_vec(&dict.values[i], assignments)?);
-                        } else if key == "gui_scripts".to_string() {
-                            entry_points.gui_scripts =
-                                Some(Self::parse_string_vec(&dict.values[i], assignments)?);
-                        }
-                    }
-                }
-                if entry_points.console_scripts.is_some() || entry_points.gui_scripts.is_some() {
-                    return Ok(entry_points);
+impl SpecParser<Metadata> for MetadataParser {
+    fn from_file(path: &Path) -> Result<Metadata>
+    where
+        Self: Sized,
+    {
+        let contents = utils::read_file(&path)?;
+        Self::from_str(&contents)
+    }
+}
+
+impl SpecStrParser<Metadata> for MetadataParser {
+    fn from_str(contents: &str) -> Result<Metadata>
+    where
+        Self: Sized,
+    {
+        Ok(Self::parse_headers(contents))
+    }
+}
+
+impl MetadataParser {
+    /// Parses core metadata, which is formatted as email headers followed by
+    /// an optional message body holding the package description.
+    pub(crate) fn parse_headers(contents: &str) -> Metadata {
+        let mut metadata = Metadata {
+            metadata_version: None,
+            name: None,
+            version: None,
+            summary: None,
+            description: None,
+            description_content_type: None,
+            requires_python: None,
+            requires_dist: Vec::new(),
+            provides_extra: Vec::new(),
+            project_urls: BTreeMap::new(),
+            entry_points: None,
+        };
+        let contents = contents.replace("\r\n", "\n");
+        let (headers, body) = match contents.split_once("\n\n") {
+            Some((headers, body)) => (headers, Some(body)),
+            None => (contents.as_str(), None),
+        };
+
+        // Fold continuation lines, which begin with whitespace, into their header.
+        let mut fields = Vec::<(String, String)>::new();
+        for line in headers.lines() {
+            if line.starts_with(|c: char| c.is_whitespace()) {
+                if let Some((_, value)) = fields.last_mut() {
+                    let line = line.trim_start();
+                    // Older metadata versions prefix description lines with `|`.
+                    let line = line.strip_prefix('|').unwrap_or(line);
+                    value.push('\n');
+                    value.push_str(line);
                 }
+                continue;
             }
-            ast::Expr::Name(name) => {
-                if let Some(v) = assignments.get(&name.id.to_string()) {
-                    return Ok(Self::parse_entrypoints(v, assignments)?);
-                }
+            if let Some((key, value)) = line.split_once(':') {
+                fields.push((key.trim().to_lowercase(), value.trim().to_string()));
             }
-            _ => (),
         }
-        return Err(PyValueError::new_err(format!(
-            "Failed to parse Entrypoint from Expr:\n{expr:#?}"
-        )));
-    }
 
-    fn get_setup_call<'a>(
-        statements: &'a Vec<ast::Stmt>,
-        idx: &mut usize,
-        assignments: &'a mut BTreeMap<String, ast::Expr>,
-    ) -> PyResult<Option<(&'a ast::ExprCall, &'a mut BTreeMap<String, ast::Expr>)>> {
-        if *idx < statements.len() {
-            match &statements[*idx] {
-                ast::Stmt::Assign(assignment) => {
-                    assignments.insert_assignments(PyAssignment::Unannotated(assignment))?;
+        for (key, value) in fields {
+            match key.as_str() {
+                "metadata-version" => metadata.metadata_version = Some(value),
+                "name" => metadata.name = Some(value),
+                "version" => metadata.version = Some(value),
+                "summary" => metadata.summary = Some(value),
+                "description" => metadata.description = Some(value),
+                "description-content-type" => metadata.description_content_type = Some(value),
+                "requires-python" => metadata.requires_python = Some(value),
+                "requires-dist" => metadata.requires_dist.push(value),
+                "provides-extra" => metadata.provides_extra.push(value),
+                "project-url" => {
+                    if let Some((label, url)) = value.split_once(',') {
+                        metadata
+                            .project_urls
+                            .insert(label.trim().to_string(), url.trim().to_string());
+                    }
+                }
+                _ => continue,
+            }
+        }
+        if let Some(body) = body {
+            if !body.trim().is_empty() {
+                metadata.description = Some(body.trim_end().to_string());
+            }
+        }
+        metadata
+    }
+
+    /// Parses an entry_points.txt file, which is formatted as an INI file with
+    /// a section per entry point group, e.g., `[console_scripts]`.
+    pub(crate) fn parse_entry_points(contents: &str) -> Option<Entrypoints> {
+        let mut console_scripts = Vec::<String>::new();
+        let mut gui_scripts = Vec::<String>::new();
+        let mut group = String::new();
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                continue;
+            }
+            if line.starts_with('[') && line.ends_with(']') {
+                group = line[1..line.len() - 1].trim().to_string();
+                continue;
+            }
+            if let Some((name, target)) = line.split_once('=') {
+                let entry_point = format!("{} = {}", name.trim(), target.trim());
+                match group.as_str() {
+                    "console_scripts" => console_scripts.push(entry_point),
+                    "gui_scripts" => gui_scripts.push(entry_point),
+                    _ => continue,
+                }
+            }
+        }
+        if console_scripts.is_empty() && gui_scripts.is_empty() {
+            return None;
+        }
+        Some(Entrypoints {
+            console_scripts: (!console_scripts.is_empty()).then_some(console_scripts),
+            gui_scripts: (!gui_scripts.is_empty()).then_some(gui_scripts),
+        })
+    }
+}
+
+impl SpecParser<Metadata> for DistributionParser {
+    fn from_file(path: &Path) -> Result<Metadata>
+    where
+        Self: Sized,
+    {
+        let file_name = path
+            .file_name()
+            .and_then(|f| f.to_str())
+            .unwrap_or_default();
+        let files = if file_name.ends_with(".tar.gz") || file_name.ends_with(".tgz") {
+            Self::read_tar_gz(path)?
+        } else {
+            Self::read_zip(path)?
+        };
+
+        // Wheels carry `<name>.dist-info/METADATA`, whereas sdists carry a
+        // top-level `<name>/PKG-INFO` and, for setuptools, an egg-info directory.
+        let metadata = files.iter().find(|(name, _)| {
+            let parts: Vec<&str> = name.split('/').collect();
+            match parts.as_slice() {
+                [dist_info, "METADATA"] => dist_info.ends_with(".dist-info"),
+                [_, "PKG-INFO"] => true,
+                _ => false,
+            }
+        });
+        let mut metadata = match metadata {
+            Some((_, contents)) => MetadataParser::parse_headers(contents),
+            None => {
+                return Err(Error::Parse(format!(
+                    "Failed to find METADATA or PKG-INFO in {:#?}",
+                    path.to_str()
+                )))
+            }
+        };
+        let entry_points = files
+            .iter()
+            .filter(|(name, _)| {
+                name.ends_with(".dist-info/entry_points.txt")
+                    || name.ends_with(".egg-info/entry_points.txt")
+            })
+            .min_by_key(|(name, _)| name.len());
+        metadata.entry_points = match entry_points {
+            Some((_, contents)) => MetadataParser::parse_entry_points(contents),
+            // Sdists built by backends other than setuptools only declare scripts
+            // in their pyproject.toml.
+            None => files
+                .iter()
+                .find(|(name, _)| {
+                    name.split('/').collect::<Vec<&str>>().len() == 2
+                        && name.ends_with("/pyproject.toml")
+                })
+                .and_then(|(_, contents)| toml::from_str::<PyProject>(contents).ok())
+                .and_then(|pyproject| pyproject.project)
+                .and_then(|project| {
+                    Entrypoints::from_project_scripts(
+                        project.project_scripts.as_ref(),
+                        project.project_gui_scripts.as_ref(),
+                    )
+                }),
+        };
+        Ok(metadata)
+    }
+}
+
+impl DistributionParser {
+    /// Files of interest are limited to metadata, entry points, and pyproject.toml.
+    fn is_relevant(name: &str) -> bool {
+        name.ends_with("/METADATA")
+            || name.ends_with("/PKG-INFO")
+            || name.ends_with("/entry_points.txt")
+            || name.ends_with("/pyproject.toml")
+    }
+
+    fn read_zip(path: &Path) -> Result<Vec<(String, String)>> {
+        let file = fs::File::open(path)?;
+        let mut archive = match zip::ZipArchive::new(file) {
+            Ok(archive) => archive,
+            Err(e) => {
+                return Err(Error::Parse(format!(
+                    "Failed to open zip archive {:#?}: {}",
+                    path.to_str(),
+                    e
+                )))
+            }
+        };
+        let mut files = Vec::<(String, String)>::new();
+        for i in 0..archive.len() {
+            let mut entry = match archive.by_index(i) {
+                Ok(entry) => entry,
+                Err(_) => continue,
+            };
+            let name = entry.name().to_string();
+            if !Self::is_relevant(&name) {
+                continue;
+            }
+            let mut contents = String::new();
+            if entry.read_to_string(&mut contents).is_ok() {
+                files.push((name, contents));
+            }
+        }
+        Ok(files)
+    }
+
+    fn read_tar_gz(path: &Path) -> Result<Vec<(String, String)>> {
+        let file = fs::File::open(path)?;
+        let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(file));
+        let mut files = Vec::<(String, String)>::new();
+        let entries = match archive.entries() {
+            Ok(entries) => entries,
+            Err(e) => {
+                return Err(Error::Parse(format!(
+                    "Failed to open tar archive {:#?}: {}",
+                    path.to_str(),
+                    e
+                )))
+            }
+        };
+        for entry in entries {
+            let mut entry = entry?;
+            let name = match entry.path() {
+                Ok(name) => name.to_string_lossy().to_string(),
+                Err(_) => continue,
+            };
+            if !Self::is_relevant(&name) {
+                continue;
+            }
+            let mut contents = String::new();
+            if entry.read_to_string(&mut contents).is_ok() {
+                files.push((name, contents));
+            }
+        }
+        Ok(files)
+    }
+}
+
+impl SpecParser<InstalledEnvironment> for SitePackagesParser {
+    fn from_file(path: &Path) -> Result<InstalledEnvironment>
+    where
+        Self: Sized,
+    {
+        let site_packages = Self::find_site_packages(path)?;
+        if site_packages.is_empty() {
+            return Err(Error::Parse(format!(
+                "Failed to find site-packages in {:#?}",
+                path.to_str()
+            )));
+        }
+        let python_version = Self::find_python_version(path, &site_packages);
+        let mut distributions = Vec::<InstalledDistribution>::new();
+        for dir in site_packages {
+            let mut dist_infos = Vec::<std::path::PathBuf>::new();
+            for entry in fs::read_dir(dir)? {
+                let entry_path = entry?.path();
+                if entry_path.is_dir()
+                    && entry_path
+                        .extension()
+                        .is_some_and(|extension| extension == "dist-info")
+                {
+                    dist_infos.push(entry_path);
+                }
+            }
+            dist_infos.sort();
+            for dist_info in dist_infos {
+                // A .dist-info directory without METADATA is left over from a
+                // broken install, which pip also ignores.
+                let contents = match fs::read_to_string(dist_info.join("METADATA")) {
+                    Ok(contents) => contents,
+                    Err(_) => continue,
+                };
+                let mut metadata = MetadataParser::parse_headers(&contents);
+                if let Ok(contents) = fs::read_to_string(dist_info.join("entry_points.txt")) {
+                    metadata.entry_points = MetadataParser::parse_entry_points(&contents);
+                }
+                let source = match fs::read_to_string(dist_info.join("direct_url.json")) {
+                    Ok(contents) => Some(Self::parse_direct_url(&dist_info, &contents)?),
+                    Err(_) => None,
+                };
+                distributions.push(InstalledDistribution { metadata, source });
+            }
+        }
+        Ok(InstalledEnvironment {
+            python_version,
+            distributions,
+        })
+    }
+}
+
+impl SitePackagesParser {
+    /// Whether `path` is a virtual environment, as marked by its pyvenv.cfg, or
+    /// another holding a site-packages directory, e.g., a conda environment, or
+    /// a site-packages directory itself.
+    pub fn is_environment(path: &Path) -> bool {
+        path.is_dir()
+            && (path.join("pyvenv.cfg").is_file()
+                || path.file_name().is_some_and(|name| name == "site-packages")
+                || Self::find_site_packages(path)
+                    .is_ok_and(|site_packages| site_packages.iter().any(|dir| dir != path)))
+    }
+
+    /// Accepts either a site-packages directory or the root of a virtual environment.
+    fn find_site_packages(path: &Path) -> Result<Vec<std::path::PathBuf>> {
+        let is_site_packages = fs::read_dir(path)?.any(|entry| {
+            entry.is_ok_and(|entry| {
+                entry
+                    .path()
+                    .extension()
+                    .is_some_and(|extension| extension == "dist-info")
+            })
+        });
+        if is_site_packages {
+            return Ok(vec![path.to_path_buf()]);
+        }
+        let mut site_packages = Vec::<std::path::PathBuf>::new();
+        // POSIX virtual environments nest site-packages under a versioned
+        // directory, e.g., lib/python3.11, whereas Windows uses Lib directly.
+        for lib in ["lib", "lib64"] {
+            let lib = path.join(lib);
+            if !lib.is_dir() {
+                continue;
+            }
+            for entry in fs::read_dir(lib)? {
+                let dir = entry?.path().join("site-packages");
+                if dir.is_dir() && !site_packages.contains(&dir) {
+                    site_packages.push(dir);
+                }
+            }
+        }
+        let dir = path.join("Lib").join("site-packages");
+        if dir.is_dir() && !site_packages.contains(&dir) {
+            site_packages.push(dir);
+        }
+        site_packages.sort();
+        Ok(site_packages)
+    }
+
+    /// Reads the version from a virtual environment's pyvenv.cfg, falling back to
+    /// the versioned directory site-packages is nested under, e.g., lib/python3.11.
+    fn find_python_version(path: &Path, site_packages: &[std::path::PathBuf]) -> Option<String> {
+        if let Ok(contents) = fs::read_to_string(path.join("pyvenv.cfg")) {
+            for line in contents.lines() {
+                if let Some((key, value)) = line.split_once('=') {
+                    if ["version", "version_info"].contains(&key.trim()) {
+                        // uv writes `version_info = 3.11.4.final.0`.
+                        let version = value
+                            .trim()
+                            .split('.')
+                            .take_while(|part| part.chars().all(|c| c.is_ascii_digit()))
+                            .collect::<Vec<&str>>()
+                            .join(".");
+                        if !version.is_empty() {
+                            return Some(version);
+                        }
+                    }
+                }
+            }
+        }
+        site_packages.iter().find_map(|dir| {
+            let lib = dir.parent()?.file_name()?.to_str()?;
+            let version = lib.strip_prefix("python")?;
+            version
+                .starts_with(|c: char| c.is_ascii_digit())
+                .then(|| version.to_string())
+        })
+    }
+
+    fn parse_direct_url(dist_info: &Path, contents: &str) -> Result<PackageSource> {
+        let direct_url = match serde_json::from_str::<DirectUrl>(contents) {
+            Ok(direct_url) => direct_url,
+            Err(e) => {
+                return Err(Error::Parse(format!(
+                    "Failed to parse direct_url.json in {:#?}: {}",
+                    dist_info.to_str(),
+                    e
+                )))
+            }
+        };
+        let source = match (direct_url.vcs_info, direct_url.dir_info) {
+            (Some(vcs_info), _) => PackageSource::Vcs {
+                url: format!("{}+{}", vcs_info.vcs, direct_url.url),
+                commit: Some(vcs_info.commit_id),
+            },
+            (None, Some(dir_info)) if dir_info.editable => PackageSource::Editable(direct_url.url),
+            _ if direct_url.url.starts_with("file://") => PackageSource::Path(direct_url.url),
+            _ => PackageSource::Url(direct_url.url),
+        };
+        Ok(source)
+    }
+}
+
+impl SetupParser {
+    /// Parses the `setup()` call, reading the files it refers to relative to
+    /// `root`, if given, and following imports `import_depth` modules deep.
+    fn parse_ast(
+        statements: Vec<ast::Stmt>,
+        root: Option<&Path>,
+        import_depth: usize,
+    ) -> Result<Setup> {
+        let mut package_name: Option<String> = None;
+        let mut version: Option<String> = None;
+        let mut description: Option<String> = None;
+        let mut python_requires: Option<String> = None;
+        let mut project_urls: Option<BTreeMap<String, String>> = None;
+        let mut install_requires: Option<Vec<Requirement>> = None;
+        let mut setup_requires: Option<Vec<Requirement>> = None;
+        let mut extra_requires: Option<BTreeMap<String, Vec<Requirement>>> = None;
+        let mut entry_points: Option<Entrypoints> = None;
+        let mut dynamic_version: Option<DynamicVersion> = None;
+        let mut readme: Option<Readme> = None;
+        let mut content_type: Option<String> = None;
+        let mut requirements_file: Option<String> = None;
+
+        let imports = LocalImports {
+            root: root.unwrap_or(Path::new(".")),
+            package: Vec::new(),
+            depth: root.map_or(0, |_| import_depth),
+        };
+        let imported = Self::imported_names(&statements);
+        if let Some((setup, assignments)) = Self::get_setup_call(&statements, &imports)? {
+            for keyword in &Self::get_setup_kwargs(setup, &assignments)? {
+                let ident = keyword.arg.clone().unwrap();
+                match ident.as_str() {
+                    "name" => {
+                        package_name = Some(Self::parse_string(&keyword.value, &assignments)?)
+                    }
+                    "version" => {
+                        (version, dynamic_version) =
+                            Self::parse_version(&keyword.value, &assignments, &imported, root)?
+                    }
+                    "description" => {
+                        description = Some(Self::parse_string(&keyword.value, &assignments)?)
+                    }
+                    "python_requires" => {
+                        python_requires = Some(Self::parse_string(&keyword.value, &assignments)?)
+                    }
+                    "project_urls" => {
+                        project_urls = Some(Self::parse_string_map(&keyword.value, &assignments)?)
+                    }
+                    "long_description" => readme = Self::parse_readme(&keyword.value, &assignments),
+                    "long_description_content_type" => {
+                        content_type = Some(Self::parse_string(&keyword.value, &assignments)?)
+                    }
+                    "install_requires" => {
+                        (install_requires, requirements_file) =
+                            Self::parse_install_requires(&keyword.value, &assignments, root)?;
+                    }
+                    "setup_requires" => {
+                        setup_requires =
+                            Some(Self::parse_string_vec(&keyword.value, &assignments)?);
+                    }
+                    "extras_require" | "extra_requires" => {
+                        extra_requires =
+                            Some(Self::parse_requires_map(&keyword.value, &assignments)?);
+                    }
+                    "entry_points" => {
+                        entry_points = Some(Self::parse_entrypoints(&keyword.value, &assignments)?);
+                    }
+                    _ => continue,
+                }
+            }
+        }
+        Ok(Setup {
+            package_name,
+            version,
+            description,
+            python_requires,
+            project_urls,
+            install_requires,
+            extra_requires,
+            setup_requires,
+            entry_points,
+            layout: None,
+            dynamic_version,
+            readme: readme.map(|readme| match readme {
+                Readme::Table { file, text, .. } => Readme::Table {
+                    file,
+                    text,
+                    content_type,
+                },
+                readme => readme,
+            }),
+            requirements_file,
+        })
+    }
+
+    /// Parses the long description, or the file it is read from, e.g.,
+    /// `open("README.md").read()`. Any other expression is skipped.
+    fn parse_readme(expr: &ast::Expr, assignments: &BTreeMap<String, ast::Expr>) -> Option<Readme> {
+        if let Ok(text) = Self::parse_string(expr, assignments) {
+            return Some(Readme::Table {
+                file: None,
+                text: Some(text),
+                content_type: None,
+            });
+        }
+        Some(Readme::Table {
+            file: Some(Self::parse_read(expr, assignments)?),
+            text: None,
+            content_type: None,
+        })
+    }
+
+    /// Parses `install_requires`, reading any requirements file it is read
+    /// from relative to `root`, if given, e.g., `open("requirements.txt").read().splitlines()`.
+    fn parse_install_requires(
+        expr: &ast::Expr,
+        assignments: &BTreeMap<String, ast::Expr>,
+        root: Option<&Path>,
+    ) -> Result<(Option<Vec<Requirement>>, Option<String>)> {
+        match Self::parse_requirements_file(expr, assignments) {
+            // A missing file is left to be read when the package is built.
+            Some(path) => match root.map(|root| root.join(&path)) {
+                Some(file) if file.is_file() => {
+                    let (requires, has_options) =
+                        Self::read_requirements_file(&file, &mut Vec::new())?;
+                    // setuptools cannot read options from the file itself, so
+                    // its requirements are listed instead.
+                    let path = if has_options { None } else { Some(path) };
+                    Ok((Some(requires), path))
+                }
+                _ => Ok((None, Some(path))),
+            },
+            None => Ok((Some(Self::parse_string_vec(expr, assignments)?), None)),
+        }
+    }
+
+    /// Reads the requirements of a requirements file, following `-r` lines
+    /// relative to it. Other options, e.g., `-c`, `-e .` or `--index-url`,
+    /// are left out. Also returns whether the file holds any option.
+    fn read_requirements_file(
+        file: &Path,
+        visited: &mut Vec<std::path::PathBuf>,
+    ) -> Result<(Vec<Requirement>, bool)> {
+        let mut requires = Vec::<Requirement>::new();
+        let mut has_options = false;
+        visited.push(file.to_path_buf());
+        for requirement in RequirementsParser::from_file(file)?.requires {
+            if !requirement.starts_with('-') {
+                if !requires.contains(&requirement) {
+                    requires.push(requirement);
+                }
+                continue;
+            }
+            has_options = true;
+            let Some(included) = requirement
+                .strip_prefix("--requirement")
+                .or_else(|| requirement.strip_prefix("-r"))
+            else {
+                continue;
+            };
+            let included = file
+                .parent()
+                .unwrap_or(Path::new(""))
+                .join(included.trim_start_matches('=').trim());
+            // Files including each other are read once.
+            if visited.contains(&included) {
+                continue;
+            }
+            for requirement in Self::read_requirements_file(&included, visited)?.0 {
+                if !requires.contains(&requirement) {
+                    requires.push(requirement);
+                }
+            }
+        }
+        Ok((requires, has_options))
+    }
+
+    /// Parses the path of a requirements file read line by line, e.g., by
+    /// `Path(path).read_text().splitlines()` or `[line for line in open(path)]`.
+    fn parse_requirements_file(
+        expr: &ast::Expr,
+        assignments: &BTreeMap<String, ast::Expr>,
+    ) -> Option<String> {
+        match expr {
+            ast::Expr::Name(name) => {
+                Self::parse_requirements_file(assignments.get(&name.id.to_string())?, assignments)
+            }
+            ast::Expr::Call(call) => {
+                let ast::Expr::Attribute(method) = call.func.as_ref() else {
+                    return None;
+                };
+                match method.attr.as_str() {
+                    "splitlines" | "split" => Self::parse_read(&method.value, assignments),
+                    "readlines" => Self::parse_file_path(&method.value, assignments),
+                    _ => None,
+                }
+            }
+            ast::Expr::ListComp(comp) => match comp.generators.as_slice() {
+                [generator] if matches!(generator.iter, ast::Expr::Call(_)) => {
+                    Self::parse_requirements_file(&generator.iter, assignments)
+                        .or_else(|| Self::parse_file_path(&generator.iter, assignments))
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Parses the path of a file read whole, e.g., by `open(path).read()` or
+    /// `Path(path).read_text(encoding="utf-8").strip()`.
+    fn parse_read(expr: &ast::Expr, assignments: &BTreeMap<String, ast::Expr>) -> Option<String> {
+        match expr {
+            ast::Expr::Name(name) => {
+                Self::parse_read(assignments.get(&name.id.to_string())?, assignments)
+            }
+            ast::Expr::Call(call) => {
+                let ast::Expr::Attribute(method) = call.func.as_ref() else {
+                    return None;
+                };
+                match method.attr.as_str() {
+                    "strip" | "rstrip" | "lstrip" | "decode" => {
+                        Self::parse_read(&method.value, assignments)
+                    }
+                    "read" | "read_text" => Self::parse_file_path(&method.value, assignments),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Parses the version, resolving one read from the project's files (e.g.,
+    /// `__version__` imported from the package) relative to `root`, if given. A
+    /// version which cannot be resolved is kept as where it is read from, and one read
+    /// from anywhere else (e.g., `get_version()`) is unsupported.
+    fn parse_version(
+        expr: &ast::Expr,
+        assignments: &BTreeMap<String, ast::Expr>,
+        imported: &BTreeMap<String, String>,
+        root: Option<&Path>,
+    ) -> Result<(Option<String>, Option<DynamicVersion>)> {
+        if let Ok(version) = Self::parse_string(expr, assignments) {
+            return Ok((Some(version), None));
+        }
+        let source =
+            Self::parse_version_source(expr, assignments, imported, root).ok_or_else(|| {
+                Error::UnsupportedExpression(format!(
+                    "Failed to parse version from Expr:\n{expr:#?}"
+                ))
+            })?;
+        match root.and_then(|root| source.resolve(root)) {
+            Some(version) => Ok((Some(version), None)),
+            None => Ok((None, Some(source))),
+        }
+    }
+
+    fn parse_version_source(
+        expr: &ast::Expr,
+        assignments: &BTreeMap<String, ast::Expr>,
+        imported: &BTreeMap<String, String>,
+        root: Option<&Path>,
+    ) -> Option<DynamicVersion> {
+        match expr {
+            ast::Expr::Name(name) => match assignments.get(&name.id.to_string()) {
+                Some(value) => Self::parse_version_source(value, assignments, imported, root),
+                // e.g., `from spam import __version__`
+                None => Some(DynamicVersion::attribute(
+                    imported.get(name.id.as_str())?,
+                    root,
+                )),
+            },
+            ast::Expr::Attribute(_) => Some(DynamicVersion::attribute(
+                &Self::parse_dotted_name(expr, imported)?,
+                root,
+            )),
+            ast::Expr::Call(call) => {
+                let ast::Expr::Attribute(method) = call.func.as_ref() else {
+                    return None;
+                };
+                match method.attr.as_str() {
+                    "strip" | "rstrip" | "lstrip" | "decode" => {
+                        Self::parse_version_source(&method.value, assignments, imported, root)
+                    }
+                    "read" | "read_text" => Some(DynamicVersion::File(Self::parse_file_path(
+                        &method.value,
+                        assignments,
+                    )?)),
+                    // e.g., `re.search(pattern, open(path).read()).group(1)`
+                    "group" => {
+                        let ast::Expr::Call(search) = method.value.as_ref() else {
+                            return None;
+                        };
+                        let pattern = Self::parse_string(search.args.first()?, assignments).ok()?;
+                        match Self::parse_version_source(
+                            search.args.get(1)?,
+                            assignments,
+                            imported,
+                            root,
+                        )? {
+                            DynamicVersion::File(path) => Some(DynamicVersion::Pattern {
+                                path,
+                                pattern: Some(pattern),
+                            }),
+                            _ => None,
+                        }
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Parses a dotted name, e.g., `spam.__version__`, following names
+    /// imported with `from ... import ...`.
+    fn parse_dotted_name(expr: &ast::Expr, imported: &BTreeMap<String, String>) -> Option<String> {
+        match expr {
+            ast::Expr::Name(name) => Some(
+                imported
+                    .get(name.id.as_str())
+                    .cloned()
+                    .unwrap_or_else(|| name.id.to_string()),
+            ),
+            ast::Expr::Attribute(attribute) => Some(format!(
+                "{}.{}",
+                Self::parse_dotted_name(&attribute.value, imported)?,
+                attribute.attr
+            )),
+            _ => None,
+        }
+    }
+
+    /// Maps the names bound by absolute `from ... import ...` statements of
+    /// setup.py to their dotted names, e.g., `__version__` to `spam.__version__`.
+    fn imported_names(statements: &[ast::Stmt]) -> BTreeMap<String, String> {
+        let mut imported = BTreeMap::<String, String>::new();
+        for statement in statements.iter() {
+            let ast::Stmt::ImportFrom(import) = statement else {
+                continue;
+            };
+            let module = match &import.module {
+                Some(module) if import.level.as_ref().map_or(0, |level| level.to_u32()) == 0 => {
+                    module
+                }
+                _ => continue,
+            };
+            for alias in import.names.iter() {
+                if alias.name.as_str() != "*" {
+                    let name = alias.asname.as_ref().unwrap_or(&alias.name);
+                    imported.insert(name.to_string(), format!("{}.{}", module, alias.name));
+                }
+            }
+        }
+        imported
+    }
+
+    /// Parses the path of a file which is read, e.g., by `open(path).read()`
+    /// or `Path(path).read_text()`, relative to the directory of setup.py.
+    fn parse_file_path(
+        expr: &ast::Expr,
+        assignments: &BTreeMap<String, ast::Expr>,
+    ) -> Option<String> {
+        // e.g., `f` bound by `with open(path) as f:`
+        if let ast::Expr::Name(name) = expr {
+            if let Some(value) = assignments.get(name.id.as_str()) {
+                return Self::parse_file_path(value, assignments);
+            }
+        }
+        if let ast::Expr::Call(call) = expr {
+            match call.func.as_ref() {
+                ast::Expr::Name(name) if name.id.as_str() == "open" => {
+                    return Self::parse_path(call.args.first()?, assignments);
+                }
+                // `io.open(path)`, `codecs.open(path)` or `Path(path).open()`
+                ast::Expr::Attribute(method) if method.attr.as_str() == "open" => {
+                    return match call.args.first() {
+                        Some(path) => Self::parse_path(path, assignments),
+                        None => Self::parse_path(&method.value, assignments),
+                    };
                 }
+                _ => (),
+            }
+        }
+        Self::parse_path(expr, assignments)
+    }
+
+    /// Parses a path relative to the directory of setup.py, which is anchored at
+    /// `__file__`, e.g., `os.path.join(os.path.dirname(__file__), "VERSION")`.
+    fn parse_path(expr: &ast::Expr, assignments: &BTreeMap<String, ast::Expr>) -> Option<String> {
+        let join = |base: String, path: String| {
+            if base.is_empty() {
+                path
+            } else {
+                format!("{}/{}", base, path)
+            }
+        };
+        let join_all = |args: &[ast::Expr]| {
+            let mut path = String::new();
+            for arg in args.iter() {
+                path = join(path, Self::parse_path(arg, assignments)?);
+            }
+            Some(path)
+        };
+        match expr {
+            ast::Expr::Constant(constant) => match &constant.value {
+                ast::Constant::Str(path) => Some(path.trim_start_matches("./").to_string()),
+                _ => None,
+            },
+            ast::Expr::Name(name) if name.id.as_str() == "__file__" => Some(String::new()),
+            ast::Expr::Name(name) => {
+                Self::parse_path(assignments.get(&name.id.to_string())?, assignments)
+            }
+            ast::Expr::Attribute(attribute) if attribute.attr.as_str() == "parent" => {
+                Self::parse_path(&attribute.value, assignments)
+            }
+            ast::Expr::BinOp(binop) if matches!(binop.op, ast::Operator::Div) => Some(join(
+                Self::parse_path(&binop.left, assignments)?,
+                Self::parse_path(&binop.right, assignments)?,
+            )),
+            ast::Expr::Call(call) => {
+                let (receiver, function) = match call.func.as_ref() {
+                    ast::Expr::Name(name) => (None, name.id.as_str()),
+                    ast::Expr::Attribute(attribute) => {
+                        (Some(attribute.value.as_ref()), attribute.attr.as_str())
+                    }
+                    _ => return None,
+                };
+                match (function, receiver) {
+                    ("join", _) => join_all(&call.args),
+                    ("joinpath", Some(receiver)) => Some(join(
+                        Self::parse_path(receiver, assignments)?,
+                        join_all(&call.args)?,
+                    )),
+                    ("resolve" | "absolute", Some(receiver)) if call.args.is_empty() => {
+                        Self::parse_path(receiver, assignments)
+                    }
+                    ("Path" | "PurePath" | "dirname" | "abspath" | "realpath", _) => {
+                        Self::parse_path(call.args.first()?, assignments)
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    fn parse_string(expr: &ast::Expr, assignments: &BTreeMap<String, ast::Expr>) -> Result<String> {
+        match Evaluator::new(assignments).evaluate(expr) {
+            Ok(PyValue::Str(s)) => Ok(s),
+            _ => Err(Error::UnsupportedExpression(format!(
+                "Failed to parse String from Expr:\n{expr:#?}",
+            ))),
+        }
+    }
+
+    fn parse_string_vec(
+        expr: &ast::Expr,
+        assignments: &BTreeMap<String, ast::Expr>,
+    ) -> Result<Vec<String>> {
+        match Evaluator::new(assignments).evaluate(expr) {
+            Ok(value) => Self::as_strings(value),
+            Err(_) => None,
+        }
+        .ok_or_else(|| {
+            Error::UnsupportedExpression(format!(
+                "Failed to parse Vec<String> from Expr:\n{expr:#?}"
+            ))
+        })
+    }
+
+    fn parse_requires_map(
+        expr: &ast::Expr,
+        assignments: &BTreeMap<String, ast::Expr>,
+    ) -> Result<BTreeMap<String, Vec<Requirement>>> {
+        let error = || {
+            Error::UnsupportedExpression(format!(
+                "Failed to parse BTreeMap<String, Vec<String>> from Expr:\n{expr:#?}"
+            ))
+        };
+        let Ok(PyValue::Dict(items)) = Evaluator::new(assignments).evaluate(expr) else {
+            return Err(error());
+        };
+        let mut mapped = BTreeMap::<String, Vec<Requirement>>::new();
+        for (key, value) in items {
+            match (key, Self::as_strings(value)) {
+                (PyValue::Str(key), Some(requires)) => {
+                    mapped.insert(key, requires);
+                }
+                _ => return Err(error()),
+            }
+        }
+        Ok(mapped)
+    }
+
+    fn parse_string_map(
+        expr: &ast::Expr,
+        assignments: &BTreeMap<String, ast::Expr>,
+    ) -> Result<BTreeMap<String, String>> {
+        let error = || {
+            Error::UnsupportedExpression(format!(
+                "Failed to parse BTreeMap<String, String> from Expr:\n{expr:#?}"
+            ))
+        };
+        let Ok(PyValue::Dict(items)) = Evaluator::new(assignments).evaluate(expr) else {
+            return Err(error());
+        };
+        let mut mapped = BTreeMap::<String, String>::new();
+        for (key, value) in items {
+            match (key, value) {
+                (PyValue::Str(key), PyValue::Str(value)) => {
+                    mapped.insert(key, value);
+                }
+                _ => return Err(error()),
+            }
+        }
+        Ok(mapped)
+    }
+
+    fn parse_entrypoints(
+        expr: &ast::Expr,
+        assignments: &BTreeMap<String, ast::Expr>,
+    ) -> Result<Entrypoints> {
+        let error = || {
+            Error::UnsupportedExpression(format!(
+                "Failed to parse Entrypoint from Expr:\n{expr:#?}"
+            ))
+        };
+        let Ok(PyValue::Dict(items)) = Evaluator::new(assignments).evaluate(expr) else {
+            return Err(error());
+        };
+        let mut entry_points = Entrypoints {
+            console_scripts: None,
+            gui_scripts: None,
+        };
+        for (key, value) in items {
+            match key.to_str().as_str() {
+                "console_scripts" => {
+                    entry_points.console_scripts = Some(Self::as_strings(value).ok_or_else(error)?)
+                }
+                "gui_scripts" => {
+                    entry_points.gui_scripts = Some(Self::as_strings(value).ok_or_else(error)?)
+                }
+                _ => (),
+            }
+        }
+        if entry_points.console_scripts.is_none() && entry_points.gui_scripts.is_none() {
+            return Err(error());
+        }
+        Ok(entry_points)
+    }
+
+    /// Returns the strings of a list (or tuple) of strings.
+    fn as_strings(value: PyValue) -> Option<Vec<String>> {
+        match value {
+            PyValue::List(values) => values
+                .into_iter()
+                .map(|value| match value {
+                    PyValue::Str(s) => Some(s),
+                    _ => None,
+                })
+                .collect(),
+            _ => None,
+        }
+    }
+
+    /// Finds the `setup()` call and the assignments in scope where it is made.
+    /// Statements are walked in order, through `if`, `try`, `with`, loops and
+    /// `match`, and a function's body is walked where the function is called
+    /// (e.g., `main()`), or else once the module has been walked.
+    fn get_setup_call<'a>(
+        statements: &'a [ast::Stmt],
+        imports: &LocalImports,
+    ) -> Result<Option<(&'a ast::ExprCall, BTreeMap<String, ast::Expr>)>> {
+        let mut assignments = BTreeMap::<String, ast::Expr>::new();
+        let mut functions = Vec::<(String, &[ast::Stmt])>::new();
+        if let Some(found) =
+            Self::find_setup_call(statements, &mut assignments, &mut functions, imports)?
+        {
+            return Ok(Some(found));
+        }
+        while !functions.is_empty() {
+            let (_, body) = functions.remove(0);
+            let mut locals = assignments.clone();
+            if let Some(found) = Self::find_setup_call(body, &mut locals, &mut functions, imports)?
+            {
+                return Ok(Some(found));
+            }
+        }
+        Ok(None)
+    }
+
+    fn find_setup_call<'a>(
+        statements: &'a [ast::Stmt],
+        assignments: &mut BTreeMap<String, ast::Expr>,
+        functions: &mut Vec<(String, &'a [ast::Stmt])>,
+        imports: &LocalImports,
+    ) -> Result<Option<(&'a ast::ExprCall, BTreeMap<String, ast::Expr>)>> {
+        for statement in statements.iter() {
+            let found = match statement {
+                ast::Stmt::Assign(assignment) => match Self::as_setup_call(&assignment.value) {
+                    Some(call) => Some((call, assignments.clone())),
+                    None => {
+                        assignments.insert_assignments(PyAssignment::Unannotated(assignment));
+                        None
+                    }
+                },
                 ast::Stmt::AnnAssign(assignment) => {
-                    assignments.insert_assignments(PyAssignment::Annotated(assignment))?;
+                    assignments.insert_assignments(PyAssignment::Annotated(assignment));
+                    None
                 }
+                ast::Stmt::AugAssign(assignment) => {
+                    assignments.insert_aug_assignment(assignment);
+                    None
+                }
+                ast::Stmt::Delete(delete) => {
+                    for target in delete.targets.iter() {
+                        assignments.remove_targets(target);
+                    }
+                    None
+                }
+                ast::Stmt::ImportFrom(import) => {
+                    Self::insert_local_imports(import, assignments, imports);
+                    None
+                }
+                ast::Stmt::FunctionDef(ast::StmtFunctionDef { name, body, .. })
+                | ast::Stmt::AsyncFunctionDef(ast::StmtAsyncFunctionDef { name, body, .. }) => {
+                    functions.retain(|(function, _)| function != name.as_str());
+                    functions.push((name.to_string(), body));
+                    None
+                }
+                ast::Stmt::Expr(expr) => match Self::as_setup_call(&expr.value) {
+                    Some(call) => Some((call, assignments.clone())),
+                    None => {
+                        assignments.insert_mutation(&expr.value);
+                        Self::find_called_function(&expr.value, assignments, functions, imports)?
+                    }
+                },
                 ast::Stmt::If(if_stmt) => {
-                    return Self::get_setup_call(&if_stmt.body, &mut 0, assignments);
+                    let test = Evaluator::new(assignments).evaluate(&if_stmt.test);
+                    match test {
+                        // Only the branch which runs is walked, e.g., not the
+                        // body of `if False:`.
+                        Ok(test) => {
+                            let branch = if test.is_truthy() {
+                                &if_stmt.body
+                            } else {
+                                &if_stmt.orelse
+                            };
+                            Self::find_setup_call(branch, assignments, functions, imports)?
+                        }
+                        Err(_) => {
+                            let branches = vec![
+                                (if_stmt.body.as_slice(), assignments.clone()),
+                                (if_stmt.orelse.as_slice(), assignments.clone()),
+                            ];
+                            Self::find_in_branches(branches, assignments, functions, imports)?
+                        }
+                    }
+                }
+                // The `try` block is taken to run, and then either its `else`
+                // block or an `except` block, which only sees the names the
+                // `try` block leaves as they were.
+                ast::Stmt::Try(ast::StmtTry {
+                    body,
+                    handlers,
+                    orelse,
+                    finalbody,
+                    ..
+                })
+                | ast::Stmt::TryStar(ast::StmtTryStar {
+                    body,
+                    handlers,
+                    orelse,
+                    finalbody,
+                    ..
+                }) => {
+                    let entry = assignments.clone();
+                    match Self::find_setup_call(body, assignments, functions, imports)? {
+                        Some(found) => Some(found),
+                        None => match Self::find_in_branches(
+                            Self::try_branches(orelse, handlers, &entry, assignments),
+                            assignments,
+                            functions,
+                            imports,
+                        )? {
+                            Some(found) => Some(found),
+                            None => {
+                                Self::find_setup_call(finalbody, assignments, functions, imports)?
+                            }
+                        },
+                    }
+                }
+                // e.g., `with open("README.md") as f:`, so that `f.read()` is
+                // read from the file.
+                ast::Stmt::With(ast::StmtWith { items, body, .. })
+                | ast::Stmt::AsyncWith(ast::StmtAsyncWith { items, body, .. }) => {
+                    for item in items.iter() {
+                        if let Some(vars) = &item.optional_vars {
+                            assignments.bind(vars, &item.context_expr);
+                        }
+                    }
+                    Self::find_setup_call(body, assignments, functions, imports)?
+                }
+                ast::Stmt::For(ast::StmtFor {
+                    target,
+                    body,
+                    orelse,
+                    ..
+                })
+                | ast::Stmt::AsyncFor(ast::StmtAsyncFor {
+                    target,
+                    body,
+                    orelse,
+                    ..
+                }) => Self::find_in_loop(
+                    Some(target.as_ref()),
+                    body,
+                    orelse,
+                    assignments,
+                    functions,
+                    imports,
+                )?,
+                ast::Stmt::While(ast::StmtWhile { body, orelse, .. }) => {
+                    Self::find_in_loop(None, body, orelse, assignments, functions, imports)?
+                }
+                ast::Stmt::Match(match_stmt) => {
+                    let mut branches = match_stmt
+                        .cases
+                        .iter()
+                        .map(|case| (case.body.as_slice(), assignments.clone()))
+                        .collect::<Vec<_>>();
+                    // No case may match, unless the last one is `case _:`.
+                    let exhaustive = match_stmt.cases.last().is_some_and(|case| {
+                        case.guard.is_none()
+                            && matches!(
+                                case.pattern,
+                                ast::Pattern::MatchAs(ast::PatternMatchAs { pattern: None, .. })
+                            )
+                    });
+                    if !exhaustive {
+                        branches.push((&[][..], assignments.clone()));
+                    }
+                    Self::find_in_branches(branches, assignments, functions, imports)?
+                }
+                _ => None,
+            };
+            if found.is_some() {
+                return Ok(found);
+            }
+        }
+        Ok(None)
+    }
+
+    /// Walks alternative branches, each from its own assignments. Afterwards,
+    /// only the names bound to the same value by every branch are kept, as
+    /// the branch which runs cannot be known.
+    fn find_in_branches<'a>(
+        branches: Vec<(&'a [ast::Stmt], BTreeMap<String, ast::Expr>)>,
+        assignments: &mut BTreeMap<String, ast::Expr>,
+        functions: &mut Vec<(String, &'a [ast::Stmt])>,
+        imports: &LocalImports,
+    ) -> Result<Option<(&'a ast::ExprCall, BTreeMap<String, ast::Expr>)>> {
+        let mut scopes = Vec::new();
+        for (branch, mut scope) in branches {
+            if let Some(found) = Self::find_setup_call(branch, &mut scope, functions, imports)? {
+                return Ok(Some(found));
+            }
+            scopes.push(scope);
+        }
+        *assignments = Self::merge_scopes(&scopes);
+        Ok(None)
+    }
+
+    /// Returns the `else` block of a `try` statement, starting from the names
+    /// bound by the `try` block, and each `except` block, starting from the
+    /// names the `try` block leaves as they were.
+    fn try_branches<'a>(
+        orelse: &'a [ast::Stmt],
+        handlers: &'a [ast::ExceptHandler],
+        entry: &BTreeMap<String, ast::Expr>,
+        assignments: &BTreeMap<String, ast::Expr>,
+    ) -> Vec<(&'a [ast::Stmt], BTreeMap<String, ast::Expr>)> {
+        let raised = Self::merge_scopes(&[entry.clone(), assignments.clone()]);
+        let mut branches = vec![(orelse, assignments.clone())];
+        for handler in handlers.iter() {
+            let ast::ExceptHandler::ExceptHandler(handler) = handler;
+            branches.push((handler.body.as_slice(), raised.clone()));
+        }
+        branches
+    }
+
+    /// Keeps the names bound to the same value in each scope.
+    fn merge_scopes(scopes: &[BTreeMap<String, ast::Expr>]) -> BTreeMap<String, ast::Expr> {
+        let Some((first, rest)) = scopes.split_first() else {
+            return BTreeMap::new();
+        };
+        first
+            .iter()
+            .filter(|(name, value)| rest.iter().all(|scope| scope.get(*name) == Some(*value)))
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect()
+    }
+
+    /// Walks a `for` or `while` loop. The loop may run any number of times,
+    /// so the names its body binds or mutates, and its target, are forgotten
+    /// before the body is walked.
+    fn find_in_loop<'a>(
+        target: Option<&ast::Expr>,
+        body: &'a [ast::Stmt],
+        orelse: &'a [ast::Stmt],
+        assignments: &mut BTreeMap<String, ast::Expr>,
+        functions: &mut Vec<(String, &'a [ast::Stmt])>,
+        imports: &LocalImports,
+    ) -> Result<Option<(&'a ast::ExprCall, BTreeMap<String, ast::Expr>)>> {
+        let mut names = BTreeSet::new();
+        if let Some(target) = target {
+            Self::insert_target_names(target, &mut names);
+        }
+        Self::insert_bound_names(body, &mut names);
+        assignments.retain(|name, _| !names.contains(name));
+        let mut scope = assignments.clone();
+        match Self::find_setup_call(body, &mut scope, functions, imports)? {
+            Some(found) => Ok(Some(found)),
+            None => Self::find_setup_call(orelse, assignments, functions, imports),
+        }
+    }
+
+    /// Collects the names bound, deleted or mutated by the statements,
+    /// including those nested in other statements.
+    fn insert_bound_names(statements: &[ast::Stmt], names: &mut BTreeSet<String>) {
+        for statement in statements.iter() {
+            match statement {
+                ast::Stmt::Assign(assignment) => {
+                    for target in assignment.targets.iter() {
+                        Self::insert_target_names(target, names);
+                    }
+                }
+                ast::Stmt::AnnAssign(ast::StmtAnnAssign { target, .. })
+                | ast::Stmt::AugAssign(ast::StmtAugAssign { target, .. }) => {
+                    Self::insert_target_names(target, names);
+                }
+                ast::Stmt::Delete(delete) => {
+                    for target in delete.targets.iter() {
+                        Self::insert_target_names(target, names);
+                    }
                 }
+                ast::Stmt::Import(ast::StmtImport { names: aliases, .. })
+                | ast::Stmt::ImportFrom(ast::StmtImportFrom { names: aliases, .. }) => {
+                    for alias in aliases.iter() {
+                        let name = alias.asname.as_ref().unwrap_or(&alias.name);
+                        names.insert(name.to_string());
+                    }
+                }
+                // e.g., `REQUIRES.append(dep)`
                 ast::Stmt::Expr(expr) => {
-                    if let ast::Expr::Call(c) = expr.value.as_ref() {
-                        let is_setup = match c.func.as_ref() {
-                            ast::Expr::Name(n) => "setup" == n.id.as_str(),
-                            ast::Expr::Attribute(a) => "setup" == a.attr.as_str(),
-                            _ => false,
-                        };
-                        if is_setup {
-                            return Ok(Some((c, assignments)));
+                    if let ast::Expr::Call(call) = expr.value.as_ref() {
+                        if let ast::Expr::Attribute(method) = call.func.as_ref() {
+                            Self::insert_target_names(&method.value, names);
                         }
-                    };
+                    }
+                }
+                ast::Stmt::If(ast::StmtIf { body, orelse, .. })
+                | ast::Stmt::While(ast::StmtWhile { body, orelse, .. }) => {
+                    Self::insert_bound_names(body, names);
+                    Self::insert_bound_names(orelse, names);
+                }
+                ast::Stmt::For(ast::StmtFor {
+                    target,
+                    body,
+                    orelse,
+                    ..
+                })
+                | ast::Stmt::AsyncFor(ast::StmtAsyncFor {
+                    target,
+                    body,
+                    orelse,
+                    ..
+                }) => {
+                    Self::insert_target_names(target, names);
+                    Self::insert_bound_names(body, names);
+                    Self::insert_bound_names(orelse, names);
+                }
+                ast::Stmt::With(ast::StmtWith { items, body, .. })
+                | ast::Stmt::AsyncWith(ast::StmtAsyncWith { items, body, .. }) => {
+                    for item in items.iter() {
+                        if let Some(vars) = &item.optional_vars {
+                            Self::insert_target_names(vars, names);
+                        }
+                    }
+                    Self::insert_bound_names(body, names);
+                }
+                ast::Stmt::Try(ast::StmtTry {
+                    body,
+                    handlers,
+                    orelse,
+                    finalbody,
+                    ..
+                })
+                | ast::Stmt::TryStar(ast::StmtTryStar {
+                    body,
+                    handlers,
+                    orelse,
+                    finalbody,
+                    ..
+                }) => {
+                    Self::insert_bound_names(body, names);
+                    for handler in handlers.iter() {
+                        let ast::ExceptHandler::ExceptHandler(handler) = handler;
+                        Self::insert_bound_names(&handler.body, names);
+                    }
+                    Self::insert_bound_names(orelse, names);
+                    Self::insert_bound_names(finalbody, names);
+                }
+                ast::Stmt::Match(match_stmt) => {
+                    for case in match_stmt.cases.iter() {
+                        Self::insert_bound_names(&case.body, names);
+                    }
                 }
                 _ => (),
+            }
+        }
+    }
+
+    /// Collects the names bound by an assignment target, or the name of the
+    /// list or dict an item is assigned to.
+    fn insert_target_names(target: &ast::Expr, names: &mut BTreeSet<String>) {
+        match target {
+            ast::Expr::Name(name) => {
+                names.insert(name.id.to_string());
+            }
+            ast::Expr::Tuple(ast::ExprTuple { elts, .. })
+            | ast::Expr::List(ast::ExprList { elts, .. }) => {
+                for elt in elts.iter() {
+                    Self::insert_target_names(elt, names);
+                }
+            }
+            ast::Expr::Starred(ast::ExprStarred { value, .. })
+            | ast::Expr::Subscript(ast::ExprSubscript { value, .. }) => {
+                Self::insert_target_names(value, names);
+            }
+            _ => (),
+        }
+    }
+
+    /// Walks the body of a function defined in the module when it is called,
+    /// with its own scope.
+    fn find_called_function<'a>(
+        expr: &ast::Expr,
+        assignments: &BTreeMap<String, ast::Expr>,
+        functions: &mut Vec<(String, &'a [ast::Stmt])>,
+        imports: &LocalImports,
+    ) -> Result<Option<(&'a ast::ExprCall, BTreeMap<String, ast::Expr>)>> {
+        let ast::Expr::Call(call) = expr else {
+            return Ok(None);
+        };
+        let ast::Expr::Name(name) = call.func.as_ref() else {
+            return Ok(None);
+        };
+        // Each function is walked once, so that recursion ends.
+        let Some(i) = functions
+            .iter()
+            .position(|(function, _)| function == name.id.as_str())
+        else {
+            return Ok(None);
+        };
+        let (_, body) = functions.remove(i);
+        let mut locals = assignments.clone();
+        Self::find_setup_call(body, &mut locals, functions, imports)
+    }
+
+    /// Binds the names imported from a local module, e.g., `NAME` in
+    /// `from spam.meta import NAME`, to their values in that module. The
+    /// module is walked like setup.py, following its own imports in turn.
+    fn insert_local_imports(
+        import: &ast::StmtImportFrom,
+        assignments: &mut BTreeMap<String, ast::Expr>,
+        imports: &LocalImports,
+    ) {
+        if imports.depth == 0 {
+            return;
+        }
+        let level = import.level.as_ref().map_or(0, |level| level.to_u32()) as usize;
+        let mut module = if level == 0 {
+            Vec::new()
+        } else {
+            let Some(len) = (imports.package.len() + 1).checked_sub(level) else {
+                return;
             };
-            *idx += 1;
-            return Self::get_setup_call(statements, idx, assignments);
+            imports.package[..len].to_vec()
+        };
+        if let Some(name) = &import.module {
+            module.extend(name.split('.').map(|part| part.to_string()));
+        }
+        let Some(path) = DynamicVersion::find_module(imports.root, &module.join(".")) else {
+            return;
+        };
+        let Ok(contents) = fs::read_to_string(imports.root.join(&path)) else {
+            return;
+        };
+        let Ok(statements) = ast::Suite::parse(&contents, &path) else {
+            return;
+        };
+        let package = if path.ends_with("__init__.py") {
+            module
+        } else {
+            module[..module.len() - 1].to_vec()
+        };
+        let mut module_assignments = BTreeMap::<String, ast::Expr>::new();
+        let module_imports = LocalImports {
+            root: imports.root,
+            package,
+            depth: imports.depth - 1,
+        };
+        // Any `setup()` call of the module is beside the point.
+        let _ = Self::find_setup_call(
+            &statements,
+            &mut module_assignments,
+            &mut Vec::new(),
+            &module_imports,
+        );
+        let evaluator = Evaluator::new(&module_assignments);
+        let mut bind = |name: &str, value: &ast::Expr| {
+            // Only values which fold are bound, as others refer to the module's names.
+            if let Some(value) = evaluator.evaluate(value).ok().and_then(|v| v.to_expr()) {
+                assignments.insert(name.to_string(), value);
+            }
+        };
+        for alias in import.names.iter() {
+            if alias.name.as_str() == "*" {
+                for (name, value) in module_assignments.iter() {
+                    if !name.starts_with('_') {
+                        bind(name, value);
+                    }
+                }
+            } else if let Some(value) = module_assignments.get(alias.name.as_str()) {
+                bind(alias.asname.as_ref().unwrap_or(&alias.name).as_str(), value);
+            }
+        }
+    }
+
+    fn as_setup_call(expr: &ast::Expr) -> Option<&ast::ExprCall> {
+        let ast::Expr::Call(call) = expr else {
+            return None;
+        };
+        let is_setup = match call.func.as_ref() {
+            ast::Expr::Name(n) => "setup" == n.id.as_str(),
+            ast::Expr::Attribute(a) => "setup" == a.attr.as_str(),
+            _ => false,
+        };
+        is_setup.then_some(call)
+    }
+
+    /// Returns the keyword arguments of the `setup()` call, merging in those
+    /// unpacked from dicts, e.g., `setup(**config)`.
+    fn get_setup_kwargs(
+        setup: &ast::ExprCall,
+        assignments: &BTreeMap<String, ast::Expr>,
+    ) -> Result<Vec<ast::Keyword>> {
+        let mut kwargs = Vec::<ast::Keyword>::new();
+        for keyword in setup.keywords.iter() {
+            match &keyword.arg {
+                Some(_) => Self::insert_kwarg(&mut kwargs, keyword.clone()),
+                None => Self::unpack_kwargs(&keyword.value, assignments, &mut kwargs, 0)?,
+            }
+        }
+        Ok(kwargs)
+    }
+
+    fn unpack_kwargs(
+        expr: &ast::Expr,
+        assignments: &BTreeMap<String, ast::Expr>,
+        kwargs: &mut Vec<ast::Keyword>,
+        depth: usize,
+    ) -> Result<()> {
+        let error = || {
+            Error::UnsupportedExpression(format!(
+                "Failed to parse keyword arguments from Expr:\n{expr:#?}"
+            ))
+        };
+        // Names are followed at most once each, so that `a = dict(**a)` ends.
+        if depth > assignments.len() {
+            return Err(error());
+        }
+        let keyword = |arg: &str, value: &ast::Expr| ast::Keyword {
+            range: Default::default(),
+            arg: Some(ast::Identifier::new(arg)),
+            value: value.clone(),
+        };
+        match expr {
+            ast::Expr::Name(name) => match assignments.get(name.id.as_str()) {
+                Some(value) => Self::unpack_kwargs(value, assignments, kwargs, depth + 1),
+                None => Err(error()),
+            },
+            ast::Expr::Dict(dict) => {
+                for (key, value) in dict.keys.iter().zip(dict.values.iter()) {
+                    match key {
+                        Some(key) => Self::insert_kwarg(
+                            kwargs,
+                            keyword(&Self::parse_string(key, assignments)?, value),
+                        ),
+                        None => Self::unpack_kwargs(value, assignments, kwargs, depth)?,
+                    }
+                }
+                Ok(())
+            }
+            // `dict(base, key=value, **other)`
+            ast::Expr::Call(call) if matches!(call.func.as_ref(), ast::Expr::Name(name) if name.id.as_str() == "dict") =>
+            {
+                for arg in call.args.iter() {
+                    Self::unpack_kwargs(arg, assignments, kwargs, depth)?;
+                }
+                for kw in call.keywords.iter() {
+                    match &kw.arg {
+                        Some(arg) => Self::insert_kwarg(kwargs, keyword(arg.as_str(), &kw.value)),
+                        None => Self::unpack_kwargs(&kw.value, assignments, kwargs, depth)?,
+                    }
+                }
+                Ok(())
+            }
+            _ => Err(error()),
+        }
+    }
+
+    /// Adds a keyword argument, replacing any given earlier under the same name.
+    fn insert_kwarg(kwargs: &mut Vec<ast::Keyword>, keyword: ast::Keyword) {
+        kwargs.retain(|kwarg| kwarg.arg != keyword.arg);
+        kwargs.push(keyword);
+    }
+}
+
+impl IdentValueMap for BTreeMap<String, ast::Expr> {
+    fn insert_assignments(&mut self, assignment: PyAssignment) -> &mut Self {
+        match assignment {
+            PyAssignment::Unannotated(assignment) => {
+                // `a = b = value` binds the same value to each target.
+                let value = Evaluator::new(self)
+                    .evaluate(&assignment.value)
+                    .ok()
+                    .and_then(|value| value.to_expr())
+                    .unwrap_or_else(|| inline_file_names(&assignment.value, self));
+                for target in assignment.targets.iter() {
+                    self.bind(target, &value);
+                }
+            }
+            PyAssignment::Annotated(assignment) => {
+                if let Some(value) = &assignment.value {
+                    self.bind(&assignment.target, value);
+                }
+            }
+        }
+        self
+    }
+
+    fn insert_aug_assignment(&mut self, assignment: &ast::StmtAugAssign) -> &mut Self {
+        let value = ast::Expr::BinOp(ast::ExprBinOp {
+            range: Default::default(),
+            left: assignment.target.clone(),
+            op: assignment.op,
+            right: assignment.value.clone(),
+        });
+        match Evaluator::new(self).evaluate(&value) {
+            Ok(_) => self.bind(&assignment.target, &value),
+            // The value can no longer be known.
+            Err(_) => self.remove_targets(&assignment.target),
+        }
+    }
+
+    fn insert_mutation(&mut self, expr: &ast::Expr) -> &mut Self {
+        let ast::Expr::Call(call) = expr else {
+            return self;
+        };
+        let ast::Expr::Attribute(method) = call.func.as_ref() else {
+            return self;
+        };
+        match fold_mutation(self, call, method) {
+            Some(value) => self.bind(&method.value, &value),
+            // The value can no longer be known, e.g., after `REQUIRES.pop()`.
+            None => self.remove_targets(&method.value),
+        }
+    }
+
+    fn remove_targets(&mut self, target: &ast::Expr) -> &mut Self {
+        match target {
+            ast::Expr::Name(name) => {
+                self.remove(name.id.as_str());
+            }
+            ast::Expr::Tuple(ast::ExprTuple { elts, .. })
+            | ast::Expr::List(ast::ExprList { elts, .. }) => {
+                for elt in elts.iter() {
+                    self.remove_targets(elt);
+                }
+            }
+            ast::Expr::Starred(starred) => {
+                self.remove_targets(&starred.value);
+            }
+            // A mutated list or dict can no longer be known.
+            ast::Expr::Subscript(subscript) => {
+                self.remove_targets(&subscript.value);
+            }
+            _ => (),
+        }
+        self
+    }
+
+    /// Binds a value, as folded where it is bound, to a name, to each name of
+    /// an unpacked tuple (e.g., `a, *rest = ...`), or to an item of a bound
+    /// list or dict. Attributes of other objects are not tracked.
+    fn bind(&mut self, target: &ast::Expr, value: &ast::Expr) -> &mut Self {
+        let folded = Evaluator::new(self).evaluate(value);
+        match target {
+            ast::Expr::Name(name) => {
+                let value = match folded.ok().and_then(|value| value.to_expr()) {
+                    Some(value) => value,
+                    None => value.clone(),
+                };
+                self.insert(name.id.to_string(), value);
+            }
+            ast::Expr::Tuple(ast::ExprTuple { elts, .. })
+            | ast::Expr::List(ast::ExprList { elts, .. }) => {
+                let values = match (folded, value) {
+                    (Ok(PyValue::List(values)), _) => values
+                        .iter()
+                        .map(|value| value.to_expr())
+                        .collect::<Option<Vec<_>>>(),
+                    // Values which cannot be folded, e.g., `a, b = open(p), "spam"`.
+                    (
+                        _,
+                        ast::Expr::Tuple(ast::ExprTuple { elts: values, .. })
+                        | ast::Expr::List(ast::ExprList { elts: values, .. }),
+                    ) if !values
+                        .iter()
+                        .any(|value| matches!(value, ast::Expr::Starred(_))) =>
+                    {
+                        Some(values.clone())
+                    }
+                    _ => None,
+                };
+                let starred = elts
+                    .iter()
+                    .position(|elt| matches!(elt, ast::Expr::Starred(_)));
+                match (values, starred) {
+                    (Some(values), None) if values.len() == elts.len() => {
+                        for (elt, value) in elts.iter().zip(values.iter()) {
+                            self.bind(elt, value);
+                        }
+                    }
+                    (Some(values), Some(i)) if values.len() + 1 >= elts.len() => {
+                        let rest = values.len() + 1 - elts.len();
+                        for (elt, value) in elts[..i].iter().zip(values.iter()) {
+                            self.bind(elt, value);
+                        }
+                        let rest_values = values[i..i + rest].to_vec();
+                        if let ast::Expr::Starred(starred) = &elts[i] {
+                            let rest = ast::Expr::List(ast::ExprList {
+                                range: Default::default(),
+                                elts: rest_values,
+                                ctx: ast::ExprContext::Load,
+                            });
+                            self.bind(&starred.value, &rest);
+                        }
+                        for (elt, value) in elts[i + 1..].iter().zip(values[i + rest..].iter()) {
+                            self.bind(elt, value);
+                        }
+                    }
+                    _ => {
+                        self.remove_targets(target);
+                    }
+                }
+            }
+            // e.g., `EXTRAS["all"] = [...]`
+            ast::Expr::Subscript(subscript) => {
+                let evaluator = Evaluator::new(self);
+                let updated = match (
+                    evaluator.evaluate(&subscript.value),
+                    evaluator.evaluate(&subscript.slice),
+                    folded,
+                ) {
+                    (Ok(PyValue::Dict(mut items)), Ok(key), Ok(value)) => {
+                        match items.iter_mut().find(|(k, _)| *k == key) {
+                            Some(item) => item.1 = value,
+                            None => items.push((key, value)),
+                        }
+                        PyValue::Dict(items).to_expr()
+                    }
+                    (Ok(PyValue::List(mut values)), Ok(PyValue::Int(i)), Ok(value)) => {
+                        let i = if i < 0 { values.len() as i64 + i } else { i };
+                        match usize::try_from(i).ok().and_then(|i| values.get_mut(i)) {
+                            Some(item) => {
+                                *item = value;
+                                PyValue::List(values).to_expr()
+                            }
+                            None => None,
+                        }
+                    }
+                    _ => None,
+                };
+                match updated {
+                    Some(updated) => {
+                        self.bind(&subscript.value, &updated);
+                    }
+                    None => {
+                        self.remove_targets(target);
+                    }
+                }
+            }
+            _ => (),
         }
-        return Ok(None);
+        self
     }
 }
 
-impl IdentValueMap for BTreeMap<String, ast::Expr> {
-    fn insert_assignments(&mut self, assignment: PyAssignment) -> PyResult<&mut Self> {
-        match assignment {
-            PyAssignment::Unannotated(assignment) => {
-                let mut identifiers = Vec::<String>::new();
-                for target in assignment.targets.iter() {
-                    identifiers.push(Self::parse_ident(target)?);
-                }
-                for identifier in identifiers {
-                    self.insert(identifier, *assignment.value.clone());
-                }
+/// Inlines the calls bound to the names read from, e.g., `f` in `f.read()` or
+/// `[line for line in f]` after `with open(path) as f:`, so that binding the
+/// name again later does not change which file is read.
+fn inline_file_names(expr: &ast::Expr, assignments: &BTreeMap<String, ast::Expr>) -> ast::Expr {
+    let inline = |expr: &ast::Expr| match expr {
+        ast::Expr::Name(name) => match assignments.get(name.id.as_str()) {
+            Some(value @ ast::Expr::Call(_)) => value.clone(),
+            _ => expr.clone(),
+        },
+        expr => inline_file_names(expr, assignments),
+    };
+    let mut expr = expr.clone();
+    match &mut expr {
+        ast::Expr::Call(call) => {
+            if let ast::Expr::Attribute(method) = call.func.as_mut() {
+                method.value = Box::new(inline(&method.value));
             }
-            PyAssignment::Annotated(assignment) => {
-                let target = &assignment.target;
-                if let Some(value) = &assignment.value {
-                    self.insert(Self::parse_ident(target)?, *value.clone());
-                }
+        }
+        ast::Expr::ListComp(comp) => {
+            for generator in comp.generators.iter_mut() {
+                generator.iter = inline(&generator.iter);
             }
         }
-        Ok(self)
+        _ => (),
+    }
+    expr
+}
+
+/// Folds the value of a list or dict after a call mutating it in place, if the
+/// method and all of its arguments are known.
+fn fold_mutation(
+    assignments: &BTreeMap<String, ast::Expr>,
+    call: &ast::ExprCall,
+    method: &ast::ExprAttribute,
+) -> Option<ast::Expr> {
+    let evaluator = Evaluator::new(assignments);
+    let receiver = evaluator.evaluate(&method.value).ok()?;
+    let mut args = Vec::new();
+    for arg in call.args.iter() {
+        args.push(evaluator.evaluate(arg).ok()?);
     }
+    let mut kwargs = Vec::new();
+    for keyword in call.keywords.iter() {
+        let arg = keyword.arg.as_ref()?;
+        kwargs.push((
+            PyValue::Str(arg.to_string()),
+            evaluator.evaluate(&keyword.value).ok()?,
+        ));
+    }
+    let mutated = match (receiver, method.attr.as_str(), args.as_slice()) {
+        (PyValue::List(mut values), "append", [value]) => {
+            values.push(value.clone());
+            PyValue::List(values)
+        }
+        (PyValue::List(mut values), "extend", [PyValue::List(more)]) => {
+            values.extend(more.iter().cloned());
+            PyValue::List(values)
+        }
+        (PyValue::List(mut values), "insert", [PyValue::Int(i), value]) => {
+            let i = if *i < 0 {
+                (values.len() as i64 + i).max(0)
+            } else {
+                *i
+            };
+            values.insert((i as usize).min(values.len()), value.clone());
+            PyValue::List(values)
+        }
+        (PyValue::Dict(mut items), "update", args) => {
+            let others = match args {
+                [] => Vec::new(),
+                [PyValue::Dict(other)] => other.clone(),
+                _ => return None,
+            };
+            for (key, value) in others.into_iter().chain(kwargs) {
+                match items.iter_mut().find(|(k, _)| *k == key) {
+                    Some(item) => item.1 = value,
+                    None => items.push((key, value)),
+                }
+            }
+            PyValue::Dict(items)
+        }
+        _ => return None,
+    };
+    mutated.to_expr()
 }
 
 #[cfg(test)]
@@ -342,6 +2602,186 @@ mod test {
         );
     }
 
+    #[test]
+    fn make_requirements_with_references() {
+        let r = RequirementsParser::from_str(
+            "-e .\nspam @ https://example.org/spam-1.0.tar.gz \\\n    --hash=sha256:aa\n",
+        )
+        .unwrap();
+        assert_eq!(
+            r.requires,
+            vec![
+                "-e .".to_string(),
+                "spam @ https://example.org/spam-1.0.tar.gz".to_string()
+            ]
+        );
+        assert_eq!(r.hashes.get("spam"), Some(&vec!["sha256:aa".to_string()]));
+    }
+
+    #[test]
+    fn make_compiled_requirements() {
+        let curr_dir = env::current_dir().unwrap();
+        let path_str = format!(
+            "{}/tests/inputs/requirements_compiled.txt",
+            curr_dir.to_str().unwrap()
+        );
+        let path = Path::new(&path_str);
+        let r = RequirementsParser::from_file(&path).unwrap();
+        assert_eq!(
+            r.requires,
+            vec![
+                "blinker==1.7.0".to_string(),
+                "click==8.1.7".to_string(),
+                "flask==3.0.2".to_string(),
+                "itsdangerous==2.1.2".to_string(),
+            ]
+        );
+        assert_eq!(
+            r.via.get("click==8.1.7"),
+            Some(&vec!["flask".to_string(), "-r requirements.in".to_string()])
+        );
+        assert_eq!(
+            r.via.get("flask==3.0.2"),
+            Some(&vec!["-r requirements.in".to_string()])
+        );
+        assert_eq!(
+            r.hashes.get("flask"),
+            Some(&vec![
+                "sha256:822c03f976b2217ed1ac8c9ee90c2e5f7fb32375cd8bc54e8bba6aad4e0a8ed9"
+                    .to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn make_metadata() {
+        let curr_dir = env::current_dir().unwrap();
+        let path_str = format!("{}/tests/inputs/PKG-INFO", curr_dir.to_str().unwrap());
+        let path = Path::new(&path_str);
+        let m = MetadataParser::from_file(&path).unwrap();
+        assert_eq!(m.name, Some("spam-eggs".to_string()));
+        assert_eq!(m.version, Some("2020.0.0".to_string()));
+        assert_eq!(m.requires_python, Some(">=3.8".to_string()));
+        assert_eq!(m.provides_extra, vec!["cli".to_string(), "gui".to_string()]);
+        assert_eq!(
+            m.project_urls.get("Bug Tracker"),
+            Some(&"https://github.com/me/spam/issues".to_string())
+        );
+        assert_eq!(
+            m.description,
+            Some("# spam-eggs\n\nLovely Spam! Wonderful Spam!".to_string())
+        );
+        let p = PyProject::from_metadata(m);
+        let project = p.project.unwrap();
+        assert_eq!(
+            project.dependencies,
+            Some(vec![
+                "httpx".to_string(),
+                "django>2.1; os_name != 'nt'".to_string(),
+            ])
+        );
+        assert_eq!(
+            project.optional_dependencies,
+            Some(BTreeMap::from([
+                (
+                    "cli".to_string(),
+                    vec!["rich".to_string(), "click; os_name != 'nt'".to_string()]
+                ),
+                ("gui".to_string(), vec!["PyQt5".to_string()]),
+            ]))
+        );
+    }
+
+    #[test]
+    fn make_wheel() {
+        let curr_dir = env::current_dir().unwrap();
+        let path_str = format!(
+            "{}/tests/inputs/spam_eggs-2020.0.0-py3-none-any.whl",
+            curr_dir.to_str().unwrap()
+        );
+        let path = Path::new(&path_str);
+        let m = DistributionParser::from_file(&path).unwrap();
+        assert_eq!(m.name, Some("spam-eggs".to_string()));
+        assert_eq!(
+            m.requires_dist,
+            vec!["httpx".to_string(), "rich; extra == \"cli\"".to_string()]
+        );
+        let entry_points = m.entry_points.unwrap();
+        assert_eq!(
+            entry_points.console_scripts,
+            Some(vec!["spam-cli = spam:main_cli".to_string()])
+        );
+        assert_eq!(
+            entry_points.gui_scripts,
+            Some(vec!["spam-gui = spam:main_gui".to_string()])
+        );
+    }
+
+    #[test]
+    fn make_sdist() {
+        let curr_dir = env::current_dir().unwrap();
+        let path_str = format!(
+            "{}/tests/inputs/spam_eggs-2020.0.0.tar.gz",
+            curr_dir.to_str().unwrap()
+        );
+        let path = Path::new(&path_str);
+        let m = DistributionParser::from_file(&path).unwrap();
+        assert_eq!(m.name, Some("spam-eggs".to_string()));
+        assert_eq!(m.version, Some("2020.0.0".to_string()));
+        assert_eq!(
+            m.entry_points.unwrap().console_scripts,
+            Some(vec!["spam-cli = spam:main_cli".to_string()])
+        );
+    }
+
+    #[test]
+    fn make_site_packages() {
+        let curr_dir = env::current_dir().unwrap();
+        let path_str = format!("{}/tests/inputs/venv", curr_dir.to_str().unwrap());
+        let path = Path::new(&path_str);
+        let e = SitePackagesParser::from_file(&path).unwrap();
+        assert_eq!(e.python_version, Some("3.11.4".to_string()));
+        assert_eq!(e.distributions.len(), 10);
+        let source_of = |name: &str| {
+            e.distributions
+                .iter()
+                .find(|d| d.metadata.name.as_deref() == Some(name))
+                .unwrap()
+                .source
+                .as_ref()
+        };
+        assert_eq!(
+            source_of("spam-eggs"),
+            Some(&PackageSource::Editable(
+                "file:///home/me/spam-eggs".to_string()
+            ))
+        );
+        assert_eq!(
+            source_of("six"),
+            Some(&PackageSource::Vcs {
+                url: "git+https://github.com/benjaminp/six.git".to_string(),
+                commit: Some("65486e4383f9f411da95937451205d3c7b61b9e1".to_string()),
+            })
+        );
+        assert_eq!(source_of("Flask"), None);
+    }
+
+    #[test]
+    fn detect_environments() {
+        let curr_dir = env::current_dir().unwrap();
+        let inputs = curr_dir.join("tests/inputs");
+        assert!(SitePackagesParser::is_environment(&inputs.join("venv")));
+        assert!(SitePackagesParser::is_environment(
+            &inputs.join("venv/lib/python3.11/site-packages")
+        ));
+        assert!(!SitePackagesParser::is_environment(
+            &inputs.join("layout_project")
+        ));
+        assert!(!SitePackagesParser::is_environment(
+            &inputs.join("setup.py")
+        ));
+    }
+
     #[test]
     fn make_setuppy() {
         let curr_dir = env::current_dir().unwrap();
@@ -370,7 +2810,7 @@ mod test {
         );
         assert_eq!(
             s.entry_points.as_ref().unwrap().console_scripts,
-            Some(vec!["hello-world = timmins:hello_world".to_string()])
+            Some(vec!["'hello-world' = timmins:hello_world".to_string()])
         );
         assert_eq!(
             s.entry_points.as_ref().unwrap().gui_scripts,
@@ -403,4 +2843,445 @@ mod test {
             ])
         );
     }
+
+    #[test]
+    fn make_conda_environment() {
+        let curr_dir = env::current_dir().unwrap();
+        let path_str = format!(
+            "{}/tests/inputs/environment.yml",
+            curr_dir.to_str().unwrap()
+        );
+        let path = Path::new(&path_str);
+        let e = CondaEnvironmentParser::from_file(&path).unwrap();
+        assert_eq!(&e.name, &Some("spam-eggs".to_string()));
+        let (requires, untranslatable) = e.partition();
+        assert_eq!(
+            requires,
+            vec![
+                "numpy==1.26.*".to_string(),
+                "pandas>=2.0,<3".to_string(),
+                "scipy".to_string(),
+                "requests>=2".to_string(),
+                "flask".to_string(),
+                "pydantic==2.6.2".to_string(),
+            ]
+        );
+        assert_eq!(
+            untranslatable
+                .iter()
+                .map(|u| u.entry.as_str())
+                .collect::<Vec<&str>>(),
+            vec![
+                "channels: conda-forge",
+                "python=3.11",
+                "pip",
+                "conda-forge::libgdal",
+                "cudatoolkit=11.8=h4ba93d1_12",
+            ]
+        );
+    }
+
+    #[test]
+    fn make_uv_lock() {
+        let curr_dir = env::current_dir().unwrap();
+        let path_str = format!("{}/tests/inputs/uv.lock", curr_dir.to_str().unwrap());
+        let path = Path::new(&path_str);
+        let l = UvLockParser::from_file(&path).unwrap();
+        assert_eq!(l.packages.len(), 5);
+        assert_eq!(l.packages[0].name, "anyio".to_string());
+        assert_eq!(l.packages[0].marker, None);
+        assert_eq!(l.packages[0].hashes.len(), 2);
+        assert_eq!(
+            l.packages[1].marker,
+            Some("python_full_version < '3.11'".to_string())
+        );
+        assert_eq!(
+            l.packages[2].source,
+            Some(PackageSource::Vcs {
+                url: "git+https://github.com/encode/httpx".to_string(),
+                commit: Some("5a2a2a5b6c5bcf0d2b8e1b3e3bb4d3b37c6b1b8d".to_string()),
+            })
+        );
+        assert_eq!(
+            l.packages[4].source,
+            Some(PackageSource::Editable(".".to_string()))
+        );
+    }
+
+    #[test]
+    fn make_uv_lock_transitive() {
+        let contents = r#"
+[[package]]
+name = "spam"
+version = "0.1.0"
+source = { virtual = "." }
+dependencies = [{ name = "anyio" }, { name = "exceptiongroup", marker = "python_version < '3.11'" }]
+
+[[package]]
+name = "anyio"
+version = "4.3.0"
+source = { registry = "https://pypi.org/simple" }
+sdist = { url = "https://example.org/anyio-4.3.0.tar.gz", hash = "sha256:aa" }
+
+[[package]]
+name = "exceptiongroup"
+version = "1.2.2"
+source = { registry = "https://pypi.org/simple" }
+dependencies = [{ name = "typing-extensions", marker = "python_version < '3.13'" }]
+sdist = { url = "https://example.org/exceptiongroup-1.2.2.tar.gz", hash = "sha256:bb" }
+
+[[package]]
+name = "typing-extensions"
+version = "4.12.2"
+source = { registry = "https://pypi.org/simple" }
+dependencies = [{ name = "exceptiongroup" }]
+wheels = [{ url = "https://example.org/typing_extensions-4.12.2-py3-none-any.whl", hash = "sha256:cc" }]
+"#;
+        let l = UvLockParser::from_str(contents).unwrap();
+        assert!(l.unhashed_packages().is_empty());
+        assert_eq!(
+            l.packages[3].marker,
+            Some("python_version < '3.11' and python_version < '3.13'".to_string())
+        );
+
+        let r = Requirements::from_lockfile(l);
+        assert_eq!(
+            r.requires,
+            vec![
+                "anyio==4.3.0".to_string(),
+                "exceptiongroup==1.2.2 ; python_version < '3.11'".to_string(),
+                "typing-extensions==4.12.2 ; python_version < '3.11' and python_version < '3.13'"
+                    .to_string(),
+            ]
+        );
+        assert_eq!(
+            r.hashes.get("typing-extensions"),
+            Some(&vec!["sha256:cc".to_string()])
+        );
+
+        // Hashes are left out altogether once a package has none.
+        let l =
+            UvLockParser::from_str(&contents.replace("hash = \"sha256:aa\"", "size = 1")).unwrap();
+        assert_eq!(l.unhashed_packages(), vec!["anyio"]);
+        assert!(Requirements::from_lockfile(l).hashes.is_empty());
+    }
+
+    #[test]
+    fn make_uv_lock_extras() {
+        let contents = r#"
+[[package]]
+name = "spam"
+version = "0.1.0"
+source = { virtual = "." }
+dependencies = [{ name = "httpx", extra = ["http2"], marker = "sys_platform == 'linux'" }]
+
+[package.optional-dependencies]
+socks = [{ name = "socksio" }]
+
+[package.dev-dependencies]
+dev = [{ name = "pytest" }]
+
+[[package]]
+name = "httpx"
+version = "0.27.0"
+source = { registry = "https://pypi.org/simple" }
+
+[package.optional-dependencies]
+http2 = [{ name = "h2" }]
+brotli = [{ name = "brotli" }]
+
+[[package]]
+name = "h2"
+version = "4.1.0"
+source = { registry = "https://pypi.org/simple" }
+
+[[package]]
+name = "brotli"
+version = "1.1.0"
+source = { registry = "https://pypi.org/simple" }
+
+[[package]]
+name = "socksio"
+version = "1.0.0"
+source = { registry = "https://pypi.org/simple" }
+
+[[package]]
+name = "pytest"
+version = "8.1.1"
+source = { registry = "https://pypi.org/simple" }
+dependencies = [{ name = "iniconfig" }]
+
+[[package]]
+name = "iniconfig"
+version = "2.0.0"
+source = { registry = "https://pypi.org/simple" }
+"#;
+        // Only the extras asked for by a dependency are installed, not those or
+        // the dev dependencies of the project itself.
+        let r = Requirements::from_lockfile(UvLockParser::from_str(contents).unwrap());
+        assert_eq!(
+            r.requires,
+            vec![
+                "httpx==0.27.0 ; sys_platform == 'linux'".to_string(),
+                "h2==4.1.0 ; sys_platform == 'linux'".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn make_pylock() {
+        let curr_dir = env::current_dir().unwrap();
+        let path_str = format!("{}/tests/inputs/pylock.toml", curr_dir.to_str().unwrap());
+        let path = Path::new(&path_str);
+        let l = PyLockParser::from_file(&path).unwrap();
+        assert_eq!(l.packages.len(), 3);
+        assert_eq!(
+            l.packages[0].hashes,
+            vec![
+                "sha256:c75a69e28a550a7e93789579c22aa26b0f5b83b75dc4e08fe092980051e1090a"
+                    .to_string()
+            ]
+        );
+        assert_eq!(
+            l.packages[1].marker,
+            Some("sys_platform == 'win32'".to_string())
+        );
+        assert_eq!(
+            l.packages[2].source,
+            Some(PackageSource::Vcs {
+                url: "git+https://github.com/me/spam.git".to_string(),
+                commit: Some("4b5d7e7c8a0f6d2b3c4e5f60718293a4b5c6d7e8".to_string()),
+            })
+        );
+    }
+
+    #[test]
+    fn resolve_dynamic_versions() {
+        let curr_dir = env::current_dir().unwrap();
+        let root = format!(
+            "{}/tests/inputs/dynamic_project",
+            curr_dir.to_str().unwrap()
+        );
+        let parse = |file: &str| SetupParser::from_file(&Path::new(&root).join(file)).unwrap();
+        assert_eq!(parse("setup.py").version, Some("1.4.2".to_string()));
+        assert_eq!(parse("setup_file.py").version, Some("2.0.1".to_string()));
+        assert_eq!(parse("setup_regex.py").version, Some("1.4.2".to_string()));
+        let s = parse("setup_unresolved.py");
+        assert_eq!(s.version, None);
+        assert_eq!(
+            s.dynamic_version,
+            Some(DynamicVersion::File("MISSING".to_string()))
+        );
+        // Contents which are not of a file resolve nothing from the filesystem.
+        let contents = utils::read_file(&Path::new(&root).join("setup.py")).unwrap();
+        let s = SetupParser::from_str(&contents).unwrap();
+        assert_eq!(s.version, None);
+        assert_eq!(
+            s.dynamic_version,
+            Some(DynamicVersion::Attribute {
+                attr: "spam.__version__".to_string(),
+                path: None
+            })
+        );
+        // The version is not guessed from the package when its source is unknown.
+        let contents =
+            "from setuptools import setup\nsetup(name=\"spam\", version=get_version())\n";
+        assert!(SetupParser::parse(contents, Some(&Path::new(&root).join("setup.py")), 0).is_err());
+
+        let p = PyProjectParser::from_file(&Path::new(&root).join("pyproject.toml")).unwrap();
+        let project = p.project.unwrap();
+        assert_eq!(project.version, Some("1.4.2".to_string()));
+        assert_eq!(project.dynamic, None);
+        let p = PyProjectParser::from_file(&Path::new(&root).join("pyproject_unresolved.toml"))
+            .unwrap();
+        assert_eq!(p.project.as_ref().unwrap().version, None);
+        assert_eq!(
+            p.dynamic_version(),
+            Some(DynamicVersion::Attribute {
+                attr: "eggs.__version__".to_string(),
+                path: None
+            })
+        );
+    }
+
+    #[test]
+    fn resolve_file_reads() {
+        let curr_dir = env::current_dir().unwrap();
+        let root = format!("{}/tests/inputs/readme_project", curr_dir.to_str().unwrap());
+        for file in ["setup.py", "setup_comprehension.py", "setup_with.py"] {
+            let s = SetupParser::from_file(&Path::new(&root).join(file)).unwrap();
+            assert_eq!(s.readme.as_ref().unwrap().file(), Some("README.rst"));
+            assert_eq!(
+                s.readme.as_ref().unwrap().content_type(),
+                Some("text/x-rst".to_string())
+            );
+            assert_eq!(s.requirements_file, Some("requirements.txt".to_string()));
+            assert_eq!(
+                s.install_requires,
+                Some(vec![
+                    "requests>=2.31".to_string(),
+                    "click".to_string(),
+                    "PyYAML>=6.0".to_string(),
+                ])
+            );
+        }
+
+        // `-r` lines are followed and other options left out, so the
+        // requirements are listed rather than read from the file.
+        let s = SetupParser::from_file(&Path::new(&root).join("setup_options.py")).unwrap();
+        assert_eq!(s.requirements_file, None);
+        assert_eq!(
+            s.install_requires,
+            Some(vec![
+                "requests>=2.31".to_string(),
+                "click".to_string(),
+                "PyYAML>=6.0".to_string(),
+                "pytest>=8".to_string(),
+            ])
+        );
+    }
+
+    #[test]
+    fn fold_setuppy_expressions() {
+        let curr_dir = env::current_dir().unwrap();
+        let path_str = format!(
+            "{}/tests/inputs/setup_expressions.py",
+            curr_dir.to_str().unwrap()
+        );
+        let s = SetupParser::from_file(&Path::new(&path_str)).unwrap();
+        assert_eq!(s.version, Some("1.4.2".to_string()));
+        assert_eq!(s.description, Some("spam-eggs does spam".to_string()));
+        assert_eq!(
+            s.install_requires,
+            Some(vec![
+                "requests>=2.31".to_string(),
+                "click".to_string(),
+                "pyyaml>=6.0".to_string(),
+            ])
+        );
+        assert_eq!(
+            s.extra_requires.unwrap().get("all"),
+            Some(&vec!["pytest".to_string(), "sphinx".to_string()])
+        );
+        assert_eq!(
+            s.project_urls.unwrap().get("Source"),
+            Some(&"https://github.com/me/spam-eggs".to_string())
+        );
+        assert_eq!(
+            s.entry_points.unwrap().console_scripts,
+            Some(vec![
+                "spam = spam_eggs.cli:spam".to_string(),
+                "eggs-cli = spam_eggs.cli:eggs_cli".to_string(),
+            ])
+        );
+    }
+
+    #[test]
+    fn find_nested_setup_call() {
+        let curr_dir = env::current_dir().unwrap();
+        let path_str = format!("{}/tests/inputs/setup_main.py", curr_dir.to_str().unwrap());
+        let s = SetupParser::from_file(&Path::new(&path_str)).unwrap();
+        assert_eq!(s.package_name, Some("spam-eggs".to_string()));
+        assert_eq!(s.version, Some("1.0.0".to_string()));
+        assert_eq!(s.python_requires, Some(">=3.8".to_string()));
+        assert_eq!(s.install_requires, Some(vec!["requests>=2.31".to_string()]));
+        assert_eq!(
+            s.entry_points.unwrap().console_scripts,
+            Some(vec!["spam = spam_eggs:main".to_string()])
+        );
+
+        let path_str = format!("{}/tests/inputs/setup_try.py", curr_dir.to_str().unwrap());
+        let s = SetupParser::from_file(&Path::new(&path_str)).unwrap();
+        assert_eq!(
+            s.description,
+            Some("Lovely Spam! Wonderful Spam!".to_string())
+        );
+        assert_eq!(s.install_requires, Some(vec!["cython".to_string()]));
+    }
+
+    #[test]
+    fn track_assignments_in_order() {
+        let curr_dir = env::current_dir().unwrap();
+        let path_str = format!("{}/tests/inputs/setup_flow.py", curr_dir.to_str().unwrap());
+        let s = SetupParser::from_file(&Path::new(&path_str)).unwrap();
+        assert_eq!(s.package_name, Some("spam-eggs".to_string()));
+        assert_eq!(s.version, Some("1.0.0".to_string()));
+        assert_eq!(s.description, Some("Lovely Spam! (v1)".to_string()));
+        assert_eq!(
+            s.install_requires,
+            Some(vec![
+                "requests>=2.31".to_string(),
+                "click".to_string(),
+                "rich".to_string(),
+                "pyyaml".to_string(),
+                "tomli; python_version < '3.11'".to_string(),
+            ])
+        );
+        assert_eq!(
+            s.extra_requires,
+            Some(BTreeMap::from([
+                (
+                    "test".to_string(),
+                    vec!["pytest".to_string(), "hypothesis".to_string()]
+                ),
+                ("docs".to_string(), vec!["sphinx".to_string()]),
+            ]))
+        );
+        // Mutations which cannot be followed leave the value unknown.
+        for mutation in [
+            "REQUIRES.append(get_dep())",
+            "REQUIRES.pop()",
+            "REQUIRES.sort(**options)",
+            "for dep in get_deps():\n    REQUIRES.append(dep)",
+        ] {
+            let contents = format!(
+                "from setuptools import setup\nREQUIRES = [\"click\"]\n{}\nsetup(install_requires=REQUIRES)\n",
+                mutation
+            );
+            assert!(SetupParser::from_str(&contents).is_err());
+        }
+    }
+
+    #[test]
+    fn walk_only_branches_which_run() {
+        // The platform cannot be known, so neither can the requirements.
+        let contents = "from setuptools import setup\nimport sys\nREQUIRES = [\"click\"]\nif sys.platform == \"win32\":\n    REQUIRES.append(\"pywin32\")\nsetup(install_requires=REQUIRES)\n";
+        assert!(SetupParser::from_str(contents).is_err());
+
+        // Names bound alike in each branch are kept.
+        let contents = "from setuptools import setup\nimport sys\nif sys.platform == \"win32\":\n    NAME = \"spam\"\nelse:\n    NAME = \"spam\"\nsetup(name=NAME)\n";
+        let s = SetupParser::from_str(contents).unwrap();
+        assert_eq!(s.package_name, Some("spam".to_string()));
+
+        // The body of a constant-false `if` never runs.
+        let contents = "from setuptools import setup\nDEBUG = False\nREQUIRES = [\"click\"]\nif DEBUG:\n    REQUIRES.append(\"debugpy\")\nelif not DEBUG:\n    REQUIRES.append(\"rich\")\nif False:\n    REQUIRES = []\nsetup(install_requires=REQUIRES)\n";
+        let s = SetupParser::from_str(contents).unwrap();
+        assert_eq!(
+            s.install_requires,
+            Some(vec!["click".to_string(), "rich".to_string()])
+        );
+
+        // A name bound in a loop may hold any of its values.
+        let contents = "from setuptools import setup\nNAME = \"spam\"\nfor suffix in [\"-eggs\"]:\n    NAME = NAME + suffix\nsetup(name=NAME)\n";
+        assert!(SetupParser::from_str(contents).is_err());
+    }
+
+    #[test]
+    fn follow_local_imports() {
+        let curr_dir = env::current_dir().unwrap();
+        let path_str = format!(
+            "{}/tests/inputs/import_project/setup.py",
+            curr_dir.to_str().unwrap()
+        );
+        let s = SetupParser::from_file(&Path::new(&path_str)).unwrap();
+        assert_eq!(s.package_name, Some("spam".to_string()));
+        assert_eq!(s.version, Some("2.0.1".to_string()));
+        assert_eq!(
+            s.install_requires,
+            Some(vec!["requests>=2.31".to_string(), "click>=8".to_string()])
+        );
+
+        // `BASE_DEPENDENCIES` lies two modules deep.
+        assert!(SetupParser::from_file_with_import_depth(&Path::new(&path_str), 1).is_err());
+    }
 }
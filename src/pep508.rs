@@ -0,0 +1,642 @@
+//! Helpers for reading dependency specifiers (PEP 508), including their
+//! version specifiers (PEP 440) and environment markers.
+use std::cmp::Ordering;
+use std::fmt;
+
+use crate::error::Error;
+
+/// The parts of a dependency specifier, e.g., `"requests[socks]>=2.8; python_version < '3.8'"`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedRequirement {
+    pub name: String,
+    pub extras: Vec<String>,
+    /// A comma-separated set of version specifiers, e.g., `">=2.8,<3"`.
+    pub specifier: String,
+    pub url: Option<String>,
+    pub marker: Option<String>,
+}
+
+/// The values against which environment markers are evaluated.
+/// Variables left unset are unknown, and any comparison on them holds.
+#[derive(Debug, Clone, Default)]
+pub struct MarkerEnvironment {
+    pub python_version: Option<String>,
+    pub python_full_version: Option<String>,
+    pub os_name: Option<String>,
+    pub sys_platform: Option<String>,
+    pub platform_system: Option<String>,
+    pub platform_machine: Option<String>,
+    pub implementation_name: Option<String>,
+    pub platform_python_implementation: Option<String>,
+    pub extra: Option<String>,
+}
+
+/// A version as defined by PEP 440, ordered by its release, pre-, post- and
+/// dev-release segments. Local version labels are kept but not compared.
+#[derive(Debug, Clone)]
+pub struct Version {
+    epoch: u64,
+    release: Vec<u64>,
+    pre: Option<(u8, u64)>,
+    post: Option<u64>,
+    dev: Option<u64>,
+    local: Option<String>,
+}
+
+/// Normalizes a distribution name as specified by PEP 503, e.g., `"Flask_SQLAlchemy"`
+/// becomes `"flask-sqlalchemy"`.
+pub fn canonicalize_name(name: &str) -> String {
+    let mut canonical = String::with_capacity(name.len());
+    for c in name.trim().chars() {
+        if c == '-' || c == '_' || c == '.' {
+            if !canonical.ends_with('-') {
+                canonical.push('-');
+            }
+        } else {
+            canonical.push(c.to_ascii_lowercase());
+        }
+    }
+    canonical
+}
+
+/// Splits a requirement into its parts, returning `None` for entries which
+/// are not dependency specifiers, e.g., `"-e ."` or `"--index-url ..."`.
+pub fn parse_requirement(requirement: &str) -> Option<ParsedRequirement> {
+    let requirement = requirement.trim();
+    if requirement.is_empty() || requirement.starts_with('-') || requirement.starts_with('#') {
+        return None;
+    }
+    let (requirement, marker) = match requirement.split_once(';') {
+        Some((requirement, marker)) => (requirement.trim(), Some(marker.trim().to_string())),
+        None => (requirement, None),
+    };
+    let name_end = requirement
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.'))
+        .unwrap_or(requirement.len());
+    let name = &requirement[..name_end];
+    if name.is_empty() {
+        return None;
+    }
+    let mut rest = requirement[name_end..].trim_start();
+    let mut extras = Vec::<String>::new();
+    if rest.starts_with('[') {
+        let close = rest.find(']')?;
+        extras = rest[1..close]
+            .split(',')
+            .map(|extra| extra.trim().to_string())
+            .filter(|extra| !extra.is_empty())
+            .collect();
+        rest = rest[close + 1..].trim_start();
+    }
+    let (specifier, url) = if let Some(url) = rest.strip_prefix('@') {
+        (String::new(), Some(url.trim().to_string()))
+    } else {
+        let specifier = rest
+            .trim_start_matches('(')
+            .trim_end_matches(')')
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<String>();
+        (specifier, None)
+    };
+    Some(ParsedRequirement {
+        name: name.to_string(),
+        extras,
+        specifier,
+        url,
+        marker: marker.filter(|marker| !marker.is_empty()),
+    })
+}
+
+impl std::str::FromStr for ParsedRequirement {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_requirement(s)
+            .ok_or_else(|| Error::InvalidRequirement(format!("Invalid requirement: {}", s)))
+    }
+}
+
+impl fmt::Display for ParsedRequirement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        if !self.extras.is_empty() {
+            write!(f, "[{}]", self.extras.join(","))?;
+        }
+        match &self.url {
+            Some(url) => write!(f, " @ {}", url)?,
+            None => write!(f, "{}", self.specifier)?,
+        }
+        if let Some(marker) = &self.marker {
+            write!(f, "; {}", marker)?;
+        }
+        Ok(())
+    }
+}
+
+impl MarkerEnvironment {
+    /// An environment for the running platform, whose Python version is given
+    /// separately, e.g., as read from a virtual environment.
+    pub fn for_platform(python_full_version: Option<&str>) -> Self {
+        let (sys_platform, platform_system, os_name) = match std::env::consts::OS {
+            "windows" => ("win32", "Windows", "nt"),
+            "macos" => ("darwin", "Darwin", "posix"),
+            "linux" => ("linux", "Linux", "posix"),
+            other => (other, other, "posix"),
+        };
+        let python_version = python_full_version
+            .map(|version| version.split('.').take(2).collect::<Vec<&str>>().join("."));
+        Self {
+            python_version,
+            python_full_version: python_full_version.map(|version| version.to_string()),
+            os_name: Some(os_name.to_string()),
+            sys_platform: Some(sys_platform.to_string()),
+            platform_system: Some(platform_system.to_string()),
+            platform_machine: Some(std::env::consts::ARCH.to_string()),
+            implementation_name: Some("cpython".to_string()),
+            platform_python_implementation: Some("CPython".to_string()),
+            extra: None,
+        }
+    }
+
+    fn get(&self, variable: &str) -> Option<Option<&str>> {
+        let value = match variable {
+            "python_version" => &self.python_version,
+            "python_full_version" => &self.python_full_version,
+            "os_name" | "os.name" => &self.os_name,
+            "sys_platform" | "sys.platform" => &self.sys_platform,
+            "platform_system" | "platform.system" => &self.platform_system,
+            "platform_machine" | "platform.machine" => &self.platform_machine,
+            "implementation_name" => &self.implementation_name,
+            "platform_python_implementation" | "platform.python_implementation" => {
+                &self.platform_python_implementation
+            }
+            // Without an extra being requested, `extra` compares as an empty string.
+            "extra" => return Some(Some(self.extra.as_deref().unwrap_or(""))),
+            "implementation_version" | "platform_release" | "platform_version" => &None,
+            _ => return None,
+        };
+        Some(value.as_deref())
+    }
+}
+
+/// Evaluates an environment marker, e.g., `"python_version < '3.10' and os_name == 'nt'"`.
+/// Markers which cannot be parsed are considered to hold.
+pub fn evaluate_marker(marker: &str, environment: &MarkerEnvironment) -> bool {
+    let tokens = match tokenize_marker(marker) {
+        Some(tokens) => tokens,
+        None => return true,
+    };
+    let mut parser = MarkerParser {
+        tokens: &tokens,
+        position: 0,
+        environment,
+    };
+    match parser.parse_or() {
+        Some(result) if parser.position == tokens.len() => result,
+        _ => true,
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum MarkerToken {
+    Variable(String),
+    Literal(String),
+    Operator(String),
+    And,
+    Or,
+    Open,
+    Close,
+}
+
+fn tokenize_marker(marker: &str) -> Option<Vec<MarkerToken>> {
+    let chars: Vec<char> = marker.chars().collect();
+    let mut tokens = Vec::<MarkerToken>::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '(' {
+            tokens.push(MarkerToken::Open);
+            i += 1;
+        } else if c == ')' {
+            tokens.push(MarkerToken::Close);
+            i += 1;
+        } else if c == '"' || c == '\'' {
+            let end = chars[i + 1..].iter().position(|&q| q == c)? + i + 1;
+            tokens.push(MarkerToken::Literal(chars[i + 1..end].iter().collect()));
+            i = end + 1;
+        } else if "<>=!~".contains(c) {
+            let mut end = i;
+            while end < chars.len() && "<>=!~".contains(chars[end]) {
+                end += 1;
+            }
+            tokens.push(MarkerToken::Operator(chars[i..end].iter().collect()));
+            i = end;
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let mut end = i;
+            while end < chars.len()
+                && (chars[end].is_ascii_alphanumeric() || "_.".contains(chars[end]))
+            {
+                end += 1;
+            }
+            let word: String = chars[i..end].iter().collect();
+            i = end;
+            match word.as_str() {
+                "and" => tokens.push(MarkerToken::And),
+                "or" => tokens.push(MarkerToken::Or),
+                "in" => tokens.push(MarkerToken::Operator("in".to_string())),
+                "not" => {
+                    while i < chars.len() && chars[i].is_whitespace() {
+                        i += 1;
+                    }
+                    let is_in = chars[i..].starts_with(&['i', 'n'])
+                        && !chars.get(i + 2).is_some_and(|c| c.is_ascii_alphanumeric());
+                    if !is_in {
+                        return None;
+                    }
+                    i += 2;
+                    tokens.push(MarkerToken::Operator("not in".to_string()));
+                }
+                _ => tokens.push(MarkerToken::Variable(word)),
+            }
+        } else {
+            return None;
+        }
+    }
+    Some(tokens)
+}
+
+struct MarkerParser<'a> {
+    tokens: &'a [MarkerToken],
+    position: usize,
+    environment: &'a MarkerEnvironment,
+}
+
+impl MarkerParser<'_> {
+    fn parse_or(&mut self) -> Option<bool> {
+        let mut result = self.parse_and()?;
+        while self.tokens.get(self.position) == Some(&MarkerToken::Or) {
+            self.position += 1;
+            let rhs = self.parse_and()?;
+            result = result || rhs;
+        }
+        Some(result)
+    }
+
+    fn parse_and(&mut self) -> Option<bool> {
+        let mut result = self.parse_atom()?;
+        while self.tokens.get(self.position) == Some(&MarkerToken::And) {
+            self.position += 1;
+            let rhs = self.parse_atom()?;
+            result = result && rhs;
+        }
+        Some(result)
+    }
+
+    fn parse_atom(&mut self) -> Option<bool> {
+        if self.tokens.get(self.position) == Some(&MarkerToken::Open) {
+            self.position += 1;
+            let result = self.parse_or()?;
+            if self.tokens.get(self.position) != Some(&MarkerToken::Close) {
+                return None;
+            }
+            self.position += 1;
+            return Some(result);
+        }
+        let lhs = self.tokens.get(self.position)?;
+        let op = match self.tokens.get(self.position + 1)? {
+            MarkerToken::Operator(op) => op.as_str(),
+            _ => return None,
+        };
+        let rhs = self.tokens.get(self.position + 2)?;
+        self.position += 3;
+        let (variable, lhs, rhs) = match (lhs, rhs) {
+            (MarkerToken::Variable(variable), MarkerToken::Literal(literal)) => (
+                variable,
+                self.environment.get(variable)?,
+                Some(literal.as_str()),
+            ),
+            (MarkerToken::Literal(literal), MarkerToken::Variable(variable)) => (
+                variable,
+                Some(literal.as_str()),
+                self.environment.get(variable)?,
+            ),
+            _ => return None,
+        };
+        match (lhs, rhs) {
+            (Some(lhs), Some(rhs)) => Some(compare_marker_values(variable, lhs, op, rhs)),
+            _ => Some(true),
+        }
+    }
+}
+
+fn compare_marker_values(variable: &str, lhs: &str, op: &str, rhs: &str) -> bool {
+    if variable == "extra" {
+        let (lhs, rhs) = (canonicalize_name(lhs), canonicalize_name(rhs));
+        return match op {
+            "==" => lhs == rhs,
+            "!=" => lhs != rhs,
+            _ => false,
+        };
+    }
+    match op {
+        "in" => return rhs.contains(lhs),
+        "not in" => return !rhs.contains(lhs),
+        _ => (),
+    }
+    if let Ok(version) = lhs.parse::<Version>() {
+        if rhs.parse::<Version>().is_ok() {
+            return version.satisfies(&format!("{}{}", op, rhs));
+        }
+    }
+    match op {
+        "==" | "===" => lhs == rhs,
+        "!=" => lhs != rhs,
+        "<" => lhs < rhs,
+        "<=" => lhs <= rhs,
+        ">" => lhs > rhs,
+        ">=" => lhs >= rhs,
+        _ => false,
+    }
+}
+
+impl std::str::FromStr for Version {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid version: {}", s);
+        let s = s.trim().to_lowercase();
+        let s = s.strip_prefix('v').unwrap_or(&s);
+        let (s, local) = match s.split_once('+') {
+            Some((s, local)) => (s, Some(local.to_string())),
+            None => (s, None),
+        };
+        let (epoch, s) = match s.split_once('!') {
+            Some((epoch, s)) => (epoch.parse::<u64>().map_err(|_| invalid())?, s),
+            None => (0, s),
+        };
+        let release_end = s
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(s.len());
+        let release_str = s[..release_end].trim_end_matches('.');
+        if release_str.is_empty() {
+            return Err(invalid());
+        }
+        let mut release = Vec::<u64>::new();
+        for part in release_str.split('.') {
+            release.push(part.parse::<u64>().map_err(|_| invalid())?);
+        }
+        let mut version = Version {
+            epoch,
+            release,
+            pre: None,
+            post: None,
+            dev: None,
+            local,
+        };
+        let mut rest = &s[release_end..];
+        while !rest.is_empty() {
+            rest = rest.trim_start_matches(['.', '-', '_']);
+            let label_end = rest
+                .find(|c: char| !c.is_ascii_alphabetic())
+                .unwrap_or(rest.len());
+            let label = &rest[..label_end];
+            rest = rest[label_end..].trim_start_matches(['.', '-', '_']);
+            let number_end = rest
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len());
+            let number = rest[..number_end].parse::<u64>().unwrap_or(0);
+            rest = &rest[number_end..];
+            match label {
+                "a" | "alpha" => version.pre = Some((0, number)),
+                "b" | "beta" => version.pre = Some((1, number)),
+                "c" | "rc" | "pre" | "preview" => version.pre = Some((2, number)),
+                "post" | "rev" | "r" => version.post = Some(number),
+                "dev" => version.dev = Some(number),
+                // An implicit post-release, e.g., `1.0-1`.
+                "" if number_end > 0 => version.post = Some(number),
+                _ => return Err(invalid()),
+            }
+        }
+        Ok(version)
+    }
+}
+
+impl Version {
+    pub fn is_prerelease(&self) -> bool {
+        self.pre.is_some() || self.dev.is_some()
+    }
+
+    /// Whether the version matches a comma-separated set of specifiers, e.g., `">=1.0,!=1.3.*"`.
+    /// Pre-releases are matched like any other version, as is done for installed packages.
+    pub fn satisfies(&self, specifier: &str) -> bool {
+        specifier
+            .split(',')
+            .map(|clause| clause.trim())
+            .filter(|clause| !clause.is_empty())
+            .all(|clause| self.satisfies_clause(clause))
+    }
+
+    fn satisfies_clause(&self, clause: &str) -> bool {
+        let op_end = clause
+            .find(|c: char| !"<>=!~".contains(c))
+            .unwrap_or(clause.len());
+        let (op, target) = (&clause[..op_end], clause[op_end..].trim());
+        if op == "===" {
+            return self.to_string() == target;
+        }
+        if let Some(prefix) = target.strip_suffix(".*") {
+            let prefix = match prefix.parse::<Version>() {
+                Ok(prefix) => prefix,
+                Err(_) => return false,
+            };
+            let matches = self.epoch == prefix.epoch
+                && prefix
+                    .release
+                    .iter()
+                    .enumerate()
+                    .all(|(i, part)| self.release.get(i).copied().unwrap_or(0) == *part);
+            return match op {
+                "==" => matches,
+                "!=" => !matches,
+                _ => false,
+            };
+        }
+        let target = match target.parse::<Version>() {
+            Ok(target) => target,
+            Err(_) => return false,
+        };
+        match op {
+            "==" => self.cmp_public(&target, target.local.is_some()) == Ordering::Equal,
+            "!=" => self.cmp_public(&target, target.local.is_some()) != Ordering::Equal,
+            "<=" => self.cmp_public(&target, false) != Ordering::Greater,
+            ">=" => self.cmp_public(&target, false) != Ordering::Less,
+            // `<V` excludes pre-releases of V, and `>V` its post-releases, unless V is one.
+            "<" => {
+                self.cmp_public(&target, false) == Ordering::Less
+                    && (target.is_prerelease()
+                        || !self.is_prerelease()
+                        || self.base_release() != target.base_release())
+            }
+            ">" => {
+                self.cmp_public(&target, false) == Ordering::Greater
+                    && (target.post.is_some()
+                        || self.post.is_none()
+                        || self.base_release() != target.base_release())
+            }
+            "~=" => {
+                if target.release.len() < 2 {
+                    return false;
+                }
+                let prefix = &target.release[..target.release.len() - 1];
+                self.cmp_public(&target, false) != Ordering::Less
+                    && self.epoch == target.epoch
+                    && prefix
+                        .iter()
+                        .enumerate()
+                        .all(|(i, part)| self.release.get(i).copied().unwrap_or(0) == *part)
+            }
+            _ => false,
+        }
+    }
+
+    fn base_release(&self) -> (u64, Vec<u64>) {
+        let mut release = self.release.clone();
+        while release.len() > 1 && release.last() == Some(&0) {
+            release.pop();
+        }
+        (self.epoch, release)
+    }
+
+    fn cmp_public(&self, other: &Self, with_local: bool) -> Ordering {
+        // A dev-release without a pre-release sorts before any pre-release, and
+        // a final release after them all.
+        let pre_key = |v: &Version| match (v.pre, v.post, v.dev) {
+            (None, None, Some(_)) => (-1i64, 0u64),
+            (None, _, _) => (3, 0),
+            (Some((label, number)), _, _) => (label as i64, number),
+        };
+        let post_key = |v: &Version| v.post.map_or(-1i64, |post| post as i64);
+        let dev_key = |v: &Version| v.dev.unwrap_or(u64::MAX);
+        let ordering = self
+            .base_release()
+            .cmp(&other.base_release())
+            .then(pre_key(self).cmp(&pre_key(other)))
+            .then(post_key(self).cmp(&post_key(other)))
+            .then(dev_key(self).cmp(&dev_key(other)));
+        if with_local {
+            ordering.then(self.local.cmp(&other.local))
+        } else {
+            ordering
+        }
+    }
+}
+
+impl PartialEq for Version {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Version {}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.cmp_public(other, true)
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.epoch != 0 {
+            write!(f, "{}!", self.epoch)?;
+        }
+        let release: Vec<String> = self.release.iter().map(|part| part.to_string()).collect();
+        write!(f, "{}", release.join("."))?;
+        if let Some((label, number)) = self.pre {
+            write!(f, "{}{}", ["a", "b", "rc"][label as usize], number)?;
+        }
+        if let Some(post) = self.post {
+            write!(f, ".post{}", post)?;
+        }
+        if let Some(dev) = self.dev {
+            write!(f, ".dev{}", dev)?;
+        }
+        if let Some(local) = &self.local {
+            write!(f, "+{}", local)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_requirements() {
+        let r = parse_requirement("importlib-metadata>=3.6.0; python_version < \"3.10\"").unwrap();
+        assert_eq!(r.name, "importlib-metadata");
+        assert_eq!(r.specifier, ">=3.6.0");
+        assert_eq!(r.marker, Some("python_version < \"3.10\"".to_string()));
+        let r = parse_requirement("requests [socks, security] (>=2.8.1, <3)").unwrap();
+        assert_eq!(r.extras, vec!["socks".to_string(), "security".to_string()]);
+        assert_eq!(r.specifier, ">=2.8.1,<3");
+        let r = parse_requirement("spam @ git+https://github.com/me/spam.git ; os_name == 'nt'")
+            .unwrap();
+        assert_eq!(
+            r.url,
+            Some("git+https://github.com/me/spam.git".to_string())
+        );
+        assert!(parse_requirement("-e .").is_none());
+        assert!(matches!(
+            "-e .".parse::<ParsedRequirement>(),
+            Err(Error::InvalidRequirement(_))
+        ));
+        assert_eq!(canonicalize_name("Flask_SQLAlchemy"), "flask-sqlalchemy");
+    }
+
+    #[test]
+    fn compare_versions() {
+        let v = |s: &str| s.parse::<Version>().unwrap();
+        assert!(v("1.0.dev0") < v("1.0a1"));
+        assert!(v("1.0a1") < v("1.0rc1"));
+        assert!(v("1.0rc1") < v("1.0"));
+        assert!(v("1.0") < v("1.0.post1"));
+        assert_eq!(v("1.0"), v("1.0.0"));
+        assert!(v("3.0.1").satisfies(">=3.0.0"));
+        assert!(!v("1.4").satisfies(">=1.6.2"));
+        assert!(v("2.2.1").satisfies("~=2.2,!=2.2.0"));
+        assert!(!v("3.0").satisfies("~=2.2"));
+        assert!(v("1.26.4").satisfies("==1.26.*"));
+        assert!(!v("2.0rc1").satisfies("<2.0"));
+    }
+
+    #[test]
+    fn evaluate_markers() {
+        let mut environment = MarkerEnvironment::for_platform(Some("3.11.4"));
+        assert!(!evaluate_marker("python_version < \"3.10\"", &environment));
+        assert!(evaluate_marker(
+            "python_full_version >= '3.11.1'",
+            &environment
+        ));
+        assert!(!evaluate_marker("extra == 'async'", &environment));
+        environment.extra = Some("async".to_string());
+        assert!(evaluate_marker(
+            "(python_version >= '3.8' or os_name == 'nt') and extra == \"async\"",
+            &environment
+        ));
+        assert!(evaluate_marker(
+            "'linux' not in sys_platform or sys_platform == 'linux'",
+            &environment
+        ));
+    }
+}
@@ -0,0 +1,834 @@
+//! Python bindings of the CLI's commands and spec models, built with the
+//! `python` feature. Errors are converted to Python exceptions on the way out.
+use pyo3::exceptions::{PyTypeError, PyUserWarning};
+use pyo3::prelude::*;
+use pyo3::types::{PyBool, PyDict, PyList};
+use std::path::Path;
+
+use crate::dynamic::DynamicVersion;
+use crate::generators::{
+    self, PyProjectGenerator, RequirementsGenerator, SetupGenerator, SpecGenerator, WriteOptions,
+};
+use crate::layout::PackageLayout;
+use crate::parsers::{PyProjectParser, RequirementsParser, SetupParser, SpecParser, SpecStrParser};
+use crate::specs::{BuildSystem, Entrypoints, Project, PyProject, Readme, Requirements, Setup};
+
+/// Issues `warnings` as Python `UserWarning`s.
+fn warn(py: Python<'_>, warnings: &[String]) -> PyResult<()> {
+    for warning in warnings {
+        PyErr::warn(py, py.get_type::<PyUserWarning>(), warning, 1)?;
+    }
+    Ok(())
+}
+
+/// Transpiles a source Python package build specification file (e.g., setup.py)
+/// to another (e.g., pyproject.toml).
+///
+/// When translating from a frozen requirements.txt, an installed `environment`
+/// may be given to keep only the requirements no other package depends on.
+/// When translating from a setup.py, imports of local modules are followed
+/// `import_depth` modules deep. An existing `destination` is only overwritten
+/// when `force` is given, after being copied to a .bak file if `backup` is.
+/// Entries of `source` which are left out are reported as `UserWarning`s.
+#[pyfunction]
+#[pyo3(signature = (source, destination, environment=None, import_depth=None, force=false, backup=false))]
+fn translate(
+    py: Python<'_>,
+    source: String,
+    destination: String,
+    environment: Option<String>,
+    import_depth: Option<usize>,
+    force: bool,
+    backup: bool,
+) -> PyResult<()> {
+    let warnings = crate::translate(
+        Path::new(&source),
+        Path::new(&destination),
+        environment.as_deref().map(Path::new),
+        import_depth.unwrap_or(SetupParser::DEFAULT_IMPORT_DEPTH),
+        &WriteOptions { force, backup },
+    )?;
+    warn(py, &warnings)
+}
+
+/// Transpiles the contents of a build specification to those of another, e.g.,
+/// `translate_str(text, "setup.py", "pyproject.toml")`, without reading or
+/// writing any file.
+#[pyfunction]
+fn translate_str(
+    py: Python<'_>,
+    source_text: &str,
+    source_format: &str,
+    target_format: &str,
+) -> PyResult<String> {
+    let translation = crate::translate_str(source_text, source_format, target_format)?;
+    warn(py, &translation.warnings)?;
+    Ok(translation.contents)
+}
+
+/// Reads the build specification file at `source` and returns its translation
+/// to `target_format`, e.g., `"pyproject.toml"`. `source_format` overrides the
+/// format detected from the name of `source`.
+#[pyfunction]
+#[pyo3(signature = (source, target_format, source_format=None, environment=None, import_depth=None))]
+fn translate_file(
+    py: Python<'_>,
+    source: String,
+    target_format: &str,
+    source_format: Option<&str>,
+    environment: Option<String>,
+    import_depth: Option<usize>,
+) -> PyResult<String> {
+    let translation = crate::translate_file(
+        Path::new(&source),
+        source_format,
+        target_format,
+        environment.as_deref().map(Path::new),
+        import_depth.unwrap_or(SetupParser::DEFAULT_IMPORT_DEPTH),
+    )?;
+    warn(py, &translation.warnings)?;
+    Ok(translation.contents)
+}
+
+/// Reports the requirements which differ between the build specification at
+/// `destination` and those it would hold if translated from `source`, as
+/// `+ missing`, `- unexpected` or `~ different`. The two agree when the
+/// returned report is empty.
+#[pyfunction]
+#[pyo3(signature = (source, destination, environment=None, import_depth=None))]
+fn check(
+    source: String,
+    destination: String,
+    environment: Option<String>,
+    import_depth: Option<usize>,
+) -> PyResult<String> {
+    let report = crate::check(
+        Path::new(&source),
+        Path::new(&destination),
+        environment.as_deref().map(Path::new),
+        import_depth.unwrap_or(SetupParser::DEFAULT_IMPORT_DEPTH),
+    )?;
+    Ok(report.to_string())
+}
+
+/// Reports how the build specification at `new` differs from the one at
+/// `old`, of any formats, as JSON if `json` is set. Returns the report and
+/// whether the two agree.
+#[pyfunction]
+#[pyo3(signature = (old, new, json=false, import_depth=None))]
+fn diff_specs(
+    old: String,
+    new: String,
+    json: bool,
+    import_depth: Option<usize>,
+) -> PyResult<(String, bool)> {
+    let report = crate::diff_specs(
+        Path::new(&old),
+        Path::new(&new),
+        import_depth.unwrap_or(SetupParser::DEFAULT_IMPORT_DEPTH),
+    )?;
+    let rendered = match json {
+        true => format!("{:#}\n", report.to_json()),
+        false => report.to_string(),
+    };
+    Ok((rendered, report.is_empty()))
+}
+
+/// Returns the contents of the existing build specification file at
+/// `destination` with its requirements replaced by those translated from
+/// `source`, leaving everything else as it is.
+#[pyfunction]
+#[pyo3(signature = (source, destination, environment=None, import_depth=None))]
+fn sync_file(
+    py: Python<'_>,
+    source: String,
+    destination: String,
+    environment: Option<String>,
+    import_depth: Option<usize>,
+) -> PyResult<String> {
+    let translation = crate::sync_file(
+        Path::new(&source),
+        Path::new(&destination),
+        environment.as_deref().map(Path::new),
+        import_depth.unwrap_or(SetupParser::DEFAULT_IMPORT_DEPTH),
+    )?;
+    warn(py, &translation.warnings)?;
+    Ok(translation.contents)
+}
+
+/// Returns a scaffolded build specification of `target_format`, declaring the
+/// packages found under `root`.
+#[pyfunction]
+fn create_str(target_format: &str, root: String) -> PyResult<String> {
+    Ok(crate::create_str(target_format, Path::new(&root))?)
+}
+
+/// Scaffolds a build specification file, declaring the packages found next to it.
+/// An existing `destination` is only overwritten when `force` is given.
+#[pyfunction]
+#[pyo3(signature = (destination, force=false, backup=false))]
+fn create(destination: String, force: bool, backup: bool) -> PyResult<()> {
+    Ok(crate::create(
+        Path::new(&destination),
+        &WriteOptions { force, backup },
+    )?)
+}
+
+/// Writes `contents` to the file at `path` atomically. An existing file is
+/// only overwritten when `force` is given, after being copied to a .bak file
+/// if `backup` is.
+#[pyfunction]
+#[pyo3(signature = (path, contents, force=false, backup=false))]
+fn write_file(path: String, contents: &str, force: bool, backup: bool) -> PyResult<()> {
+    Ok(generators::write_file(
+        Path::new(&path),
+        contents,
+        &WriteOptions { force, backup },
+    )?)
+}
+
+/// Renders the dependency tree of an installed environment, warning of any
+/// requirements which are missing or conflicting.
+#[pyfunction]
+fn tree(py: Python<'_>, environment: String) -> PyResult<String> {
+    let tree = crate::tree(Path::new(&environment))?;
+    let problems: Vec<String> = tree.problems().iter().map(ToString::to_string).collect();
+    warn(py, &problems)?;
+    Ok(tree.to_string())
+}
+
+/// Reports dependencies which are imported by a package's sources but not
+/// declared by the build specification at `source`, or declared but never
+/// imported, warning of files which could not be parsed. The two agree when
+/// the returned report is empty.
+#[pyfunction]
+#[pyo3(signature = (source, package=None, mappings=Vec::new()))]
+fn check_imports(
+    py: Python<'_>,
+    source: String,
+    package: Option<String>,
+    mappings: Vec<(String, String)>,
+) -> PyResult<String> {
+    let report = crate::check_imports(
+        Path::new(&source),
+        package.as_deref().map(Path::new),
+        &mappings,
+    )?;
+    let unparsable: Vec<String> = report
+        .unparsable
+        .iter()
+        .map(|path| format!("skipping unparsable file {}", path.display()))
+        .collect();
+    warn(py, &unparsable)?;
+    Ok(report.to_string())
+}
+
+/// Returns the unified diff from `original` to `modified`, labelled with
+/// `original_name` and `modified_name`, or an empty string if they are equal.
+#[pyfunction]
+fn unified_diff(
+    original: &str,
+    modified: &str,
+    original_name: &str,
+    modified_name: &str,
+) -> String {
+    crate::diff::unified_diff(original, modified, original_name, modified_name)
+}
+
+/// A readme is a path, e.g., `"README.md"`, or a dict of the pyproject.toml
+/// table, e.g., `{"file": "README.rst", "content-type": "text/x-rst"}`.
+impl IntoPy<PyObject> for Readme {
+    fn into_py(self, py: Python<'_>) -> PyObject {
+        match self {
+            Readme::Path(path) => path.into_py(py),
+            Readme::Table {
+                file,
+                text,
+                content_type,
+            } => {
+                let dict = PyDict::new(py);
+                for (key, value) in [
+                    ("file", file),
+                    ("text", text),
+                    ("content-type", content_type),
+                ] {
+                    if let Some(value) = value {
+                        // Setting a str on a fresh dict cannot fail.
+                        dict.set_item(key, value).unwrap();
+                    }
+                }
+                dict.into_py(py)
+            }
+        }
+    }
+}
+
+impl<'a> FromPyObject<'a> for Readme {
+    fn extract(object: &'a PyAny) -> PyResult<Self> {
+        if let Ok(path) = object.extract::<String>() {
+            return Ok(Readme::Path(path));
+        }
+        let Ok(dict) = object.downcast::<PyDict>() else {
+            return Err(PyTypeError::new_err("readme must be a str or a dict"));
+        };
+        let get = |key: &str| -> PyResult<Option<String>> {
+            match dict.get_item(key) {
+                Some(value) => value.extract(),
+                None => Ok(None),
+            }
+        };
+        Ok(Readme::Table {
+            file: get("file")?,
+            text: get("text")?,
+            content_type: get("content-type")?,
+        })
+    }
+}
+
+/// A dynamic version is a dict of where it is read from, i.e., `{"attr": ...,
+/// "path": ...}` for a module attribute, `{"file": ...}` for a file holding
+/// nothing but the version, or `{"path": ..., "pattern": ...}` otherwise.
+impl IntoPy<PyObject> for DynamicVersion {
+    fn into_py(self, py: Python<'_>) -> PyObject {
+        let items = match self {
+            DynamicVersion::Attribute { attr, path } => vec![("attr", Some(attr)), ("path", path)],
+            DynamicVersion::File(path) => vec![("file", Some(path))],
+            DynamicVersion::Pattern { path, pattern } => {
+                vec![("path", Some(path)), ("pattern", pattern)]
+            }
+        };
+        let dict = PyDict::new(py);
+        for (key, value) in items {
+            if let Some(value) = value {
+                // Setting a str on a fresh dict cannot fail.
+                dict.set_item(key, value).unwrap();
+            }
+        }
+        dict.into_py(py)
+    }
+}
+
+impl<'a> FromPyObject<'a> for DynamicVersion {
+    fn extract(object: &'a PyAny) -> PyResult<Self> {
+        let dict = object
+            .downcast::<PyDict>()
+            .map_err(|_| PyTypeError::new_err("dynamic_version must be a dict"))?;
+        let get = |key: &str| -> PyResult<Option<String>> {
+            match dict.get_item(key) {
+                Some(value) => value.extract(),
+                None => Ok(None),
+            }
+        };
+        if let Some(attr) = get("attr")? {
+            return Ok(DynamicVersion::Attribute {
+                attr,
+                path: get("path")?,
+            });
+        }
+        if let Some(path) = get("file")? {
+            return Ok(DynamicVersion::File(path));
+        }
+        match get("path")? {
+            Some(path) => Ok(DynamicVersion::Pattern {
+                path,
+                pattern: get("pattern")?,
+            }),
+            None => Err(PyTypeError::new_err(
+                "dynamic_version must hold an \"attr\", \"file\" or \"path\"",
+            )),
+        }
+    }
+}
+
+/// Converts a `[tool]` table of a pyproject.toml to plain Python objects.
+fn toml_to_py(py: Python<'_>, value: &toml::Value) -> PyObject {
+    match value {
+        toml::Value::String(value) => value.into_py(py),
+        toml::Value::Integer(value) => value.into_py(py),
+        toml::Value::Float(value) => value.into_py(py),
+        toml::Value::Boolean(value) => value.into_py(py),
+        toml::Value::Datetime(value) => value.to_string().into_py(py),
+        toml::Value::Array(values) => {
+            PyList::new(py, values.iter().map(|value| toml_to_py(py, value))).into_py(py)
+        }
+        toml::Value::Table(table) => {
+            let dict = PyDict::new(py);
+            for (key, value) in table.iter() {
+                dict.set_item(key, toml_to_py(py, value)).unwrap();
+            }
+            dict.into_py(py)
+        }
+    }
+}
+
+/// Converts plain Python objects back to a `[tool]` table of a pyproject.toml.
+fn py_to_toml(object: &PyAny) -> PyResult<toml::Value> {
+    // A bool is also an int to Python, so it is tried first.
+    if let Ok(value) = object.downcast::<PyBool>() {
+        return Ok(toml::Value::Boolean(value.is_true()));
+    }
+    if let Ok(value) = object.extract::<i64>() {
+        return Ok(toml::Value::Integer(value));
+    }
+    if let Ok(value) = object.extract::<f64>() {
+        return Ok(toml::Value::Float(value));
+    }
+    if let Ok(value) = object.extract::<String>() {
+        return Ok(toml::Value::String(value));
+    }
+    if let Ok(dict) = object.downcast::<PyDict>() {
+        let mut table = toml::Table::new();
+        for (key, value) in dict.iter() {
+            table.insert(key.extract()?, py_to_toml(value)?);
+        }
+        return Ok(toml::Value::Table(table));
+    }
+    if let Ok(list) = object.downcast::<PyList>() {
+        return Ok(toml::Value::Array(
+            list.iter().map(py_to_toml).collect::<PyResult<_>>()?,
+        ));
+    }
+    Err(PyTypeError::new_err(format!(
+        "cannot convert {} to TOML",
+        object.get_type().name()?
+    )))
+}
+
+#[pymethods]
+impl Requirements {
+    #[new]
+    fn py_new() -> Self {
+        Self::default()
+    }
+
+    /// Parses the requirements.txt at `path`.
+    #[staticmethod]
+    #[pyo3(name = "from_file")]
+    fn py_from_file(path: String) -> PyResult<Self> {
+        Ok(RequirementsParser::from_file(Path::new(&path))?)
+    }
+
+    /// Parses the contents of a requirements.txt.
+    #[staticmethod]
+    #[pyo3(name = "from_str")]
+    fn py_from_str(contents: &str) -> PyResult<Self> {
+        Ok(RequirementsParser::from_str(contents)?)
+    }
+
+    fn to_setup(&self) -> Setup {
+        Setup::from_requirements(self.clone())
+    }
+
+    fn to_pyproject(&self) -> PyProject {
+        PyProject::from_requirements(self.clone())
+    }
+
+    /// Returns the contents of a requirements.txt.
+    fn to_str(&self) -> PyResult<String> {
+        Ok(RequirementsGenerator::make_string(self)?)
+    }
+
+    #[pyo3(signature = (path, force=false, backup=false))]
+    fn to_file(&self, path: String, force: bool, backup: bool) -> PyResult<()> {
+        Ok(RequirementsGenerator::make_file(
+            Path::new(&path),
+            self,
+            &WriteOptions { force, backup },
+        )?)
+    }
+
+    fn to_dict(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let dict = PyDict::new(py);
+        dict.set_item("requires", self.requires.clone())?;
+        dict.set_item("hashes", self.hashes.clone())?;
+        dict.set_item("via", self.via.clone())?;
+        Ok(dict.into_py(py))
+    }
+}
+
+#[pymethods]
+impl Setup {
+    /// Leaves every field unset, whereas `Setup::default` starts from empty values.
+    #[new]
+    fn py_new() -> Self {
+        Self {
+            package_name: None,
+            version: None,
+            description: None,
+            python_requires: None,
+            project_urls: None,
+            extra_requires: None,
+            install_requires: None,
+            setup_requires: None,
+            entry_points: None,
+            layout: None,
+            dynamic_version: None,
+            readme: None,
+            requirements_file: None,
+        }
+    }
+
+    /// Parses the setup.py at `path` without running it.
+    #[staticmethod]
+    #[pyo3(name = "from_file", signature = (path, import_depth=SetupParser::DEFAULT_IMPORT_DEPTH))]
+    fn py_from_file(path: String, import_depth: usize) -> PyResult<Self> {
+        Ok(SetupParser::from_file_with_import_depth(
+            Path::new(&path),
+            import_depth,
+        )?)
+    }
+
+    /// Parses the contents of a setup.py without running it.
+    #[staticmethod]
+    #[pyo3(name = "from_str")]
+    fn py_from_str(contents: &str) -> PyResult<Self> {
+        Ok(SetupParser::from_str(contents)?)
+    }
+
+    fn to_requirements(&self) -> Requirements {
+        Requirements::from_setup(self.clone())
+    }
+
+    fn to_pyproject(&self) -> PyProject {
+        PyProject::from_setup(self.clone())
+    }
+
+    /// Returns the contents of a setup.py.
+    fn to_str(&self) -> PyResult<String> {
+        Ok(SetupGenerator::make_string(self)?)
+    }
+
+    #[pyo3(signature = (path, force=false, backup=false))]
+    fn to_file(&self, path: String, force: bool, backup: bool) -> PyResult<()> {
+        Ok(SetupGenerator::make_file(
+            Path::new(&path),
+            self,
+            &WriteOptions { force, backup },
+        )?)
+    }
+
+    fn to_dict(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let dict = PyDict::new(py);
+        dict.set_item("package_name", self.package_name.clone())?;
+        dict.set_item("version", self.version.clone())?;
+        dict.set_item("description", self.description.clone())?;
+        dict.set_item("python_requires", self.python_requires.clone())?;
+        dict.set_item("project_urls", self.project_urls.clone())?;
+        dict.set_item("extra_requires", self.extra_requires.clone())?;
+        dict.set_item("install_requires", self.install_requires.clone())?;
+        dict.set_item("setup_requires", self.setup_requires.clone())?;
+        let entry_points = match &self.entry_points {
+            Some(entry_points) => Some(entry_points.to_dict(py)?),
+            None => None,
+        };
+        dict.set_item("entry_points", entry_points)?;
+        let layout = match &self.layout {
+            Some(layout) => Some(layout.to_dict(py)?),
+            None => None,
+        };
+        dict.set_item("layout", layout)?;
+        dict.set_item("dynamic_version", self.dynamic_version.clone().into_py(py))?;
+        dict.set_item("readme", self.readme.clone().into_py(py))?;
+        dict.set_item("requirements_file", self.requirements_file.clone())?;
+        Ok(dict.into_py(py))
+    }
+}
+
+#[pymethods]
+impl Entrypoints {
+    #[new]
+    fn py_new() -> Self {
+        Self {
+            console_scripts: None,
+            gui_scripts: None,
+        }
+    }
+
+    fn to_dict(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let dict = PyDict::new(py);
+        dict.set_item("console_scripts", self.console_scripts.clone())?;
+        dict.set_item("gui_scripts", self.gui_scripts.clone())?;
+        Ok(dict.into_py(py))
+    }
+}
+
+#[pymethods]
+impl PackageLayout {
+    #[new]
+    fn py_new() -> Self {
+        Self::default()
+    }
+
+    fn to_dict(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let dict = PyDict::new(py);
+        dict.set_item("package_dir", self.package_dir.clone())?;
+        dict.set_item("packages", self.packages.clone())?;
+        dict.set_item("namespaces", self.namespaces)?;
+        dict.set_item("py_modules", self.py_modules.clone())?;
+        dict.set_item("package_data", self.package_data.clone())?;
+        Ok(dict.into_py(py))
+    }
+}
+
+#[pymethods]
+impl PyProject {
+    #[new]
+    fn py_new() -> Self {
+        Self {
+            build_system: None,
+            project: None,
+            tool: None,
+        }
+    }
+
+    // pyo3 cannot convert a `toml::Table` on its own, so the fields of a
+    // pyproject.toml are exposed by hand rather than with `get_all`.
+    #[getter]
+    fn get_build_system(&self) -> Option<BuildSystem> {
+        self.build_system.clone()
+    }
+
+    #[setter]
+    fn set_build_system(&mut self, value: Option<BuildSystem>) {
+        self.build_system = value;
+    }
+
+    #[getter]
+    fn get_project(&self) -> Option<Project> {
+        self.project.clone()
+    }
+
+    #[setter]
+    fn set_project(&mut self, value: Option<Project>) {
+        self.project = value;
+    }
+
+    /// The `[tool]` table, as plain Python objects.
+    #[getter]
+    fn get_tool(&self, py: Python<'_>) -> Option<PyObject> {
+        self.tool
+            .as_ref()
+            .map(|tool| toml_to_py(py, &toml::Value::Table(tool.clone())))
+    }
+
+    #[setter]
+    fn set_tool(&mut self, value: Option<&PyDict>) -> PyResult<()> {
+        self.tool = match value.map(|tool| py_to_toml(tool)).transpose()? {
+            Some(toml::Value::Table(tool)) => Some(tool),
+            _ => None,
+        };
+        Ok(())
+    }
+
+    /// Parses the pyproject.toml at `path`.
+    #[staticmethod]
+    #[pyo3(name = "from_file")]
+    fn py_from_file(path: String) -> PyResult<Self> {
+        Ok(PyProjectParser::from_file(Path::new(&path))?)
+    }
+
+    /// Parses the contents of a pyproject.toml.
+    #[staticmethod]
+    #[pyo3(name = "from_str")]
+    fn py_from_str(contents: &str) -> PyResult<Self> {
+        Ok(PyProjectParser::from_str(contents)?)
+    }
+
+    fn to_requirements(&self) -> Requirements {
+        Requirements::from_pyproject(self.clone())
+    }
+
+    fn to_setup(&self) -> Setup {
+        Setup::from_pyproject(self.clone())
+    }
+
+    /// Returns the contents of a pyproject.toml.
+    fn to_str(&self) -> PyResult<String> {
+        Ok(PyProjectGenerator::make_string(self)?)
+    }
+
+    #[pyo3(signature = (path, force=false, backup=false))]
+    fn to_file(&self, path: String, force: bool, backup: bool) -> PyResult<()> {
+        Ok(PyProjectGenerator::make_file(
+            Path::new(&path),
+            self,
+            &WriteOptions { force, backup },
+        )?)
+    }
+
+    fn to_dict(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let dict = PyDict::new(py);
+        let build_system = match &self.build_system {
+            Some(build_system) => Some(build_system.to_dict(py)?),
+            None => None,
+        };
+        dict.set_item("build_system", build_system)?;
+        let project = match &self.project {
+            Some(project) => Some(project.to_dict(py)?),
+            None => None,
+        };
+        dict.set_item("project", project)?;
+        dict.set_item("tool", self.get_tool(py))?;
+        Ok(dict.into_py(py))
+    }
+}
+
+#[pymethods]
+impl BuildSystem {
+    #[new]
+    fn py_new() -> Self {
+        Self {
+            build_backend: None,
+            requires: None,
+        }
+    }
+
+    fn to_dict(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let dict = PyDict::new(py);
+        dict.set_item("build_backend", self.build_backend.clone())?;
+        dict.set_item("requires", self.requires.clone())?;
+        Ok(dict.into_py(py))
+    }
+}
+
+#[pymethods]
+impl Project {
+    #[new]
+    fn py_new() -> Self {
+        Self {
+            name: None,
+            version: None,
+            dynamic: None,
+            description: None,
+            readme: None,
+            requires_python: None,
+            dependencies: None,
+            optional_dependencies: None,
+            project_scripts: None,
+            project_gui_scripts: None,
+            urls: None,
+        }
+    }
+
+    fn to_dict(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let dict = PyDict::new(py);
+        dict.set_item("name", self.name.clone())?;
+        dict.set_item("version", self.version.clone())?;
+        dict.set_item("dynamic", self.dynamic.clone())?;
+        dict.set_item("description", self.description.clone())?;
+        dict.set_item("readme", self.readme.clone().into_py(py))?;
+        dict.set_item("requires_python", self.requires_python.clone())?;
+        dict.set_item("dependencies", self.dependencies.clone())?;
+        dict.set_item("optional_dependencies", self.optional_dependencies.clone())?;
+        dict.set_item("project_scripts", self.project_scripts.clone())?;
+        dict.set_item("project_gui_scripts", self.project_gui_scripts.clone())?;
+        dict.set_item("urls", self.urls.clone())?;
+        Ok(dict.into_py(py))
+    }
+}
+
+/// A Python module implemented in Rust.
+#[pymodule]
+fn _babelone_core(_py: Python, m: &PyModule) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(create, m)?)?;
+    m.add_function(wrap_pyfunction!(create_str, m)?)?;
+    m.add_function(wrap_pyfunction!(translate, m)?)?;
+    m.add_function(wrap_pyfunction!(translate_str, m)?)?;
+    m.add_function(wrap_pyfunction!(translate_file, m)?)?;
+    m.add_function(wrap_pyfunction!(check, m)?)?;
+    m.add_function(wrap_pyfunction!(sync_file, m)?)?;
+    m.add_function(wrap_pyfunction!(diff_specs, m)?)?;
+    m.add_function(wrap_pyfunction!(tree, m)?)?;
+    m.add_function(wrap_pyfunction!(check_imports, m)?)?;
+    m.add_function(wrap_pyfunction!(write_file, m)?)?;
+    m.add_function(wrap_pyfunction!(unified_diff, m)?)?;
+    m.add_class::<Requirements>()?;
+    m.add_class::<Setup>()?;
+    m.add_class::<Entrypoints>()?;
+    m.add_class::<PackageLayout>()?;
+    m.add_class::<PyProject>()?;
+    m.add_class::<BuildSystem>()?;
+    m.add_class::<Project>()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pyo3::types::IntoPyDict;
+
+    /// Runs the Python `code` with `spec` bound to `spec`.
+    fn run(spec: impl IntoPy<PyObject>, code: &str) {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let locals = [("spec", spec.into_py(py))].into_py_dict(py);
+            if let Err(err) = py.run(code, None, Some(locals)) {
+                err.print(py);
+                panic!("failed to run {}", code);
+            }
+        });
+    }
+
+    #[test]
+    fn leave_new_specs_unset() {
+        run(
+            Setup::py_new(),
+            "assert spec.package_name is None and spec.version is None\n\
+             assert spec.install_requires is None and spec.entry_points is None",
+        );
+        run(
+            PyProject::py_new(),
+            "assert spec.project is None and spec.build_system is None",
+        );
+    }
+
+    #[test]
+    fn expose_setup_fields() {
+        let mut setup = Setup::py_new();
+        setup.dynamic_version = Some(DynamicVersion::Attribute {
+            attr: "spam.__version__".to_string(),
+            path: Some("src/spam/__init__.py".to_string()),
+        });
+        setup.layout = Some(PackageLayout {
+            package_dir: Some("src".to_string()),
+            packages: vec!["spam".to_string()],
+            ..Default::default()
+        });
+        run(
+            setup,
+            r#"
+assert spec.dynamic_version == {"attr": "spam.__version__", "path": "src/spam/__init__.py"}
+assert spec.layout.package_dir == "src" and spec.layout.packages == ["spam"]
+spec.install_requires = ["eggs>=1.0"]
+assert spec.install_requires == ["eggs>=1.0"]
+spec.dynamic_version = {"file": "VERSION"}
+assert spec.to_dict()["dynamic_version"] == {"file": "VERSION"}
+try:
+    spec.dynamic_version = {"pattern": "v(.*)"}
+except TypeError:
+    pass
+else:
+    raise AssertionError("a pattern without a path was accepted")
+"#,
+        );
+    }
+
+    #[test]
+    fn expose_pyproject_tool() {
+        let pyproject = PyProjectParser::from_str(
+            "[project]\nname = \"spam\"\n\n[tool.black]\nline-length = 88\npreview = true\n",
+        )
+        .unwrap();
+        run(
+            pyproject,
+            r#"
+assert spec.project.name == "spam"
+assert spec.tool == {"black": {"line-length": 88, "preview": True}}
+spec.tool = {"ruff": {"select": ["E", "F"]}}
+assert "[tool.ruff]" in spec.to_str()
+spec.tool = None
+assert "[tool" not in spec.to_str()
+"#,
+        );
+    }
+}
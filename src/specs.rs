@@ -1,11 +1,26 @@
 //! Models encapsulating Python package build specifications.
+#[cfg(feature = "python")]
+use pyo3::pyclass;
 use serde::{Deserialize, Serialize};
-use std::{collections::BTreeMap, default::Default, fmt};
+use std::{collections::BTreeMap, default::Default, fmt, path::Path};
 
+use crate::dynamic::DynamicVersion;
+use crate::error::Result;
+use crate::layout::PackageLayout;
+use crate::pep508;
+use crate::utils;
+
+#[derive(Clone, Copy)]
 pub enum PyBuildSpec {
     Requirements,
     Setup,
     PyProject,
+    CondaEnvironment,
+    UvLock,
+    PyLock,
+    Metadata,
+    Distribution,
+    InstalledEnvironment,
 }
 
 /// Denotes a Python package dependency and its required version,
@@ -15,45 +30,196 @@ pub enum PyBuildSpec {
 pub type Requirement = String;
 
 /// Encapsulates build requirements defined in a requirements.txt (or similar file).
-#[derive(Default)]
+#[cfg_attr(feature = "python", pyclass(get_all, set_all))]
+#[derive(Clone, Debug, Default)]
 pub struct Requirements {
     pub requires: Vec<Requirement>,
+    /// Artifact hashes pinned for a requirement, e.g., `"sha256:<digest>"`, keyed
+    /// by its canonical package name (see [`Requirements::hash_key`]).
+    pub hashes: BTreeMap<String, Vec<String>>,
+    /// Provenance of a requirement as annotated by pip-compile, e.g., `"-r requirements.in"`.
+    pub via: BTreeMap<Requirement, Vec<String>>,
 }
 
 /// Encapsulates build specifications defined in a setup.py file.
+#[cfg_attr(feature = "python", pyclass(get_all, set_all))]
+#[derive(Clone, Debug)]
 pub struct Setup {
     pub package_name: Option<String>,
     pub version: Option<String>,
+    pub description: Option<String>,
+    pub python_requires: Option<String>,
+    pub project_urls: Option<BTreeMap<String, String>>,
     pub extra_requires: Option<BTreeMap<String, Vec<Requirement>>>,
     pub install_requires: Option<Vec<Requirement>>,
     pub setup_requires: Option<Vec<Requirement>>,
     pub entry_points: Option<Entrypoints>,
+    /// Packages, modules and data files, as given to `packages`, `package_dir`,
+    /// `py_modules` and `package_data`.
+    pub layout: Option<PackageLayout>,
+    /// Where the version is read from when it could not be resolved statically.
+    pub dynamic_version: Option<DynamicVersion>,
+    /// The `long_description`, or the file it is read from.
+    pub readme: Option<Readme>,
+    /// The file `install_requires` is read from, e.g., requirements.txt.
+    pub requirements_file: Option<String>,
+}
+
+/// A project's long description, as given by `readme` in a pyproject.toml or
+/// `long_description` in a setup.py.
+///
+/// # Examples
+/// `"README.md"`, `{file = "README.rst", content-type = "text/x-rst"}`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Readme {
+    Path(String),
+    Table {
+        file: Option<String>,
+        text: Option<String>,
+        #[serde(rename = "content-type")]
+        content_type: Option<String>,
+    },
 }
 
+#[cfg_attr(feature = "python", pyclass(get_all, set_all))]
+#[derive(Clone)]
 pub struct Entrypoints {
     pub console_scripts: Option<Vec<String>>,
     pub gui_scripts: Option<Vec<String>>,
 }
 
-/// Encapsulates build specifications defined in a pyproject.toml file.
+/// Encapsulates a conda environment defined in an environment.yml file.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CondaEnvironment {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channels: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dependencies: Option<Vec<CondaDependency>>,
+}
+
+/// An entry under `dependencies` in an environment.yml file, being either a
+/// conda package match spec or the nested list of pip requirements.
+///
+/// # Examples
+/// `"numpy=1.26"`, `{"pip": ["flask"]}`
 #[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum CondaDependency {
+    Package(String),
+    Pip { pip: Vec<Requirement> },
+}
+
+/// An entry in a build specification that has no faithful equivalent
+/// in babelone's requirement model, along with the reason why.
+#[derive(Debug, PartialEq)]
+pub struct Untranslatable {
+    pub entry: String,
+    pub reason: String,
+}
+
+/// Encapsulates core metadata defined in a PKG-INFO or METADATA file.
+#[derive(Debug)]
+pub struct Metadata {
+    pub metadata_version: Option<String>,
+    pub name: Option<String>,
+    pub version: Option<String>,
+    pub summary: Option<String>,
+    pub description: Option<String>,
+    pub description_content_type: Option<String>,
+    pub requires_python: Option<String>,
+    /// Requirements which may be restricted to an extra, e.g., `"pytest; extra == 'dev'"`.
+    pub requires_dist: Vec<Requirement>,
+    pub provides_extra: Vec<String>,
+    pub project_urls: BTreeMap<String, String>,
+    /// Read from the entry_points.txt distributed alongside the metadata, if any.
+    pub entry_points: Option<Entrypoints>,
+}
+
+/// Encapsulates the resolved packages of a lockfile, e.g., uv.lock or pylock.toml.
+#[derive(Debug, Default)]
+pub struct Lockfile {
+    pub packages: Vec<LockedPackage>,
+}
+
+/// A package pinned by a lockfile.
+#[derive(Debug)]
+pub struct LockedPackage {
+    pub name: String,
+    pub version: Option<String>,
+    pub source: Option<PackageSource>,
+    /// An environment marker restricting when the package is installed.
+    pub marker: Option<String>,
+    /// Hashes of the package's artifacts, e.g., `"sha256:<digest>"`.
+    pub hashes: Vec<String>,
+}
+
+/// Where a locked package is installed from.
+#[derive(Debug, PartialEq)]
+pub enum PackageSource {
+    /// A package index, e.g., `"https://pypi.org/simple"`.
+    Registry(String),
+    /// A remote archive.
+    Url(String),
+    /// A version control repository, e.g., `"git+https://github.com/me/spam.git"`,
+    /// optionally fixed to a commit.
+    Vcs { url: String, commit: Option<String> },
+    /// A local archive or directory.
+    Path(String),
+    /// A local directory installed in editable mode.
+    Editable(String),
+    /// A local project that is not itself installed, e.g., a uv workspace root.
+    Virtual(String),
+}
+
+/// Encapsulates the distributions installed in an environment's site-packages.
+#[derive(Debug, Default)]
+pub struct InstalledEnvironment {
+    /// The environment's Python version, e.g., `"3.11.4"`, if it could be determined.
+    pub python_version: Option<String>,
+    pub distributions: Vec<InstalledDistribution>,
+}
+
+/// A distribution installed in site-packages, as described by its .dist-info directory.
+#[derive(Debug)]
+pub struct InstalledDistribution {
+    pub metadata: Metadata,
+    /// Read from direct_url.json for distributions not installed from an index.
+    pub source: Option<PackageSource>,
+}
+
+/// Encapsulates build specifications defined in a pyproject.toml file.
+#[cfg_attr(feature = "python", pyclass)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct PyProject {
     #[serde(rename = "build-system")]
     pub build_system: Option<BuildSystem>,
     pub project: Option<Project>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool: Option<toml::Table>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "python", pyclass(get_all, set_all))]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct BuildSystem {
     #[serde(rename = "build-backend")]
     pub build_backend: Option<String>,
     pub requires: Option<Vec<Requirement>>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "python", pyclass(get_all, set_all))]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Project {
     pub name: Option<String>,
     pub version: Option<String>,
+    /// Fields provided by the build backend, e.g., `["version"]`.
+    pub dynamic: Option<Vec<String>>,
+    pub description: Option<String>,
+    pub readme: Option<Readme>,
+    #[serde(rename = "requires-python")]
+    pub requires_python: Option<String>,
     pub dependencies: Option<Vec<Requirement>>,
     #[serde(rename = "optional-dependencies")]
     pub optional_dependencies: Option<BTreeMap<String, Vec<Requirement>>>,
@@ -61,6 +227,7 @@ pub struct Project {
     pub project_scripts: Option<BTreeMap<String, String>>,
     #[serde(rename = "gui-scripts")]
     pub project_gui_scripts: Option<BTreeMap<String, String>>,
+    pub urls: Option<BTreeMap<String, String>>,
 }
 
 impl Requirements {
@@ -77,7 +244,10 @@ impl Requirements {
                 requires.append(&mut extra_require);
             }
         }
-        Self { requires }
+        Self {
+            requires,
+            ..Default::default()
+        }
     }
 
     pub fn from_pyproject(pyproject: PyProject) -> Self {
@@ -92,11 +262,135 @@ impl Requirements {
                 requires.append(&mut reqs);
             }
         }
-        Self { requires }
+        Self {
+            requires,
+            ..Default::default()
+        }
+    }
+
+    pub fn from_metadata(metadata: Metadata) -> Self {
+        Self::from_pyproject(PyProject::from_metadata(metadata))
+    }
+
+    pub fn from_conda_environment(environment: CondaEnvironment) -> Self {
+        let (requires, _) = environment.partition();
+        Self {
+            requires,
+            ..Default::default()
+        }
+    }
+
+    /// The key of the hashes pinned for `requirement`, being its canonical
+    /// package name, e.g., `"flask"` for `"Flask==3.0.2"`. Requirements without
+    /// a name, e.g., `"-e ."`, cannot be pinned by hash.
+    pub fn hash_key(requirement: &str) -> Option<String> {
+        pep508::parse_requirement(requirement)
+            .map(|requirement| pep508::canonicalize_name(&requirement.name))
+    }
+
+    /// Pins each package of the lockfile. Their hashes are only kept if every
+    /// package has some, as pip requires a hash on every requirement once any
+    /// has one (see [`Lockfile::unhashed_packages`]).
+    pub fn from_lockfile(lockfile: Lockfile) -> Self {
+        let mut requires = Vec::<Requirement>::new();
+        let mut hashes = BTreeMap::<String, Vec<String>>::new();
+        let keep_hashes = lockfile.unhashed_packages().is_empty();
+        for package in lockfile.packages {
+            let mut requirement = match (&package.source, &package.version) {
+                (Some(PackageSource::Virtual(_)), _) => continue,
+                (Some(PackageSource::Editable(path)), _) => {
+                    requires.push(format!("-e {}", path));
+                    continue;
+                }
+                (Some(PackageSource::Url(url)), _) => format!("{} @ {}", package.name, url),
+                (Some(PackageSource::Path(path)), _) => format!("{} @ {}", package.name, path),
+                (Some(PackageSource::Vcs { url, commit }), _) => match commit {
+                    Some(commit) => format!("{} @ {}@{}", package.name, url, commit),
+                    None => format!("{} @ {}", package.name, url),
+                },
+                (_, Some(version)) => format!("{}=={}", package.name, version),
+                (_, None) => package.name.clone(),
+            };
+            if let Some(marker) = &package.marker {
+                // Whitespace before `;` keeps the marker from being read as part of a URL.
+                requirement.push_str(&format!(" ; {}", marker));
+            }
+            if keep_hashes && !package.hashes.is_empty() {
+                // A package locked at several versions, e.g., per Python version,
+                // is installed from the artifacts of any of them.
+                let key = pep508::canonicalize_name(&package.name);
+                let package_hashes = hashes.entry(key).or_default();
+                for hash in package.hashes {
+                    if !package_hashes.contains(&hash) {
+                        package_hashes.push(hash);
+                    }
+                }
+            }
+            requires.push(requirement);
+        }
+        Self {
+            requires,
+            hashes,
+            ..Default::default()
+        }
+    }
+
+    pub fn from_installed_environment(environment: InstalledEnvironment) -> Self {
+        Self::from_lockfile(Lockfile::from_installed_environment(environment))
+    }
+}
+
+impl Lockfile {
+    /// The names of the packages which have no hashes to pin, e.g., those
+    /// installed from a VCS or in editable mode, when other packages do.
+    pub fn unhashed_packages(&self) -> Vec<&str> {
+        let packages = self
+            .packages
+            .iter()
+            .filter(|package| !matches!(package.source, Some(PackageSource::Virtual(_))));
+        if packages.clone().all(|package| package.hashes.is_empty()) {
+            return Vec::new();
+        }
+        packages
+            .filter(|package| {
+                package.hashes.is_empty()
+                    || matches!(package.source, Some(PackageSource::Editable(_)))
+            })
+            .map(|package| package.name.as_str())
+            .collect()
+    }
+
+    /// Pins each installed distribution as `pip freeze` would, omitting the
+    /// packaging tools it also leaves out.
+    pub fn from_installed_environment(environment: InstalledEnvironment) -> Self {
+        let mut packages = Vec::<LockedPackage>::new();
+        for distribution in environment.distributions {
+            let name = match distribution.metadata.name {
+                Some(name) => name,
+                None => continue,
+            };
+            if ["pip", "setuptools", "wheel", "distribute"].contains(&name.to_lowercase().as_str())
+            {
+                continue;
+            }
+            packages.push(LockedPackage {
+                name,
+                version: distribution.metadata.version,
+                source: distribution.source,
+                marker: None,
+                hashes: Vec::new(),
+            });
+        }
+        packages.sort_by_key(|package| package.name.to_lowercase());
+        Self { packages }
     }
 }
 
 impl Setup {
+    pub fn from_metadata(metadata: Metadata) -> Self {
+        Self::from_pyproject(PyProject::from_metadata(metadata))
+    }
+
     pub fn from_requirements(requirements: Requirements) -> Self {
         let install_requires = Some(requirements.requires);
         Self {
@@ -106,51 +400,53 @@ impl Setup {
             entry_points: None,
             package_name: None,
             version: None,
+            description: None,
+            python_requires: None,
+            project_urls: None,
+            layout: None,
+            dynamic_version: None,
+            readme: None,
+            requirements_file: None,
         }
     }
 
+    pub fn from_conda_environment(environment: CondaEnvironment) -> Self {
+        Self::from_requirements(Requirements::from_conda_environment(environment))
+    }
+
     pub fn from_pyproject(pyproject: PyProject) -> Self {
-        let (package_name, version, install_requires, extra_requires, entry_points) =
-            if pyproject.project.is_some() {
-                let project = pyproject.project.unwrap();
-                let mut console_scripts: Option<Vec<String>> = None;
-                let mut gui_scripts: Option<Vec<String>> = None;
-                if let Some(project_scripts) = project.project_scripts {
-                    let mut scripts = Vec::<String>::new();
-                    for (key, value) in project_scripts.iter() {
-                        scripts.push(format!("{} = {}", key, value));
-                    }
-                    if !scripts.is_empty() {
-                        console_scripts = Some(scripts);
-                    }
-                }
-                if let Some(project_gui_scripts) = project.project_gui_scripts {
-                    let mut scripts = Vec::<String>::new();
-                    for (key, value) in project_gui_scripts.iter() {
-                        scripts.push(format!("{} = {}", key, value));
-                    }
-                    if !scripts.is_empty() {
-                        gui_scripts = Some(scripts);
-                    }
-                }
-                let entry_points = if console_scripts.is_some() || gui_scripts.is_some() {
-                    Some(Entrypoints {
-                        console_scripts,
-                        gui_scripts,
-                    })
-                } else {
-                    None
-                };
-                (
-                    project.name,
-                    project.version,
-                    project.dependencies,
-                    project.optional_dependencies,
-                    entry_points,
-                )
-            } else {
-                (None, None, None, None, None)
-            };
+        let dynamic_version = pyproject.dynamic_version();
+        let requirements_file = pyproject.requirements_file();
+        let (
+            package_name,
+            version,
+            description,
+            readme,
+            python_requires,
+            project_urls,
+            install_requires,
+            extra_requires,
+            entry_points,
+        ) = if pyproject.project.is_some() {
+            let project = pyproject.project.unwrap();
+            let entry_points = Entrypoints::from_project_scripts(
+                project.project_scripts.as_ref(),
+                project.project_gui_scripts.as_ref(),
+            );
+            (
+                project.name,
+                project.version,
+                project.description,
+                project.readme,
+                project.requires_python,
+                project.urls,
+                project.dependencies,
+                project.optional_dependencies,
+                entry_points,
+            )
+        } else {
+            (None, None, None, None, None, None, None, None, None)
+        };
         let setup_requires = if pyproject.build_system.is_some() {
             let build_system = pyproject.build_system.unwrap();
             build_system.requires
@@ -160,12 +456,23 @@ impl Setup {
         Self {
             package_name,
             version,
+            description,
+            python_requires,
+            project_urls,
             install_requires,
             setup_requires,
             extra_requires,
             entry_points,
+            layout: None,
+            dynamic_version,
+            readme,
+            requirements_file,
         }
     }
+
+    pub fn from_installed_environment(environment: InstalledEnvironment) -> Self {
+        Self::from_pyproject(PyProject::from_installed_environment(environment))
+    }
 }
 
 impl Default for Setup {
@@ -173,14 +480,72 @@ impl Default for Setup {
         Self {
             package_name: Some(String::default()),
             version: Some(String::default()),
+            description: None,
+            python_requires: None,
+            project_urls: None,
             entry_points: Some(Entrypoints::default()),
             extra_requires: Some(BTreeMap::default()),
             install_requires: Some(Vec::default()),
             setup_requires: Some(Vec::default()),
+            layout: None,
+            dynamic_version: None,
+            readme: None,
+            requirements_file: None,
         }
     }
 }
 
+impl Readme {
+    /// Returns the file the description is read from, if any.
+    pub fn file(&self) -> Option<&str> {
+        match self {
+            Self::Path(path) => Some(path),
+            Self::Table { file, .. } => file.as_deref(),
+        }
+    }
+
+    /// Returns the description given inline, if any.
+    pub fn text(&self) -> Option<&str> {
+        match self {
+            Self::Path(_) => None,
+            Self::Table { text, .. } => text.as_deref(),
+        }
+    }
+
+    /// Returns the description, reading it from the file relative to `root`
+    /// when given as one. A file which does not exist gives no description.
+    pub fn read(&self, root: &Path) -> Result<Option<String>> {
+        match (self.text(), self.file()) {
+            (Some(text), _) => Ok(Some(text.to_string())),
+            (None, Some(file)) if root.join(file).is_file() => {
+                Ok(Some(utils::read_file(&root.join(file))?))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Returns the content type, inferring it from the file's extension as
+    /// build backends do when none is given.
+    pub fn content_type(&self) -> Option<String> {
+        if let Self::Table {
+            content_type: Some(content_type),
+            ..
+        } = self
+        {
+            return Some(content_type.clone());
+        }
+        let file = self.file()?.to_lowercase();
+        let content_type = if file.ends_with(".md") || file.ends_with(".markdown") {
+            "text/markdown"
+        } else if file.ends_with(".rst") {
+            "text/x-rst"
+        } else {
+            "text/plain"
+        };
+        Some(content_type.to_string())
+    }
+}
+
 impl Default for Entrypoints {
     fn default() -> Self {
         Self {
@@ -190,6 +555,64 @@ impl Default for Entrypoints {
     }
 }
 
+impl Entrypoints {
+    /// Collects pyproject.toml `[project.scripts]` and `[project.gui-scripts]` tables
+    /// as entry points, e.g., `"spam-cli = spam:main_cli"`.
+    pub fn from_project_scripts(
+        project_scripts: Option<&BTreeMap<String, String>>,
+        project_gui_scripts: Option<&BTreeMap<String, String>>,
+    ) -> Option<Self> {
+        let to_entry_points = |scripts: Option<&BTreeMap<String, String>>| {
+            let scripts: Vec<String> = scripts?
+                .iter()
+                .map(|(key, value)| format!("{} = {}", key, value))
+                .collect();
+            if scripts.is_empty() {
+                None
+            } else {
+                Some(scripts)
+            }
+        };
+        let console_scripts = to_entry_points(project_scripts);
+        let gui_scripts = to_entry_points(project_gui_scripts);
+        if console_scripts.is_some() || gui_scripts.is_some() {
+            Some(Self {
+                console_scripts,
+                gui_scripts,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Splits entry points into pyproject.toml `[project.scripts]` and
+    /// `[project.gui-scripts]` tables, skipping those without a `=`.
+    pub fn to_project_scripts(
+        &self,
+    ) -> (
+        Option<BTreeMap<String, String>>,
+        Option<BTreeMap<String, String>>,
+    ) {
+        let to_scripts = |entry_points: &Option<Vec<String>>| {
+            let mut scripts = BTreeMap::<String, String>::new();
+            for entry_point in entry_points.iter().flatten() {
+                if let Some((key, path)) = entry_point.split_once('=') {
+                    scripts.insert(key.trim().to_string(), path.trim().to_string());
+                }
+            }
+            if scripts.is_empty() {
+                None
+            } else {
+                Some(scripts)
+            }
+        };
+        (
+            to_scripts(&self.console_scripts),
+            to_scripts(&self.gui_scripts),
+        )
+    }
+}
+
 impl fmt::Debug for Entrypoints {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_map()
@@ -216,20 +639,38 @@ impl PyProject {
             dependencies,
             name: None,
             version: None,
+            dynamic: None,
+            description: None,
+            readme: None,
+            requires_python: None,
             optional_dependencies: None,
             project_scripts: None,
             project_gui_scripts: None,
+            urls: None,
         });
         Self {
             project,
             build_system,
+            tool: None,
         }
     }
 
     pub fn from_setup(setup: Setup) -> Self {
         let name = setup.package_name;
         let version = setup.version;
-        let dependencies = setup.install_requires;
+        let description = setup.description;
+        // pyproject.toml requires a content type, which PyPI otherwise assumes
+        // to be reStructuredText for an inline description.
+        let readme = setup.readme.map(|readme| Readme::Table {
+            file: readme.file().map(|file| file.to_string()),
+            text: readme.text().map(|text| text.to_string()),
+            content_type: readme
+                .content_type()
+                .or_else(|| Some("text/x-rst".to_string())),
+        });
+        let requires_python = setup.python_requires;
+        let urls = setup.project_urls;
+        let mut dependencies = setup.install_requires;
         let requires = setup.setup_requires;
         let optional_dependencies = setup.extra_requires;
         let build_system = if requires.is_some() {
@@ -240,41 +681,212 @@ impl PyProject {
         } else {
             None
         };
-        let mut project_scripts: Option<BTreeMap<String, String>> = None;
-        let mut project_gui_scripts: Option<BTreeMap<String, String>> = None;
-        if let Some(entry_points) = setup.entry_points {
-            if let Some(console_scripts) = entry_points.console_scripts {
-                let mut scripts = BTreeMap::<String, String>::new();
-                for console_script in console_scripts.iter() {
-                    let mut key_and_path = console_script.split('=').map(|s| s.trim().to_string());
-                    scripts.insert(key_and_path.next().unwrap(), key_and_path.next().unwrap());
-                }
-                if !scripts.is_empty() {
-                    project_scripts = Some(scripts);
-                }
+        let (project_scripts, project_gui_scripts) = match setup.entry_points {
+            Some(entry_points) => entry_points.to_project_scripts(),
+            None => (None, None),
+        };
+        // setup.py is always built with setuptools.
+        let mut tool = toml::Table::new();
+        if let Some(layout) = setup.layout {
+            merge_tables(&mut tool, layout.to_tool_table("setuptools.build_meta"));
+        }
+        let mut dynamic = Vec::<String>::new();
+        if let (None, Some(dynamic_version)) = (&version, setup.dynamic_version) {
+            // A version source setuptools cannot read is left for the user to
+            // configure, with a warning when translating.
+            if let Ok(table) = dynamic_version.to_tool_table("setuptools.build_meta") {
+                merge_tables(&mut tool, table);
             }
-            if let Some(gui_scripts) = entry_points.gui_scripts {
-                let mut scripts = BTreeMap::<String, String>::new();
-                for gui_script in gui_scripts.iter() {
-                    let mut key_and_path = gui_script.split('=').map(|s| s.trim().to_string());
-                    scripts.insert(key_and_path.next().unwrap(), key_and_path.next().unwrap());
-                }
-                if !scripts.is_empty() {
-                    project_gui_scripts = Some(scripts);
-                }
+            dynamic.push("version".to_string());
+        }
+        // Requirements read from a file stay in that file.
+        if let Some(requirements_file) = setup.requirements_file {
+            let mut table = toml::Table::new();
+            table.insert(
+                "file".to_string(),
+                toml::Value::Array(vec![toml::Value::String(requirements_file)]),
+            );
+            for key in ["dependencies", "dynamic", "setuptools"] {
+                let mut parent = toml::Table::new();
+                parent.insert(key.to_string(), toml::Value::Table(table));
+                table = parent;
             }
+            merge_tables(&mut tool, table);
+            dynamic.push("dependencies".to_string());
+            dependencies = None;
         }
+        let dynamic = if dynamic.is_empty() {
+            None
+        } else {
+            Some(dynamic)
+        };
+        let tool = if tool.is_empty() { None } else { Some(tool) };
         let project = Some(Project {
             name,
             version,
+            dynamic,
+            description,
+            readme,
+            requires_python,
             dependencies,
             optional_dependencies,
             project_scripts,
             project_gui_scripts,
+            urls,
         });
         Self {
             project,
             build_system,
+            tool,
+        }
+    }
+
+    pub fn from_metadata(metadata: Metadata) -> Self {
+        let mut dependencies = Vec::<Requirement>::new();
+        let mut optional_dependencies = BTreeMap::<String, Vec<Requirement>>::new();
+        for extra in metadata.provides_extra.iter() {
+            optional_dependencies.insert(extra.clone(), Vec::new());
+        }
+        for requirement in metadata.requires_dist.iter() {
+            let (requirement, extras) = split_extra_marker(requirement);
+            if extras.is_empty() {
+                dependencies.push(requirement);
+                continue;
+            }
+            for extra in extras {
+                optional_dependencies
+                    .entry(extra)
+                    .or_default()
+                    .push(requirement.clone());
+            }
+        }
+        let (project_scripts, project_gui_scripts) = match metadata.entry_points {
+            Some(entry_points) => entry_points.to_project_scripts(),
+            None => (None, None),
+        };
+        let project = Some(Project {
+            name: metadata.name,
+            version: metadata.version,
+            dynamic: None,
+            description: metadata.summary,
+            readme: metadata.description.map(|text| Readme::Table {
+                file: None,
+                text: Some(text),
+                content_type: Some(
+                    metadata
+                        .description_content_type
+                        .unwrap_or_else(|| "text/x-rst".to_string()),
+                ),
+            }),
+            requires_python: metadata.requires_python,
+            dependencies: Some(dependencies),
+            optional_dependencies: if optional_dependencies.is_empty() {
+                None
+            } else {
+                Some(optional_dependencies)
+            },
+            project_scripts,
+            project_gui_scripts,
+            urls: if metadata.project_urls.is_empty() {
+                None
+            } else {
+                Some(metadata.project_urls)
+            },
+        });
+        Self {
+            project,
+            build_system: None,
+            tool: None,
+        }
+    }
+
+    pub fn from_conda_environment(environment: CondaEnvironment) -> Self {
+        Self::from_requirements(Requirements::from_conda_environment(environment))
+    }
+
+    pub fn from_installed_environment(environment: InstalledEnvironment) -> Self {
+        let mut lockfile = Lockfile::from_installed_environment(environment);
+        // Editable installs have no equivalent in `dependencies`, so they are
+        // declared as direct references to their project directory instead.
+        for package in lockfile.packages.iter_mut() {
+            if let Some(PackageSource::Editable(url)) = &package.source {
+                package.source = Some(PackageSource::Path(url.clone()));
+            }
+        }
+        Self::from_requirements(Requirements::from_lockfile(lockfile))
+    }
+
+    /// Declares the packages of a project in the `[tool]` table of its build
+    /// backend, choosing setuptools when no backend is set.
+    pub fn set_layout(&mut self, layout: &PackageLayout) {
+        let build_system = self.build_system.get_or_insert_with(BuildSystem::default);
+        if build_system
+            .build_backend
+            .as_deref()
+            .unwrap_or_default()
+            .is_empty()
+        {
+            build_system.build_backend = Some("setuptools.build_meta".to_string());
+            build_system.requires = Some(vec!["setuptools>=61.0".to_string()]);
+        }
+        let tool = layout.to_tool_table(build_system.build_backend.as_deref().unwrap_or_default());
+        merge_tables(self.tool.get_or_insert_with(toml::Table::new), tool);
+    }
+
+    /// Returns where the build backend reads the version from, if the version
+    /// is declared as dynamic.
+    pub fn dynamic_version(&self) -> Option<DynamicVersion> {
+        let project = self.project.as_ref()?;
+        if !project
+            .dynamic
+            .as_ref()
+            .is_some_and(|dynamic| dynamic.iter().any(|field| field == "version"))
+        {
+            return None;
+        }
+        let build_backend = self
+            .build_system
+            .as_ref()
+            .and_then(|build_system| build_system.build_backend.as_deref())
+            .unwrap_or_default();
+        DynamicVersion::from_tool_table(self.tool.as_ref(), build_backend, project.name.as_deref())
+    }
+
+    /// Returns the requirements file setuptools reads the dependencies from,
+    /// if the dependencies are declared as dynamic.
+    pub fn requirements_file(&self) -> Option<String> {
+        let project = self.project.as_ref()?;
+        if !project
+            .dynamic
+            .as_ref()
+            .is_some_and(|dynamic| dynamic.iter().any(|field| field == "dependencies"))
+        {
+            return None;
+        }
+        let file = self
+            .tool
+            .as_ref()?
+            .get("setuptools")?
+            .get("dynamic")?
+            .get("dependencies")?
+            .get("file")?;
+        match file {
+            toml::Value::Array(files) => files.first()?.as_str().map(|file| file.to_string()),
+            file => file.as_str().map(|file| file.to_string()),
+        }
+    }
+}
+
+/// Merges the tables of `from` into `into`, recursing into tables both share.
+fn merge_tables(into: &mut toml::Table, from: toml::Table) {
+    for (key, value) in from {
+        match (into.get_mut(&key), value) {
+            (Some(toml::Value::Table(existing)), toml::Value::Table(table)) => {
+                merge_tables(existing, table)
+            }
+            (_, value) => {
+                into.insert(key, value);
+            }
         }
     }
 }
@@ -284,6 +896,7 @@ impl Default for PyProject {
         Self {
             project: Some(Project::default()),
             build_system: Some(BuildSystem::default()),
+            tool: None,
         }
     }
 }
@@ -293,10 +906,15 @@ impl Default for Project {
         Self {
             name: Some(String::default()),
             version: Some(String::new()),
+            dynamic: None,
+            description: None,
+            readme: None,
+            requires_python: None,
             dependencies: Some(Vec::new()),
             optional_dependencies: Some(BTreeMap::default()),
             project_scripts: Some(BTreeMap::default()),
             project_gui_scripts: Some(BTreeMap::default()),
+            urls: None,
         }
     }
 }
@@ -309,3 +927,360 @@ impl Default for BuildSystem {
         }
     }
 }
+
+impl Metadata {
+    pub fn from_requirements(requirements: Requirements) -> Self {
+        Self::from_pyproject(PyProject::from_requirements(requirements))
+    }
+
+    pub fn from_setup(setup: Setup) -> Self {
+        Self::from_pyproject(PyProject::from_setup(setup))
+    }
+
+    pub fn from_pyproject(pyproject: PyProject) -> Self {
+        let mut metadata = Self {
+            metadata_version: Some("2.1".to_string()),
+            name: None,
+            version: None,
+            summary: None,
+            description: None,
+            description_content_type: None,
+            requires_python: None,
+            requires_dist: Vec::new(),
+            provides_extra: Vec::new(),
+            project_urls: BTreeMap::new(),
+            entry_points: None,
+        };
+        if let Some(project) = pyproject.project {
+            metadata.entry_points = Entrypoints::from_project_scripts(
+                project.project_scripts.as_ref(),
+                project.project_gui_scripts.as_ref(),
+            );
+            metadata.name = project.name;
+            metadata.version = project.version;
+            metadata.summary = project.description;
+            if let Some(readme) = project.readme {
+                metadata.description_content_type = readme.content_type();
+                metadata.description = readme.text().map(|text| text.to_string());
+            }
+            metadata.requires_python = project.requires_python;
+            metadata.project_urls = project.urls.unwrap_or_default();
+            if let Some(mut dependencies) = project.dependencies {
+                metadata.requires_dist.append(&mut dependencies);
+            }
+            if let Some(optional_dependencies) = project.optional_dependencies {
+                for (extra, requirements) in optional_dependencies {
+                    for requirement in requirements.iter() {
+                        metadata
+                            .requires_dist
+                            .push(join_extra_marker(requirement, &extra));
+                    }
+                    metadata.provides_extra.push(extra);
+                }
+            }
+        }
+        metadata
+    }
+}
+
+impl Default for Metadata {
+    fn default() -> Self {
+        Self {
+            metadata_version: Some("2.1".to_string()),
+            name: Some(String::default()),
+            version: Some(String::default()),
+            summary: Some(String::default()),
+            description: None,
+            description_content_type: None,
+            requires_python: None,
+            requires_dist: Vec::default(),
+            provides_extra: Vec::default(),
+            project_urls: BTreeMap::default(),
+            entry_points: None,
+        }
+    }
+}
+
+impl CondaEnvironment {
+    pub fn from_requirements(requirements: Requirements) -> Self {
+        let dependencies = Some(vec![
+            CondaDependency::Package("pip".to_string()),
+            CondaDependency::Pip {
+                pip: requirements.requires,
+            },
+        ]);
+        Self {
+            name: None,
+            channels: None,
+            dependencies,
+        }
+    }
+
+    pub fn from_setup(setup: Setup) -> Self {
+        Self::from_requirements(Requirements::from_setup(setup))
+    }
+
+    pub fn from_pyproject(pyproject: PyProject) -> Self {
+        Self::from_requirements(Requirements::from_pyproject(pyproject))
+    }
+
+    /// Splits the environment into requirements installable by pip and the
+    /// entries which cannot be translated without guessing, e.g., the Python
+    /// interpreter itself, conda build strings or conda channels.
+    pub fn partition(&self) -> (Vec<Requirement>, Vec<Untranslatable>) {
+        let mut requires = Vec::<Requirement>::new();
+        let mut untranslatable = Vec::<Untranslatable>::new();
+        if let Some(channels) = &self.channels {
+            for channel in channels.iter() {
+                untranslatable.push(Untranslatable {
+                    entry: format!("channels: {}", channel),
+                    reason: "conda channels have no pip equivalent".to_string(),
+                });
+            }
+        }
+        if let Some(dependencies) = &self.dependencies {
+            for dependency in dependencies.iter() {
+                match dependency {
+                    CondaDependency::Package(spec) => match conda_to_requirement(spec) {
+                        Ok(requirement) => requires.push(requirement),
+                        Err(reason) => untranslatable.push(Untranslatable {
+                            entry: spec.clone(),
+                            reason,
+                        }),
+                    },
+                    CondaDependency::Pip { pip } => {
+                        for requirement in pip.iter() {
+                            requires.push(requirement.trim().to_string());
+                        }
+                    }
+                }
+            }
+        }
+        (requires, untranslatable)
+    }
+}
+
+impl Default for CondaEnvironment {
+    fn default() -> Self {
+        Self {
+            name: Some(String::default()),
+            channels: Some(vec!["defaults".to_string()]),
+            dependencies: Some(vec![
+                CondaDependency::Package("pip".to_string()),
+                CondaDependency::Pip { pip: Vec::new() },
+            ]),
+        }
+    }
+}
+
+impl fmt::Display for Untranslatable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.entry, self.reason)
+    }
+}
+
+/// Separates the extras named by an `extra == "<name>"` marker from a requirement,
+/// keeping any remaining markers on the requirement.
+///
+/// # Examples
+/// `"pytest>=7; extra == 'dev'"` becomes `("pytest>=7", ["dev"])`.
+fn split_extra_marker(requirement: &str) -> (Requirement, Vec<String>) {
+    let (spec, marker) = match requirement.split_once(';') {
+        Some((spec, marker)) => (spec.trim(), marker.trim()),
+        None => return (requirement.trim().to_string(), Vec::new()),
+    };
+    // Only `extra == "<name>"`, with a single quoted name, names an extra.
+    let parse_extra = |clause: &str| -> Option<String> {
+        let (lhs, rhs) = strip_parentheses(clause).split_once("==")?;
+        let rhs = rhs.trim();
+        let quote = rhs.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+        let name = rhs.strip_prefix(quote)?.strip_suffix(quote)?;
+        match lhs.trim() == "extra" && !name.contains(['"', '\'']) {
+            true => Some(name.to_string()),
+            false => None,
+        }
+    };
+    // e.g., `extra == "a" or extra == "b"`, but not `extra == "a" or python_version < "3.8"`
+    let parse_extras = |clause: &str| -> Option<Vec<String>> {
+        split_marker(strip_parentheses(clause), " or ")
+            .into_iter()
+            .map(parse_extra)
+            .collect()
+    };
+    // `and` binds more tightly than `or`, so a marker holding `or` outside of
+    // parentheses is either made of extras alone or kept as a whole.
+    if split_marker(marker, " or ").len() > 1 {
+        return match parse_extras(marker) {
+            Some(extras) => (spec.to_string(), extras),
+            None => (requirement.trim().to_string(), Vec::new()),
+        };
+    }
+    let mut extras = Vec::<String>::new();
+    let mut rest = Vec::<&str>::new();
+    for clause in split_marker(strip_parentheses(marker), " and ") {
+        match parse_extras(clause) {
+            Some(mut alternatives) => extras.append(&mut alternatives),
+            None => rest.push(clause.trim()),
+        }
+    }
+    if rest.is_empty() {
+        (spec.to_string(), extras)
+    } else {
+        (format!("{}; {}", spec, rest.join(" and ")), extras)
+    }
+}
+
+/// Splits a marker on `separator` outside of parentheses and quoted strings.
+fn split_marker<'a>(marker: &'a str, separator: &str) -> Vec<&'a str> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut quote = None;
+    let mut start = 0;
+    for (i, c) in marker.char_indices() {
+        match (c, quote) {
+            ('"' | '\'', None) => quote = Some(c),
+            (c, Some(q)) if c == q => quote = None,
+            (_, Some(_)) => (),
+            ('(', None) => depth += 1,
+            (')', None) => depth -= 1,
+            _ if depth == 0 && i >= start && marker[i..].starts_with(separator) => {
+                parts.push(&marker[start..i]);
+                start = i + separator.len();
+            }
+            _ => (),
+        }
+    }
+    parts.push(&marker[start..]);
+    parts
+}
+
+/// Removes the parentheses wrapping a whole marker, e.g., `(extra == "a")`.
+fn strip_parentheses(marker: &str) -> &str {
+    let mut marker = marker.trim();
+    while let Some(inner) = marker
+        .strip_prefix('(')
+        .and_then(|inner| inner.strip_suffix(')'))
+    {
+        // Not `(a) and (b)`, whose first parenthesis closes before the end.
+        let mut depth = 0;
+        let wraps = inner.chars().all(|c| {
+            depth += match c {
+                '(' => 1,
+                ')' => -1,
+                _ => 0,
+            };
+            depth >= 0
+        });
+        if !wraps {
+            break;
+        }
+        marker = inner.trim();
+    }
+    marker
+}
+
+/// Restricts a requirement to the given extra, preserving any existing markers.
+fn join_extra_marker(requirement: &str, extra: &str) -> Requirement {
+    match requirement.split_once(';') {
+        Some((spec, marker)) => format!(
+            "{}; ({}) and extra == \"{}\"",
+            spec.trim(),
+            marker.trim(),
+            extra
+        ),
+        None => format!("{}; extra == \"{}\"", requirement.trim(), extra),
+    }
+}
+
+/// Translates a conda match spec (e.g., `"numpy=1.26"`) into a pip requirement,
+/// returning the reason for failure when no faithful translation exists.
+/// Only a name and a version, pinned or ranged, are carried over.
+fn conda_to_requirement(spec: &str) -> std::result::Result<Requirement, String> {
+    let spec = spec.trim();
+    if let Some((channel, _)) = spec.split_once("::") {
+        return Err(format!("pinned to the conda channel '{}'", channel));
+    }
+    let split = spec
+        .find(|c: char| c.is_whitespace() || "=<>!~[".contains(c))
+        .unwrap_or(spec.len());
+    let (name, constraint) = spec.split_at(split);
+    let constraint = constraint.trim();
+    match name {
+        "python" => return Err("the Python interpreter is not a pip requirement".to_string()),
+        "pip" => return Err("pip is an installer, not a package dependency".to_string()),
+        _ => (),
+    }
+    if constraint.starts_with('[') {
+        return Err("bracketed conda match specs have no pip equivalent".to_string());
+    }
+    if constraint.contains('|') {
+        return Err("alternative version constraints have no pip equivalent".to_string());
+    }
+    let version = if constraint.is_empty() {
+        String::new()
+    } else if constraint.starts_with(|c: char| "<>!~".contains(c)) || constraint.starts_with("==") {
+        constraint.replace(' ', "")
+    } else {
+        // Both `numpy=1.26` and `numpy 1.26` forms may carry a trailing build string.
+        let fuzzy = constraint.starts_with('=');
+        let parts: Vec<&str> = constraint
+            .trim_start_matches('=')
+            .split(|c: char| c == '=' || c.is_whitespace())
+            .filter(|s| !s.is_empty())
+            .collect();
+        if parts.len() != 1 {
+            return Err("conda build strings have no pip equivalent".to_string());
+        }
+        // A single `=` matches any version the given one is a prefix of, e.g.,
+        // `numpy=1.26` matches 1.26.4, while `numpy 1.26` matches 1.26 only.
+        match parts[0].strip_suffix('*') {
+            Some(prefix) => format!("=={}.*", prefix.trim_end_matches('.')),
+            None if fuzzy => format!("=={}.*", parts[0]),
+            None => format!("=={}", parts[0]),
+        }
+    };
+    let requirement = format!("{}{}", name, version);
+    match pep508::parse_requirement(&requirement) {
+        Some(_) => Ok(requirement),
+        None => Err("the conda match spec has no pip equivalent".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn split_extra_markers() {
+        assert_eq!(
+            split_extra_marker("pytest>=7; extra == 'dev'"),
+            ("pytest>=7".to_string(), vec!["dev".to_string()])
+        );
+        assert_eq!(
+            split_extra_marker("tomli; python_version < \"3.11\" and extra == \"test\""),
+            (
+                "tomli; python_version < \"3.11\"".to_string(),
+                vec!["test".to_string()]
+            )
+        );
+        assert_eq!(
+            split_extra_marker("pytest; extra == \"a\" or extra == \"b\""),
+            ("pytest".to_string(), vec!["a".to_string(), "b".to_string()])
+        );
+        assert_eq!(
+            split_extra_marker("pytest; (extra == \"a\" or extra == \"b\") and os_name == \"nt\""),
+            (
+                "pytest; os_name == \"nt\"".to_string(),
+                vec!["a".to_string(), "b".to_string()]
+            )
+        );
+        // A marker not made of extras alone is kept as it is.
+        assert_eq!(
+            split_extra_marker("pytest; extra == \"test\" or python_version < \"3.8\""),
+            (
+                "pytest; extra == \"test\" or python_version < \"3.8\"".to_string(),
+                Vec::new()
+            )
+        );
+    }
+}
@@ -1,16 +1,20 @@
-use pyo3::exceptions::PyOSError;
-use pyo3::PyResult;
-use std::{fs, path::Path};
+use crate::error::{Error, Result};
+use std::{fs, io, path::Path};
 
-pub fn read_file(path: &Path) -> PyResult<String> {
-    if let Ok(bytes) = fs::read(path) {
-        let contents = String::from_utf8(bytes);
-        if contents.is_ok() {
-            return Ok(contents.unwrap());
-        }
+pub fn read_file(path: &Path) -> Result<String> {
+    let contents = fs::read(path).and_then(|bytes| {
+        String::from_utf8(bytes).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+    });
+    contents.map_err(|error| Error::Io {
+        message: format!("File {:#?} does not exist or is corrupt.", path.to_str()),
+        source: Some(error),
+    })
+}
+
+/// Returns the directory holding a file, which the files it refers to are relative to.
+pub fn directory_of(path: &Path) -> &Path {
+    match path.parent() {
+        Some(parent) if parent != Path::new("") => parent,
+        _ => Path::new("."),
     }
-    Err(PyOSError::new_err(format!(
-        "File {:#?} does not exist or is corrupt.",
-        path.to_str()
-    )))
 }
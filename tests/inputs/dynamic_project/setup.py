@@ -0,0 +1,9 @@
+from setuptools import setup
+
+from spam import __version__
+
+setup(
+    name="spam",
+    version=__version__,
+    install_requires=["requests>=2.31"],
+)
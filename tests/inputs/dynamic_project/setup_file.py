@@ -0,0 +1,10 @@
+from pathlib import Path
+
+from setuptools import setup
+
+here = Path(__file__).parent
+
+setup(
+    name="spam",
+    version=(here / "VERSION").read_text().strip(),
+)
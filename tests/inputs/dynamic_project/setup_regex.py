@@ -0,0 +1,10 @@
+import re
+
+from setuptools import setup
+
+setup(
+    name="spam",
+    version=re.search(
+        r"__version__ = \"([^\"]+)\"", open("src/spam/_version.py").read()
+    ).group(1),
+)
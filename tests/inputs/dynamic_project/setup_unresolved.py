@@ -0,0 +1,9 @@
+import os
+
+from setuptools import setup
+
+setup(
+    name="spam",
+    version=open(os.path.join(os.path.dirname(__file__), "MISSING")).read().strip(),
+    install_requires=["requests>=2.31"],
+)
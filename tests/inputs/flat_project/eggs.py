@@ -0,0 +1 @@
+EGGS = 1
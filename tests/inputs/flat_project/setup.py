@@ -0,0 +1 @@
+from setuptools import setup
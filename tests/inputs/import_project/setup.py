@@ -0,0 +1,10 @@
+from setuptools import setup, find_packages
+
+from spam.meta import NAME, VERSION, DEPENDENCIES
+
+setup(
+    name=NAME,
+    version=VERSION,
+    packages=find_packages(),
+    install_requires=DEPENDENCIES,
+)
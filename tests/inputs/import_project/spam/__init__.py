@@ -0,0 +1 @@
+from .meta import VERSION as __version__
@@ -0,0 +1,2 @@
+__version__ = "2.0.1"
+BASE_DEPENDENCIES = ["requests>=2.31"]
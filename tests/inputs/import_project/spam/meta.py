@@ -0,0 +1,4 @@
+from ._about import __version__ as VERSION, BASE_DEPENDENCIES
+
+NAME = "spam"
+DEPENDENCIES = BASE_DEPENDENCIES + ["click>=8"]
@@ -0,0 +1,13 @@
+import os
+import sys
+from typing import Optional
+
+import requests
+import yaml
+
+from . import cli
+from .utils import load
+
+
+def fetch(url: Optional[str] = None):
+    return yaml.safe_load(requests.get(url or os.environ["SPAM_URL"]).text)
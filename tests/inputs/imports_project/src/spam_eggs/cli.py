@@ -0,0 +1,15 @@
+import click
+
+from spam_eggs.utils import load
+
+try:
+    import ujson as json
+except ImportError:
+    import json
+
+
+@click.command()
+def main():
+    import PIL.Image
+
+    print(json.dumps(load()))
@@ -0,0 +1,7 @@
+from __future__ import annotations
+
+import pathlib
+
+
+def load() -> dict:
+    return {"path": str(pathlib.Path.cwd())}
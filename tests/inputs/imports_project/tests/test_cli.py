@@ -0,0 +1,8 @@
+import pytest
+
+from spam_eggs import fetch
+
+
+def test_fetch():
+    with pytest.raises(KeyError):
+        fetch()
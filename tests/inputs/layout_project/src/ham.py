@@ -0,0 +1 @@
+HAM = "ham"
@@ -0,0 +1 @@
+"""Spam."""
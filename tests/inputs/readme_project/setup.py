@@ -0,0 +1,16 @@
+import os
+from pathlib import Path
+
+from setuptools import setup
+
+here = Path(__file__).parent
+long_description = (here / "README.rst").read_text(encoding="utf-8")
+
+setup(
+    name="spam",
+    version="0.3.0",
+    long_description=long_description,
+    install_requires=open(os.path.join(os.path.dirname(__file__), "requirements.txt"))
+    .read()
+    .splitlines(),
+)
@@ -0,0 +1,11 @@
+import io
+
+from setuptools import setup
+
+setup(
+    name="spam",
+    version="0.3.0",
+    long_description=io.open("README.rst", encoding="utf-8").read(),
+    long_description_content_type="text/x-rst",
+    install_requires=[line.strip() for line in open("requirements.txt") if line.strip()],
+)
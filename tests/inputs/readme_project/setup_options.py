@@ -0,0 +1,7 @@
+from setuptools import setup
+
+setup(
+    name="spam",
+    version="0.3.0",
+    install_requires=open("requirements-dev.txt").read().splitlines(),
+)
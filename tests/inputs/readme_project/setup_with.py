@@ -0,0 +1,19 @@
+import os
+
+from setuptools import setup
+
+here = os.path.abspath(os.path.dirname(__file__))
+
+with open(os.path.join(here, "README.rst"), encoding="utf-8") as f:
+    long_description = f.read()
+
+with open(os.path.join(here, "requirements.txt")) as f:
+    requires = f.read().splitlines()
+
+setup(
+    name="spam",
+    version="0.3.0",
+    long_description=long_description,
+    long_description_content_type="text/x-rst",
+    install_requires=requires,
+)
@@ -0,0 +1,27 @@
+from setuptools import setup
+
+NAME = "spam-eggs"
+VERSION_INFO = (1, 4, 2)
+BASE_REQUIRES = ["requests>=2.31", "click"]
+EXTRAS = {
+    "test": ["pytest"],
+    "docs": ["sphinx"],
+}
+
+setup(
+    name=NAME,
+    version="%d.%d.%d" % VERSION_INFO,
+    description="{} does {}".format(NAME, "spam"),
+    install_requires=BASE_REQUIRES + ["pyyaml>=%s" % "6.0"],
+    extra_requires=dict(
+        EXTRAS,
+        all=[req for reqs in EXTRAS.values() for req in reqs],
+    ),
+    project_urls={
+        **{"Homepage": "https://example.com"},
+        "Source": "/".join(["https://github.com/me", NAME]),
+    },
+    entry_points=dict(
+        console_scripts=[f"{name} = spam_eggs.cli:{name.replace('-', '_')}" for name in ("spam", "eggs-cli")],
+    ),
+)
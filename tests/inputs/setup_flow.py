@@ -0,0 +1,34 @@
+from setuptools import setup
+
+NAME, VERSION = "spam-eggs", "1.0.0"
+MAJOR, *_ = VERSION.split(".")
+DESCRIPTION = "Lovely Spam!"
+
+REQUIRES = ["requests>=2.31"]
+REQUIRES += ["click"]
+REQUIRES.append("rich")
+REQUIRES.extend(["pyyaml", "tomli; python_version < '3.11'"])
+
+EXTRAS = {}
+EXTRAS["test"] = ["pytest"]
+EXTRAS.update(docs=["sphinx"])
+EXTRAS["test"] += ["hypothesis"]
+
+
+class Options:
+    pass
+
+
+options = Options()
+options.name = "not-spam"
+
+setup(
+    name=NAME,
+    version=VERSION,
+    description=f"{DESCRIPTION} (v{MAJOR})",
+    install_requires=REQUIRES,
+    extra_requires=EXTRAS,
+)
+
+DESCRIPTION = "Wonderful Spam!"
+REQUIRES.append("numpy")
@@ -0,0 +1,25 @@
+import sys
+
+try:
+    from setuptools import setup
+except ImportError:
+    from distutils.core import setup
+
+NAME = "spam-eggs"
+COMMON = {"name": NAME, "python_requires": ">=3.8"}
+
+
+def main():
+    if sys.version_info < (3, 8):
+        raise SystemExit("Python 3.8 or later is required.")
+    requires = ["requests>=2.31"]
+    config = dict(COMMON, version="1.0.0")
+    setup(
+        **config,
+        install_requires=requires,
+        **{"entry_points": {"console_scripts": ["spam = spam_eggs:main"]}},
+    )
+
+
+if __name__ == "__main__":
+    main()
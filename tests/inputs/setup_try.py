@@ -0,0 +1,16 @@
+from setuptools import setup
+
+with open("README.md") as f:
+    DESCRIPTION = "Lovely Spam! Wonderful Spam!"
+
+try:
+    from Cython.Build import cythonize
+except ImportError:
+    setup(name="spam-eggs", version="1.0.0", description=DESCRIPTION)
+else:
+    setup(
+        name="spam-eggs",
+        version="1.0.0",
+        description=DESCRIPTION,
+        install_requires=["cython"],
+    )
@@ -1,5 +1,11 @@
-use babelone::{generators::*, parsers::*, specs::*};
-use std::{env, path::Path};
+use babelone::{generators::*, graph::*, imports::*, layout::*, parsers::*, specs::*};
+use std::{collections::BTreeMap, env, path::Path};
+
+/// Outputs are regenerated on every run.
+const OVERWRITE: WriteOptions = WriteOptions {
+    force: true,
+    backup: false,
+};
 
 #[test]
 fn setup_to_requirements() {
@@ -11,7 +17,7 @@ fn setup_to_requirements() {
     );
     let setup = SetupParser::from_file(&Path::new(&in_path));
     let requirements = Requirements::from_setup(setup.unwrap());
-    let result = RequirementsGenerator::make_file(&Path::new(&out_path), &requirements);
+    let result = RequirementsGenerator::make_file(&Path::new(&out_path), &requirements, &OVERWRITE);
     assert!(result.is_ok());
 }
 
@@ -25,7 +31,7 @@ fn pyproject_to_requirements() {
     );
     let pyproject = PyProjectParser::from_file(&Path::new(&in_path));
     let requirements = Requirements::from_pyproject(pyproject.unwrap());
-    let result = RequirementsGenerator::make_file(&Path::new(&out_path), &requirements);
+    let result = RequirementsGenerator::make_file(&Path::new(&out_path), &requirements, &OVERWRITE);
     assert!(result.is_ok());
 }
 
@@ -42,7 +48,7 @@ fn requirements_to_setup() {
     );
     let requirements = RequirementsParser::from_file(&Path::new(&in_path));
     let setup = Setup::from_requirements(requirements.unwrap());
-    let result = SetupGenerator::make_file(&Path::new(&out_path), &setup);
+    let result = SetupGenerator::make_file(&Path::new(&out_path), &setup, &OVERWRITE);
     assert!(result.is_ok());
 }
 
@@ -56,7 +62,7 @@ fn pyproject_to_setup() {
     );
     let pyproject = PyProjectParser::from_file(&Path::new(&in_path));
     let setup = Setup::from_pyproject(pyproject.unwrap());
-    let result = SetupGenerator::make_file(&Path::new(&out_path), &setup);
+    let result = SetupGenerator::make_file(&Path::new(&out_path), &setup, &OVERWRITE);
     assert!(result.is_ok());
 }
 
@@ -73,7 +79,7 @@ fn requirements_to_pyproject() {
     );
     let requirements = RequirementsParser::from_file(&Path::new(&in_path));
     let pyproject = PyProject::from_requirements(requirements.unwrap());
-    let result = PyProjectGenerator::make_file(&Path::new(&out_path), &pyproject);
+    let result = PyProjectGenerator::make_file(&Path::new(&out_path), &pyproject, &OVERWRITE);
     assert!(result.is_ok());
 }
 
@@ -87,6 +93,748 @@ fn setup_to_pyproject() {
     );
     let setup = SetupParser::from_file(&Path::new(&in_path));
     let pyproject = PyProject::from_setup(setup.unwrap());
-    let result = PyProjectGenerator::make_file(&Path::new(&out_path), &pyproject);
+    let result = PyProjectGenerator::make_file(&Path::new(&out_path), &pyproject, &OVERWRITE);
+    assert!(result.is_ok());
+}
+
+#[test]
+fn environment_to_requirements() {
+    let curr_dir = env::current_dir().unwrap();
+    let in_path = format!("{}/tests/inputs/environment.yml", curr_dir.to_str().unwrap());
+    let out_path = format!(
+        "{}/tests/outputs/requirements__environment_to_requirements.txt",
+        curr_dir.to_str().unwrap()
+    );
+    let environment = CondaEnvironmentParser::from_file(&Path::new(&in_path));
+    let requirements = Requirements::from_conda_environment(environment.unwrap());
+    let result = RequirementsGenerator::make_file(&Path::new(&out_path), &requirements, &OVERWRITE);
+    assert!(result.is_ok());
+    assert_eq!(
+        std::fs::read_to_string(&out_path).unwrap(),
+        "numpy==1.26.*\npandas>=2.0,<3\nscipy\nrequests>=2\nflask\npydantic==2.6.2\n"
+    );
+}
+
+#[test]
+fn pyproject_to_environment() {
+    let curr_dir = env::current_dir().unwrap();
+    let in_path = format!("{}/tests/inputs/pyproject.toml", curr_dir.to_str().unwrap());
+    let out_path = format!(
+        "{}/tests/outputs/environment__pyproject_to_environment.yml",
+        curr_dir.to_str().unwrap()
+    );
+    let pyproject = PyProjectParser::from_file(&Path::new(&in_path));
+    let environment = CondaEnvironment::from_pyproject(pyproject.unwrap());
+    let result =
+        CondaEnvironmentGenerator::make_file(&Path::new(&out_path), &environment, &OVERWRITE);
+    assert!(result.is_ok());
+}
+
+#[test]
+fn uv_lock_to_requirements() {
+    let curr_dir = env::current_dir().unwrap();
+    let in_path = format!("{}/tests/inputs/uv.lock", curr_dir.to_str().unwrap());
+    let out_path = format!(
+        "{}/tests/outputs/requirements__uv_lock_to_requirements.txt",
+        curr_dir.to_str().unwrap()
+    );
+    let lockfile = UvLockParser::from_file(&Path::new(&in_path));
+    let requirements = Requirements::from_lockfile(lockfile.unwrap());
+    let result = RequirementsGenerator::make_file(&Path::new(&out_path), &requirements, &OVERWRITE);
+    assert!(result.is_ok());
+}
+
+#[test]
+fn pylock_to_requirements() {
+    let curr_dir = env::current_dir().unwrap();
+    let in_path = format!("{}/tests/inputs/pylock.toml", curr_dir.to_str().unwrap());
+    let out_path = format!(
+        "{}/tests/outputs/requirements__pylock_to_requirements.txt",
+        curr_dir.to_str().unwrap()
+    );
+    let lockfile = PyLockParser::from_file(&Path::new(&in_path));
+    let requirements = Requirements::from_lockfile(lockfile.unwrap());
+    let result = RequirementsGenerator::make_file(&Path::new(&out_path), &requirements, &OVERWRITE);
+    assert!(result.is_ok());
+}
+
+#[test]
+fn compiled_requirements_to_requirements() {
+    let curr_dir = env::current_dir().unwrap();
+    let in_path = format!(
+        "{}/tests/inputs/requirements_compiled.txt",
+        curr_dir.to_str().unwrap()
+    );
+    let out_path = format!(
+        "{}/tests/outputs/requirements__compiled_to_requirements.txt",
+        curr_dir.to_str().unwrap()
+    );
+    let requirements = RequirementsParser::from_file(&Path::new(&in_path)).unwrap();
+    let result = RequirementsGenerator::make_file(&Path::new(&out_path), &requirements, &OVERWRITE);
+    assert!(result.is_ok());
+    let regenerated = RequirementsParser::from_file(&Path::new(&out_path)).unwrap();
+    assert_eq!(regenerated.requires, requirements.requires);
+    assert_eq!(regenerated.hashes, requirements.hashes);
+    assert_eq!(regenerated.via, requirements.via);
+}
+
+#[test]
+fn metadata_to_pyproject() {
+    let curr_dir = env::current_dir().unwrap();
+    let in_path = format!("{}/tests/inputs/PKG-INFO", curr_dir.to_str().unwrap());
+    let out_path = format!(
+        "{}/tests/outputs/pyproject__metadata_to_pyproject.toml",
+        curr_dir.to_str().unwrap()
+    );
+    let metadata = MetadataParser::from_file(&Path::new(&in_path));
+    let pyproject = PyProject::from_metadata(metadata.unwrap());
+    let result = PyProjectGenerator::make_file(&Path::new(&out_path), &pyproject, &OVERWRITE);
+    assert!(result.is_ok());
+}
+
+#[test]
+fn pyproject_to_metadata() {
+    let curr_dir = env::current_dir().unwrap();
+    let in_path = format!("{}/tests/inputs/pyproject.toml", curr_dir.to_str().unwrap());
+    let out_path = format!(
+        "{}/tests/outputs/metadata__pyproject_to_metadata",
+        curr_dir.to_str().unwrap()
+    );
+    let pyproject = PyProjectParser::from_file(&Path::new(&in_path));
+    let metadata = Metadata::from_pyproject(pyproject.unwrap());
+    let result = MetadataGenerator::make_file(&Path::new(&out_path), &metadata, &OVERWRITE);
+    assert!(result.is_ok());
+}
+
+#[test]
+fn wheel_to_pyproject() {
+    let curr_dir = env::current_dir().unwrap();
+    let in_path = format!(
+        "{}/tests/inputs/spam_eggs-2020.0.0-py3-none-any.whl",
+        curr_dir.to_str().unwrap()
+    );
+    let out_path = format!(
+        "{}/tests/outputs/pyproject__wheel_to_pyproject.toml",
+        curr_dir.to_str().unwrap()
+    );
+    let metadata = DistributionParser::from_file(&Path::new(&in_path));
+    let pyproject = PyProject::from_metadata(metadata.unwrap());
+    let result = PyProjectGenerator::make_file(&Path::new(&out_path), &pyproject, &OVERWRITE);
+    assert!(result.is_ok());
+}
+
+#[test]
+fn sdist_to_requirements() {
+    let curr_dir = env::current_dir().unwrap();
+    let in_path = format!(
+        "{}/tests/inputs/spam_eggs-2020.0.0.tar.gz",
+        curr_dir.to_str().unwrap()
+    );
+    let out_path = format!(
+        "{}/tests/outputs/requirements__sdist_to_requirements.txt",
+        curr_dir.to_str().unwrap()
+    );
+    let metadata = DistributionParser::from_file(&Path::new(&in_path));
+    let requirements = Requirements::from_metadata(metadata.unwrap());
+    let result = RequirementsGenerator::make_file(&Path::new(&out_path), &requirements, &OVERWRITE);
+    assert!(result.is_ok());
+}
+
+#[test]
+fn venv_to_requirements() {
+    let curr_dir = env::current_dir().unwrap();
+    let in_path = format!("{}/tests/inputs/venv", curr_dir.to_str().unwrap());
+    let out_path = format!(
+        "{}/tests/outputs/requirements__venv_to_requirements.txt",
+        curr_dir.to_str().unwrap()
+    );
+    let environment = SitePackagesParser::from_file(&Path::new(&in_path));
+    let requirements = Requirements::from_installed_environment(environment.unwrap());
+    assert!(requirements
+        .requires
+        .contains(&"-e file:///home/me/spam-eggs".to_string()));
+    let result = RequirementsGenerator::make_file(&Path::new(&out_path), &requirements, &OVERWRITE);
+    assert!(result.is_ok());
+}
+
+#[test]
+fn venv_to_pyproject() {
+    let curr_dir = env::current_dir().unwrap();
+    let in_path = format!(
+        "{}/tests/inputs/venv/lib/python3.11/site-packages",
+        curr_dir.to_str().unwrap()
+    );
+    let out_path = format!(
+        "{}/tests/outputs/pyproject__venv_to_pyproject.toml",
+        curr_dir.to_str().unwrap()
+    );
+    let environment = SitePackagesParser::from_file(&Path::new(&in_path));
+    let pyproject = PyProject::from_installed_environment(environment.unwrap());
+    let result = PyProjectGenerator::make_file(&Path::new(&out_path), &pyproject, &OVERWRITE);
+    assert!(result.is_ok());
+}
+
+#[test]
+fn frozen_requirements_to_pyproject() {
+    let curr_dir = env::current_dir().unwrap();
+    let in_path = format!(
+        "{}/tests/inputs/requirements_frozen.txt",
+        curr_dir.to_str().unwrap()
+    );
+    let env_path = format!("{}/tests/inputs/venv", curr_dir.to_str().unwrap());
+    let out_path = format!(
+        "{}/tests/outputs/pyproject__frozen_requirements_to_pyproject.toml",
+        curr_dir.to_str().unwrap()
+    );
+    let requirements = RequirementsParser::from_file(&Path::new(&in_path));
+    let environment = SitePackagesParser::from_file(&Path::new(&env_path));
+    let tree = DependencyTree::from_installed_environment(&environment.unwrap());
+    let requirements = tree.minimize(requirements.unwrap());
+    assert!(!requirements.requires.contains(&"Flask==3.0.2".to_string()));
+    let pyproject = PyProject::from_requirements(requirements);
+    let result = PyProjectGenerator::make_file(&Path::new(&out_path), &pyproject, &OVERWRITE);
     assert!(result.is_ok());
 }
+
+#[test]
+fn pyproject_imports() {
+    let curr_dir = env::current_dir().unwrap();
+    let package_path = format!(
+        "{}/tests/inputs/imports_project",
+        curr_dir.to_str().unwrap()
+    );
+    let in_path = format!("{}/pyproject.toml", package_path);
+    let project = PyProjectParser::from_file(&Path::new(&in_path))
+        .unwrap()
+        .project
+        .unwrap();
+    let optional = project.optional_dependencies.unwrap()["test"].clone();
+    let scanner = ImportScanner::default();
+    let imports = scanner.scan(&Path::new(&package_path)).unwrap();
+    assert_eq!(
+        imports.modules.keys().collect::<Vec<&String>>(),
+        vec!["PIL", "click", "pytest", "requests", "ujson", "yaml"]
+    );
+    let report = scanner.compare(&imports, &project.dependencies.unwrap(), &optional);
+    assert_eq!(
+        report.undeclared.keys().collect::<Vec<&String>>(),
+        vec!["Pillow", "PyYAML", "ujson"]
+    );
+    assert_eq!(report.unused, vec!["numpy".to_string()]);
+}
+
+#[test]
+fn layout_to_setup() {
+    let curr_dir = env::current_dir().unwrap();
+    let in_path = format!("{}/tests/inputs/layout_project", curr_dir.to_str().unwrap());
+    let out_path = format!(
+        "{}/tests/outputs/setup__layout_to_setup.py",
+        curr_dir.to_str().unwrap()
+    );
+    let layout = PackageLayout::discover(&Path::new(&in_path));
+    let setup = Setup {
+        layout: Some(layout.unwrap()),
+        ..Default::default()
+    };
+    let result = SetupGenerator::make_file(&Path::new(&out_path), &setup, &OVERWRITE);
+    assert!(result.is_ok());
+}
+
+#[test]
+fn layout_to_pyproject() {
+    let curr_dir = env::current_dir().unwrap();
+    let in_path = format!("{}/tests/inputs/layout_project", curr_dir.to_str().unwrap());
+    let out_path = format!(
+        "{}/tests/outputs/pyproject__layout_to_pyproject.toml",
+        curr_dir.to_str().unwrap()
+    );
+    let layout = PackageLayout::discover(&Path::new(&in_path)).unwrap();
+    let mut pyproject = PyProject::default();
+    pyproject.set_layout(&layout);
+    assert_eq!(
+        pyproject.build_system.as_ref().unwrap().build_backend,
+        Some("setuptools.build_meta".to_string())
+    );
+    let result = PyProjectGenerator::make_file(&Path::new(&out_path), &pyproject, &OVERWRITE);
+    assert!(result.is_ok());
+
+    let mut pyproject = PyProject::from_requirements(Requirements::default());
+    pyproject.build_system = Some(BuildSystem {
+        requires: Some(vec!["hatchling".to_string()]),
+        build_backend: Some("hatchling.build".to_string()),
+    });
+    pyproject.set_layout(&layout);
+    let wheel = &pyproject.tool.unwrap()["hatch"]["build"]["targets"]["wheel"];
+    assert_eq!(wheel["only-include"].as_array().unwrap().len(), 3);
+}
+
+#[test]
+fn dynamic_setup_to_pyproject() {
+    let curr_dir = env::current_dir().unwrap();
+    let in_path = format!(
+        "{}/tests/inputs/dynamic_project/setup_unresolved.py",
+        curr_dir.to_str().unwrap()
+    );
+    let out_path = format!(
+        "{}/tests/outputs/pyproject__dynamic_setup_to_pyproject.toml",
+        curr_dir.to_str().unwrap()
+    );
+    let setup = SetupParser::from_file(&Path::new(&in_path));
+    let pyproject = PyProject::from_setup(setup.unwrap());
+    assert_eq!(
+        pyproject.project.as_ref().unwrap().dynamic,
+        Some(vec!["version".to_string()])
+    );
+    let result = PyProjectGenerator::make_file(&Path::new(&out_path), &pyproject, &OVERWRITE);
+    assert!(result.is_ok());
+}
+
+#[test]
+fn dynamic_pyproject_to_setup() {
+    let curr_dir = env::current_dir().unwrap();
+    let in_path = format!(
+        "{}/tests/inputs/dynamic_project/pyproject_unresolved.toml",
+        curr_dir.to_str().unwrap()
+    );
+    let out_path = format!(
+        "{}/tests/outputs/setup__dynamic_pyproject_to_setup.py",
+        curr_dir.to_str().unwrap()
+    );
+    let pyproject = PyProjectParser::from_file(&Path::new(&in_path));
+    let setup = Setup::from_pyproject(pyproject.unwrap());
+    assert!(setup.dynamic_version.is_some());
+    let result = SetupGenerator::make_file(&Path::new(&out_path), &setup, &OVERWRITE);
+    assert!(result.is_ok());
+}
+
+#[test]
+fn file_reads_setup_to_pyproject() {
+    let curr_dir = env::current_dir().unwrap();
+    let in_path = format!(
+        "{}/tests/inputs/readme_project/setup.py",
+        curr_dir.to_str().unwrap()
+    );
+    let out_path = format!(
+        "{}/tests/outputs/pyproject__file_reads_setup_to_pyproject.toml",
+        curr_dir.to_str().unwrap()
+    );
+    let setup = SetupParser::from_file(&Path::new(&in_path));
+    let pyproject = PyProject::from_setup(setup.unwrap());
+    assert_eq!(
+        pyproject.project.as_ref().unwrap().dynamic,
+        Some(vec!["dependencies".to_string()])
+    );
+    assert_eq!(
+        pyproject.requirements_file(),
+        Some("requirements.txt".to_string())
+    );
+    let result = PyProjectGenerator::make_file(&Path::new(&out_path), &pyproject, &OVERWRITE);
+    assert!(result.is_ok());
+
+    let out_path = format!(
+        "{}/tests/outputs/setup__file_reads_pyproject_to_setup.py",
+        curr_dir.to_str().unwrap()
+    );
+    let setup = Setup::from_pyproject(pyproject);
+    let result = SetupGenerator::make_file(&Path::new(&out_path), &setup, &OVERWRITE);
+    assert!(result.is_ok());
+}
+
+#[test]
+fn file_reads_setup_to_metadata() {
+    let curr_dir = env::current_dir().unwrap();
+    let in_path = format!(
+        "{}/tests/inputs/readme_project/setup.py",
+        curr_dir.to_str().unwrap()
+    );
+    let translation = babelone::translate_file(Path::new(&in_path), None, "PKG-INFO", None, 0);
+    let metadata = MetadataParser::from_str(&translation.unwrap().contents).unwrap();
+    assert!(metadata
+        .description
+        .is_some_and(|description| description.contains("Lovely Spam!")));
+}
+
+#[cfg(feature = "cli")]
+#[test]
+fn cli_translate() {
+    let curr_dir = env::current_dir().unwrap();
+    let in_path = format!(
+        "{}/tests/inputs/import_project/setup.py",
+        curr_dir.to_str().unwrap()
+    );
+    let out_dir = format!(
+        "{}/tests/outputs/cli__translate",
+        curr_dir.to_str().unwrap()
+    );
+    std::fs::create_dir_all(&out_dir).unwrap();
+    let out_path = format!("{}/pyproject.toml", out_dir);
+    let _ = std::fs::remove_file(&out_path);
+    let status = std::process::Command::new(env!("CARGO_BIN_EXE_babelone"))
+        .args(["translate", &in_path, &out_path])
+        .status()
+        .unwrap();
+    assert!(status.success());
+    let pyproject = PyProjectParser::from_file(&Path::new(&out_path)).unwrap();
+    assert_eq!(
+        pyproject.project.unwrap().version,
+        Some("2.0.1".to_string())
+    );
+
+    // Lockfiles are read-only.
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_babelone"))
+        .args(["translate", &out_path, &format!("{}/uv.lock", out_dir)])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("error: "));
+}
+
+#[test]
+fn translate_in_memory() {
+    let setup = r#"
+from setuptools import setup
+
+setup(
+    name="spam",
+    version="1.0.0",
+    install_requires=["requests>=2.31", "click"],
+    entry_points={"console_scripts": ["spam = spam.cli:main", "eggs"]},
+)
+"#;
+    let pyproject = babelone::translate_str(setup, "setup.py", "pyproject.toml").unwrap();
+    assert!(pyproject.warnings.is_empty());
+    let pyproject = PyProjectParser::from_str(&pyproject.contents).unwrap();
+    let project = pyproject.project.unwrap();
+    assert_eq!(project.name, Some("spam".to_string()));
+    assert_eq!(
+        project.dependencies,
+        Some(vec!["requests>=2.31".to_string(), "click".to_string()])
+    );
+    // Entry points without a `=` are skipped rather than failing the translation.
+    assert_eq!(
+        project.project_scripts,
+        Some(BTreeMap::from([(
+            "spam".to_string(),
+            "spam.cli:main".to_string()
+        )]))
+    );
+
+    let requirements = babelone::translate_str(setup, "setup.py", "requirements.txt")
+        .unwrap()
+        .contents;
+    assert_eq!(requirements, "requests>=2.31\nclick\n");
+
+    // Wheels are read from a path, and lockfiles are read-only.
+    assert!(babelone::translate_str("", "spam.whl", "pyproject.toml").is_err());
+    assert!(babelone::translate_str(&requirements, "requirements.txt", "uv.lock").is_err());
+
+    // Conda packages are carried over by name and version, but not the interpreter.
+    let environment = "dependencies:\n  - python=3.11\n  - numpy=1.26\n  - pip:\n    - flask\n";
+    let requirements =
+        babelone::translate_str(environment, "environment.yml", "requirements.txt").unwrap();
+    assert_eq!(requirements.contents, "numpy==1.26.*\nflask\n");
+    assert_eq!(
+        requirements.warnings,
+        vec![
+            "skipping untranslatable entry python=3.11 (the Python interpreter is not a pip requirement)"
+                .to_string()
+        ]
+    );
+}
+
+#[cfg(feature = "cli")]
+#[test]
+fn cli_streaming() {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_babelone"))
+        .args(["translate", "-", "-", "--from", "requirements.txt"])
+        .args(["--to", "pyproject.toml"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"requests>=2.31\nclick\n")
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let pyproject = PyProjectParser::from_str(&String::from_utf8(output.stdout).unwrap());
+    assert_eq!(
+        pyproject.unwrap().project.unwrap().dependencies,
+        Some(vec!["requests>=2.31".to_string(), "click".to_string()])
+    );
+
+    // There is no file name to detect the format of stdout from.
+    let output = Command::new(env!("CARGO_BIN_EXE_babelone"))
+        .args(["create", "-"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+
+    // Only translate reads from stdin.
+    let curr_dir = env::current_dir().unwrap();
+    let in_path = format!(
+        "{}/tests/inputs/requirements.txt",
+        curr_dir.to_str().unwrap()
+    );
+    for command in ["check", "sync"] {
+        let output = Command::new(env!("CARGO_BIN_EXE_babelone"))
+            .args([command, "-", &in_path])
+            .output()
+            .unwrap();
+        assert!(!output.status.success());
+        assert!(String::from_utf8(output.stderr)
+            .unwrap()
+            .contains("Path \"-\" does not exist."));
+    }
+}
+
+#[cfg(feature = "cli")]
+#[test]
+fn cli_overwrite() {
+    use std::process::Command;
+
+    let curr_dir = env::current_dir().unwrap();
+    let in_path = format!(
+        "{}/tests/inputs/requirements.txt",
+        curr_dir.to_str().unwrap()
+    );
+    let out_dir = format!(
+        "{}/tests/outputs/cli__overwrite",
+        curr_dir.to_str().unwrap()
+    );
+    std::fs::create_dir_all(&out_dir).unwrap();
+    let out_path = format!("{}/pyproject.toml", out_dir);
+    let bak_path = format!("{}.bak", out_path);
+    let _ = std::fs::remove_file(&bak_path);
+    std::fs::write(&out_path, "# hand-written\n").unwrap();
+
+    // An existing OUTPUT is refused, and left as it was.
+    let output = Command::new(env!("CARGO_BIN_EXE_babelone"))
+        .args(["translate", &in_path, &out_path])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(
+        std::fs::read_to_string(&out_path).unwrap(),
+        "# hand-written\n"
+    );
+
+    // A dry run prints to stdout without touching OUTPUT.
+    let output = Command::new(env!("CARGO_BIN_EXE_babelone"))
+        .args(["translate", &in_path, &out_path, "--dry-run"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(PyProjectParser::from_str(&String::from_utf8(output.stdout).unwrap()).is_ok());
+    assert_eq!(
+        std::fs::read_to_string(&out_path).unwrap(),
+        "# hand-written\n"
+    );
+
+    // A backup is only taken of a file overwritten with --force.
+    let output = Command::new(env!("CARGO_BIN_EXE_babelone"))
+        .args(["translate", &in_path, &out_path, "--backup"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(!Path::new(&bak_path).exists());
+
+    let status = Command::new(env!("CARGO_BIN_EXE_babelone"))
+        .args(["translate", &in_path, &out_path, "--force", "--backup"])
+        .status()
+        .unwrap();
+    assert!(status.success());
+    assert!(PyProjectParser::from_file(&Path::new(&out_path)).is_ok());
+    assert_eq!(
+        std::fs::read_to_string(&bak_path).unwrap(),
+        "# hand-written\n"
+    );
+}
+
+#[cfg(feature = "cli")]
+#[test]
+fn cli_diff() {
+    use std::process::Command;
+
+    let curr_dir = env::current_dir().unwrap();
+    let in_path = format!(
+        "{}/tests/inputs/requirements.txt",
+        curr_dir.to_str().unwrap()
+    );
+    let out_dir = format!("{}/tests/outputs/cli__diff", curr_dir.to_str().unwrap());
+    std::fs::create_dir_all(&out_dir).unwrap();
+    let out_path = format!("{}/pyproject.toml", out_dir);
+    std::fs::write(&out_path, "# hand-written\n").unwrap();
+
+    // Changes are shown, and signalled by the exit code, without writing OUTPUT.
+    let output = Command::new(env!("CARGO_BIN_EXE_babelone"))
+        .args(["translate", &in_path, &out_path, "--diff"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let diff = String::from_utf8(output.stdout).unwrap();
+    assert!(diff.starts_with(&format!(
+        "--- a/{}\n+++ b/{}\n@@ -1 +1,",
+        out_path, out_path
+    )));
+    assert!(diff.contains("\n-# hand-written\n"));
+    assert_eq!(
+        std::fs::read_to_string(&out_path).unwrap(),
+        "# hand-written\n"
+    );
+
+    let status = Command::new(env!("CARGO_BIN_EXE_babelone"))
+        .args(["translate", &in_path, &out_path, "--force"])
+        .status()
+        .unwrap();
+    assert!(status.success());
+    let output = Command::new(env!("CARGO_BIN_EXE_babelone"))
+        .args(["translate", &in_path, &out_path, "--diff"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+}
+
+#[cfg(feature = "cli")]
+#[test]
+fn cli_check() {
+    use std::process::Command;
+
+    let curr_dir = env::current_dir().unwrap();
+    let in_path = format!("{}/tests/inputs/pyproject.toml", curr_dir.to_str().unwrap());
+    let out_dir = format!("{}/tests/outputs/cli__check", curr_dir.to_str().unwrap());
+    std::fs::create_dir_all(&out_dir).unwrap();
+    let out_path = format!("{}/requirements.txt", out_dir);
+    let status = Command::new(env!("CARGO_BIN_EXE_babelone"))
+        .args(["translate", &in_path, &out_path, "--force"])
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let output = Command::new(env!("CARGO_BIN_EXE_babelone"))
+        .args(["check", &in_path, &out_path])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+
+    // Requirements are compared once normalized, not as written.
+    let requirements = std::fs::read_to_string(&out_path).unwrap();
+    let mut lines = requirements.lines().collect::<Vec<&str>>();
+    let first = lines.remove(0).to_uppercase();
+    let drifted = format!("{}\n{}\nnumpy\n", first, lines.join("\n"));
+    std::fs::write(&out_path, drifted).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_babelone"))
+        .args(["check", &in_path, &out_path])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let report = String::from_utf8(output.stdout).unwrap();
+    assert!(report.ends_with("\n- numpy\n"));
+}
+
+#[cfg(feature = "cli")]
+#[test]
+fn cli_semantic_diff() {
+    use std::process::Command;
+
+    let curr_dir = env::current_dir().unwrap();
+    let in_path = format!("{}/tests/inputs/pyproject.toml", curr_dir.to_str().unwrap());
+    let out_dir = format!(
+        "{}/tests/outputs/cli__semantic_diff",
+        curr_dir.to_str().unwrap()
+    );
+    std::fs::create_dir_all(&out_dir).unwrap();
+    let out_path = format!("{}/pyproject.toml", out_dir);
+    let pyproject = std::fs::read_to_string(&in_path)
+        .unwrap()
+        .replace("version = \"2020.0.0\"", "version = \"2021.0.0\"")
+        .replace("\"httpx\",", "\"httpx>=0.27\", \"rich\",")
+        .replace("gui = [\"PyQt5\"]\n", "")
+        .replace("spam:main_cli", "spam.cli:main");
+    std::fs::write(&out_path, pyproject).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_babelone"))
+        .args(["diff", &in_path, &in_path])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+
+    let output = Command::new(env!("CARGO_BIN_EXE_babelone"))
+        .args(["diff", &in_path, &out_path])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "Metadata:\n~ version: 2020.0.0 -> 2021.0.0\n\
+         Dependencies:\n+ rich\n[gui]\n- pyqt5\n\
+         Specifiers:\n~ httpx: (any) -> >=0.27\n\
+         Extras:\n- gui\n\
+         Scripts:\n~ spam-cli = spam:main_cli -> spam.cli:main\n"
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_babelone"))
+        .args(["diff", &in_path, &out_path, "--json"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        report["metadata"]["version"],
+        serde_json::json!({"from": "2020.0.0", "to": "2021.0.0"})
+    );
+    assert_eq!(
+        report["specifiers"],
+        serde_json::json!([{"extra": null, "name": "httpx", "marker": null, "from": "", "to": ">=0.27"}])
+    );
+    assert_eq!(
+        report["dependencies"][1],
+        serde_json::json!({"extra": "gui", "name": "pyqt5", "removed": "pyqt5"})
+    );
+    assert_eq!(report["extras"], serde_json::json!([{"removed": "gui"}]));
+}
+
+#[cfg(feature = "cli")]
+#[test]
+fn cli_sync() {
+    use std::process::Command;
+
+    let curr_dir = env::current_dir().unwrap();
+    let in_path = format!(
+        "{}/tests/inputs/requirements.txt",
+        curr_dir.to_str().unwrap()
+    );
+    let out_dir = format!("{}/tests/outputs/cli__sync", curr_dir.to_str().unwrap());
+    std::fs::create_dir_all(&out_dir).unwrap();
+    let out_path = format!("{}/pyproject.toml", out_dir);
+    let pyproject = "[project]\nname = \"spam\"  # kept\ndependencies = [\"old\"]\n\n[project.optional-dependencies]\ngui = [\"PyQt5\"]\n\n[tool.black]\nline-length = 88\n";
+    std::fs::write(&out_path, pyproject).unwrap();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_babelone"))
+        .args(["sync", &in_path, &out_path])
+        .status()
+        .unwrap();
+    assert!(status.success());
+    let synced = std::fs::read_to_string(&out_path).unwrap();
+    assert!(synced.starts_with("[project]\nname = \"spam\"  # kept\ndependencies = [\n"));
+    assert!(synced.ends_with("]\n\n[tool.black]\nline-length = 88\n"));
+    let expected = RequirementsParser::from_file(&Path::new(&in_path)).unwrap();
+    let project = PyProjectParser::from_str(&synced).unwrap().project.unwrap();
+    assert_eq!(project.dependencies, Some(expected.requires));
+
+    // Extras SOURCE does not declare are removed, so the two are in sync.
+    let output = Command::new(env!("CARGO_BIN_EXE_babelone"))
+        .args(["check", &in_path, &out_path])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+}
@@ -0,0 +1,13 @@
+"""Installation configuration and package metadata for eggs."""
+import re
+
+from setuptools import setup
+
+
+if __name__ == "__main__":
+    setup(
+        name="eggs",
+        version=re.search(r"""(?m)^__version__\s*(?::\s*str\s*)?=\s*['"](?P<version>[^'"]+)['"]""", open("eggs/__init__.py").read()).group("version"),
+        install_requires=["requests>=2.31"],
+        setup_requires=["setuptools>=61.0"],
+    )
\ No newline at end of file
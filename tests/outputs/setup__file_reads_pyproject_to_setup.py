@@ -0,0 +1,12 @@
+"""Installation configuration and package metadata for spam."""
+from setuptools import setup
+
+
+if __name__ == "__main__":
+    setup(
+        name="spam",
+        version="0.3.0",
+        long_description=open("README.rst", encoding="utf-8").read(),
+        long_description_content_type="text/x-rst",
+        install_requires=open("requirements.txt").read().splitlines(),
+    )
\ No newline at end of file
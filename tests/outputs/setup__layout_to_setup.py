@@ -0,0 +1,17 @@
+"""Installation configuration and package metadata."""
+from setuptools import find_namespace_packages, setup
+
+
+if __name__ == "__main__":
+    setup(
+        name="",
+        version="",
+        install_requires=[],
+        setup_requires=[],
+        extras_require={},
+        entry_points={"console_scripts": [], "gui_scripts": []},
+        packages=find_namespace_packages(where="src", include=["acme", "acme.*", "spam", "spam.*"]),
+        package_dir={"": "src"},
+        py_modules=["ham"],
+        package_data={"spam": ["data/schema.json", "py.typed"]},
+    )
\ No newline at end of file
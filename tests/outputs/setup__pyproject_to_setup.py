@@ -4,10 +4,15 @@ from setuptools import setup
 
 if __name__ == "__main__":
     setup(
-        package_name="spam-eggs",
+        name="spam-eggs",
         version="2020.0.0",
+        description="Lovely Spam! Wonderful Spam!",
+        long_description=open("README.rst", encoding="utf-8").read(),
+        long_description_content_type="text/x-rst",
+        python_requires=">=3.8",
+        project_urls={"Bug Tracker": "https://github.com/me/spam/issues", "Changelog": "https://github.com/me/spam/blob/master/CHANGELOG.md", "Documentation": "https://readthedocs.org", "Homepage": "https://example.com", "Repository": "https://github.com/me/spam.git"},
         install_requires=["httpx", "gidgethub[httpx]>4.0.0", "django>2.1; os_name != 'nt'", "django>2.0; os_name == 'nt'"],
         setup_requires=["hatchling"],
-        extra_requires={"cli": ["rich", "click"], "gui": ["PyQt5"]},
+        extras_require={"cli": ["rich", "click"], "gui": ["PyQt5"]},
         entry_points={"console_scripts": ["spam-cli = spam:main_cli"], "gui_scripts": ["spam-gui = spam:main_gui"]},
     )
\ No newline at end of file